@@ -0,0 +1,404 @@
+//! Post-game analysis : every move of a recorded game compared with all other moves available.
+use super::configuration::{Configuration, Movement};
+use super::game::{recorded_move, GameRecord, ReplayError};
+use super::strategy::{AlphaBeta, BlobCount, Evaluator, Score, Search, Strategy};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::cmp::Reverse;
+use std::fmt;
+use std::io::{self, Write};
+
+/// Default evaluation drop above which a move is a blunder.
+pub const BLUNDER_THRESHOLD: Score = 4;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+/// How good a played move was.
+/// Values are for the player who moved : the higher the better.
+pub struct MoveAnalysis {
+    /// Index of the move in the record (passes are not counted).
+    pub ply: usize,
+    /// Player who moved (false for red, true for blue).
+    pub player: bool,
+    /// Played move.
+    pub movement: Movement,
+    /// Value of the played move.
+    pub value: Score,
+    /// Rank of the played move among all legal moves : 1 for the best one, ties sharing
+    /// the best rank.
+    pub rank: usize,
+    /// Number of legal moves.
+    pub moves: usize,
+    /// Best move (the first one found on ties).
+    pub best: Movement,
+    /// Value of the best move.
+    pub best_value: Score,
+    /// Did the move lose more than the blunder threshold ?
+    pub blunder: bool,
+}
+
+impl MoveAnalysis {
+    /// Evaluation lost by playing this move instead of the best one (0 for best moves).
+    pub fn delta(&self) -> Score {
+        self.best_value - self.value
+    }
+}
+
+/// Displayed as one line like "12 blue jump (3,4)->(5,4): rank 3/17, value -2 (best
+/// clone (2,3): +1, delta 3)", the best move being only given when another one was played
+/// and blunders ending with " blunder".
+impl fmt::Display for MoveAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}: rank {}/{}, value {:+}",
+            self.ply,
+            if self.player { "blue" } else { "red" },
+            self.movement,
+            self.rank,
+            self.moves,
+            self.value
+        )?;
+        if self.rank > 1 {
+            write!(
+                f,
+                " (best {}: {:+}, delta {})",
+                self.best,
+                self.best_value,
+                self.delta()
+            )?;
+        }
+        if self.blunder {
+            write!(f, " blunder")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Why the best move of a configuration is the best one (see `Configuration::move_report`).
+/// Values are for the player about to play : the higher the better.
+pub struct MoveReport {
+    /// Best move (the first one found on ties), None if the player must pass.
+    pub best: Option<Movement>,
+    /// Description of the best move, like "clone (2,3)", or "pass".
+    pub description: String,
+    /// Value of the best move.
+    pub value: Score,
+    /// Number of blobs the best move converts.
+    pub captures: i8,
+    /// Blob difference right after the best move.
+    pub differential: i8,
+    /// Static exchange : blob difference once the opponent answered the best move with the
+    /// reply converting the most blobs back.
+    pub exchange: i8,
+    /// Moves expected from both players, starting with the best one.
+    pub principal_variation: Vec<Movement>,
+    /// How much better the best move is than the second best one, None without other moves.
+    pub margin: Option<Score>,
+}
+
+/// Displayed over several lines, like "best move: clone (1,1)" then "value: +7",
+/// "captures: 3", ...
+impl fmt::Display for MoveReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "best move: {}", self.description)?;
+        writeln!(f, "value: {:+}", self.value)?;
+        writeln!(f, "captures: {}", self.captures)?;
+        writeln!(f, "blob difference: {:+}", self.differential)?;
+        writeln!(f, "after exchange: {:+}", self.exchange)?;
+        write!(f, "principal variation:")?;
+        for movement in &self.principal_variation {
+            write!(f, " {}", movement)?;
+        }
+        writeln!(f)?;
+        match self.margin {
+            Some(margin) => write!(f, "margin over second best: {}", margin),
+            None => write!(f, "no other move"),
+        }
+    }
+}
+
+/// Analysis of recorded games by alpha - beta searches.
+/// Each legal move of each position is valued by searching the position it leads to, so that
+/// played moves can be ranked.
+/// As in any `AlphaBeta` search, configurations close to the end of the game are searched
+/// much deeper (see `ENDGAME_EMPTY_CELLS`) whatever the depth.
+pub struct Analyzer<E = BlobCount> {
+    depth: u8,
+    blunder_threshold: Score,
+    evaluator: E,
+}
+
+impl Analyzer {
+    /// Value moves by searching given number of plies, the move itself included (at least
+    /// one).
+    pub fn new(depth: u8) -> Self {
+        Analyzer {
+            depth: depth.max(1),
+            blunder_threshold: BLUNDER_THRESHOLD,
+            evaluator: BlobCount,
+        }
+    }
+}
+
+impl<E: Evaluator> Analyzer<E> {
+    /// Evaluate leaves of the searches with given evaluator instead of the blob count.
+    pub fn evaluator<F: Evaluator>(self, evaluator: F) -> Analyzer<F> {
+        Analyzer {
+            depth: self.depth,
+            blunder_threshold: self.blunder_threshold,
+            evaluator,
+        }
+    }
+
+    /// Flag moves losing more than given evaluation as blunders (`BLUNDER_THRESHOLD` by
+    /// default).
+    pub fn blunder_threshold(self, blunder_threshold: Score) -> Self {
+        Analyzer {
+            blunder_threshold,
+            ..self
+        }
+    }
+
+    /// Analyze all moves of given record.
+    /// Fails if the record contains an illegal move.
+    pub fn analyze(&self, record: &GameRecord) -> Result<Vec<MoveAnalysis>, ReplayError> {
+        let after = record.replay()?;
+        let mut search = AlphaBeta::new(self.depth - 1).evaluator(&self.evaluator);
+        let mut before = Configuration::new_with_player(&record.board, record.start_player);
+        let mut analyses = Vec::with_capacity(record.moves.len());
+        for (ply, (movement, after)) in record.moves.iter().zip(&after).enumerate() {
+            if before.must_pass() {
+                before = before.pass();
+            }
+            let values: Vec<(Movement, Score)> = before
+                .movements()
+                .map(|candidate| {
+                    let child = before.play(&candidate);
+                    (candidate, -search.compute_next_move_scored(&child).1)
+                })
+                .collect();
+            let value = values
+                .iter()
+                .find(|(candidate, _)| candidate == movement)
+                .map(|&(_, value)| value)
+                .expect("replayed moves are legal");
+            let (best, best_value) = values.iter().fold(values[0], |best, &candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+            analyses.push(MoveAnalysis {
+                ply,
+                player: before.current_player,
+                movement: *movement,
+                value,
+                rank: 1 + values.iter().filter(|(_, other)| *other > value).count(),
+                moves: values.len(),
+                best,
+                best_value,
+                blunder: best_value - value > self.blunder_threshold,
+            });
+            before = *after;
+        }
+        Ok(analyses)
+    }
+
+    /// All lines of given configuration, one per legal move, best first (in generation order
+    /// on ties).
+    /// Each move is searched with the full window (`Search`, without the endgame solver) so that
+    /// all values are exact.
+    pub fn lines(&self, config: &Configuration) -> Vec<Analysis> {
+        let mut lines: Vec<Analysis> = config
+            .movements()
+            .map(|movement| {
+                let mut search = Search::new().evaluator(&self.evaluator);
+                let child = config.play(&movement);
+                let (line, value) = search.principal_variation(&child, self.depth - 1);
+                let mut principal_variation = vec![movement];
+                principal_variation.extend(line);
+                Analysis {
+                    movement,
+                    value: -value,
+                    principal_variation,
+                }
+            })
+            .collect();
+        // stable : ties stay in generation order
+        lines.sort_by_key(|line| Reverse(line.value));
+        lines
+    }
+
+    /// Report on the best move of given configuration, found as in `Analyzer::lines`.
+    pub fn report(&self, config: &Configuration) -> MoveReport {
+        let mut lines = self.lines(config).into_iter();
+        let best = match lines.next() {
+            Some(best) => best,
+            None => {
+                let (line, value) = Search::new()
+                    .evaluator(&self.evaluator)
+                    .principal_variation(config, self.depth);
+                return MoveReport {
+                    best: None,
+                    description: "pass".to_owned(),
+                    value,
+                    captures: 0,
+                    differential: -config.value(),
+                    exchange: -config.value(),
+                    principal_variation: line,
+                    margin: None,
+                };
+            }
+        };
+        let movement = best.movement;
+        let after = config.play(&movement);
+        let exchange = after
+            .movements()
+            .map(|reply| -after.play(&reply).value())
+            .min()
+            .unwrap_or_else(|| after.value());
+        MoveReport {
+            best: Some(movement),
+            description: movement.to_string(),
+            value: best.value,
+            captures: config.captures(&movement),
+            differential: after.value(),
+            exchange,
+            margin: lines.next().map(|second| best.value - second.value),
+            principal_variation: best.principal_variation,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A line of play found by a search.
+pub struct Analysis {
+    /// First move of the line.
+    pub movement: Movement,
+    /// Value of the line for the player about to play : the higher the better.
+    pub value: Score,
+    /// Moves expected from both players, starting with `movement`.
+    pub principal_variation: Vec<Movement>,
+}
+
+/// Best line of given configuration, searching given number of plies
+/// (see `Search::principal_variation`).
+/// None if the player about to play has no move.
+pub fn analyze(config: &Configuration, depth: u8) -> Option<Analysis> {
+    let (principal_variation, value) = Search::new().principal_variation(config, depth);
+    Some(Analysis {
+        movement: *principal_variation.first()?,
+        value,
+        principal_variation,
+    })
+}
+
+/// Best `k` lines of given configuration, searching given number of plies : each one starts
+/// with a different move, and the first one is the line of `analyze`.
+/// All moves are searched exactly (see `Analyzer::lines`), fewer lines are returned if there
+/// are fewer legal moves.
+pub fn analyze_multipv(config: &Configuration, depth: u8, k: usize) -> Vec<Analysis> {
+    let mut lines = Analyzer::new(depth).lines(config);
+    lines.truncate(k);
+    lines
+}
+
+/// Write given analyses as CSV, one line per move after a header line.
+/// Columns are ply, player ("red" or "blue"), movement, value, rank, moves, best, best_value,
+/// delta and blunder, moves being written as in records ("x y" for clones and
+/// "from_x from_y to_x to_y" for jumps).
+pub fn write_csv<W: Write>(analyses: &[MoveAnalysis], mut writer: W) -> io::Result<()> {
+    writeln!(
+        writer,
+        "ply,player,movement,value,rank,moves,best,best_value,delta,blunder"
+    )?;
+    for analysis in analyses {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{}",
+            analysis.ply,
+            if analysis.player { "blue" } else { "red" },
+            recorded_move(&analysis.movement),
+            analysis.value,
+            analysis.rank,
+            analysis.moves,
+            recorded_move(&analysis.best),
+            analysis.best_value,
+            analysis.delta(),
+            analysis.blunder
+        )?;
+    }
+    Ok(())
+}
+
+/// Write given analyses as a JSON array of objects with the fields of `MoveAnalysis`, moves
+/// being `{"Duplicate": destination}` or `{"Jump": [source, destination]}` (positions are
+/// `y * 8 + x`).
+#[cfg(feature = "serde")]
+pub fn write_json<W: Write>(analyses: &[MoveAnalysis], writer: W) -> io::Result<()> {
+    serde_json::to_writer_pretty(writer, analyses)?;
+    Ok(())
+}
+
+/// Without the serde feature JSON cannot be written.
+#[cfg(not(feature = "serde"))]
+pub fn write_json<W: Write>(_analyses: &[MoveAnalysis], _writer: W) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "JSON analyses need the serde feature",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::positions::{BoardPosition, Position};
+
+    #[test]
+    fn report_explains_a_capture() {
+        // red clones in (1,1) to convert the three blue blobs around it
+        let board = Board::from_fen("r1b5/2b5/1b6/8/8/8/8/6rb r").unwrap();
+        let config = Configuration::new(&board);
+        let report = config.move_report(3);
+        let best = Movement::Duplicate(Position::from_2d(1, 1));
+        assert_eq!(report.best, Some(best));
+        assert_eq!(report.description, "clone (1,1)");
+        assert_eq!(report.captures, 3);
+        assert_eq!(report.differential, config.play(&best).value());
+        assert_eq!(report.differential, 5);
+        assert!(report.exchange <= report.differential);
+        assert_eq!(report.principal_variation.first(), Some(&best));
+        assert_eq!(report.principal_variation.len(), 3);
+        assert!(report.margin.unwrap() > 0);
+    }
+
+    #[test]
+    fn multipv_starts_with_the_best_line() {
+        let board = Board::default();
+        let config = Configuration::from_fen("1r6/3bbb2/2b4b/r2b4/8/8/r7/r7 r", &board).unwrap();
+        let single = analyze(&config, 3).unwrap();
+        let lines = analyze_multipv(&config, 3, 2);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].movement, single.movement);
+        assert_eq!(lines[0].value, single.value);
+        assert_ne!(lines[1].movement, lines[0].movement);
+        assert!(config.is_legal(&lines[1].movement));
+        assert!(lines[1].value <= lines[0].value);
+        for line in &lines {
+            assert_eq!(line.principal_variation[0], line.movement);
+        }
+    }
+
+    #[test]
+    fn stuck_players_report_a_pass() {
+        let board = Board::from_fen("r##5/###5/###5/8/8/8/8/7b r").unwrap();
+        let report = Configuration::new(&board).move_report(2);
+        assert_eq!(report.best, None);
+        assert_eq!(report.description, "pass");
+        assert_eq!(report.margin, None);
+    }
+}
@@ -0,0 +1,78 @@
+use blobwar::analysis::{write_csv, write_json, Analyzer, BLUNDER_THRESHOLD};
+use blobwar::game::GameRecord;
+
+use std::env::args;
+use std::io::stdout;
+use std::process::exit;
+
+const USAGE: &str = "usage: analyze [--depth DEPTH] [--blunder-threshold DELTA]
+       [--format text|csv|json] RECORD
+replay a game saved with --record and rank each move among all legal moves, valued by an
+alpha - beta search of given depth (default 4)
+moves losing more than the threshold (default 4) against the best one are flagged as blunders";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1)
+}
+
+fn main() {
+    let mut depth = 4;
+    let mut threshold = BLUNDER_THRESHOLD;
+    let mut format = String::from("text");
+    let mut record_path = None;
+    let mut arguments = args().skip(1);
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .unwrap_or_else(|| fail(&format!("missing value for {}", argument)))
+        };
+        let invalid = || -> ! { fail(&format!("invalid value for {}", argument)) };
+        match argument.as_str() {
+            "--depth" => depth = value().parse().unwrap_or_else(|_| invalid()),
+            "--blunder-threshold" => threshold = value().parse().unwrap_or_else(|_| invalid()),
+            "--format" => format = value(),
+            _ if argument.starts_with("--") => fail(&format!("unknown argument {}", argument)),
+            _ if record_path.is_some() => fail("only one record can be analyzed"),
+            _ => record_path = Some(argument),
+        }
+    }
+    let record_path = record_path.unwrap_or_else(|| fail("missing record"));
+    let record = GameRecord::load(&record_path).unwrap_or_else(|error| fail(&error.to_string()));
+    let analyses = Analyzer::new(depth)
+        .blunder_threshold(threshold)
+        .analyze(&record)
+        .unwrap_or_else(|error| fail(&error.to_string()));
+    let written = match format.as_str() {
+        "text" => None,
+        "csv" => Some(write_csv(&analyses, stdout())),
+        "json" => Some(write_json(&analyses, stdout())),
+        _ => fail(&format!("unknown format {}", format)),
+    };
+    if let Some(written) = written {
+        return written.unwrap_or_else(|error| fail(&error.to_string()));
+    }
+    let mut best_moves = [0, 0];
+    let mut blunders = [0, 0];
+    let mut moves = [0, 0];
+    let mut lost = [0i64, 0];
+    for analysis in &analyses {
+        println!("{}", analysis);
+        let player = analysis.player as usize;
+        moves[player] += 1;
+        best_moves[player] += (analysis.rank == 1) as usize;
+        blunders[player] += analysis.blunder as usize;
+        lost[player] += analysis.delta() as i64;
+    }
+    for (player, name) in ["red", "blue"].iter().enumerate() {
+        println!(
+            "{}: {}/{} best moves, {} blunders, average delta {:.2}",
+            name,
+            best_moves[player],
+            moves[player],
+            blunders[player],
+            lost[player] as f64 / moves[player].max(1) as f64
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use blobwar::board::Board;
+use blobwar::configuration::Configuration;
+use blobwar::strategy::{AlphaBeta, MinMax, Search, SearchStats};
+
+use std::env::args;
+use std::process::exit;
+use std::time::Instant;
+
+const USAGE: &str = "usage: bench [--depth DEPTH] [--threads THREADS]
+search the default board with each strategy and report nodes per second";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1)
+}
+
+/// Print one line of results.
+fn report(name: &str, stats: &SearchStats) {
+    let seconds = stats.elapsed.as_secs_f64();
+    let speed = if seconds > 0.0 {
+        stats.nodes as f64 / seconds
+    } else {
+        0.0
+    };
+    println!(
+        "{:<44} {:>12} {:>10.1} {:>12.0}",
+        name,
+        stats.nodes,
+        seconds * 1000.0,
+        speed
+    );
+}
+
+fn main() {
+    let mut depth = 5;
+    let mut threads = 1;
+    let mut arguments = args().skip(1);
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .and_then(|value| value.parse::<usize>().ok())
+                .unwrap_or_else(|| fail(&format!("missing or invalid value for {}", argument)))
+        };
+        match argument.as_str() {
+            "--depth" => depth = value() as u8,
+            "--threads" => threads = value(),
+            _ => fail(&format!("unknown argument {}", argument)),
+        }
+    }
+
+    let board = Board::default();
+    let configuration = Configuration::new(&board);
+    println!(
+        "{:<44} {:>12} {:>10} {:>12}",
+        "strategy", "nodes", "ms", "nodes/s"
+    );
+
+    let mut minmax = MinMax::new(depth).threads(threads);
+    let (_, stats) = minmax.compute_next_move_with_stats(&configuration);
+    report(&minmax.to_string(), &stats);
+
+    let mut alphabeta = AlphaBeta::new(depth).threads(threads);
+    let (_, stats) = alphabeta.compute_next_move_with_stats(&configuration);
+    report(&alphabeta.to_string(), &stats);
+
+    // the sequential search, with its move ordering heuristics
+    let mut search = Search::new().killer_moves().history_heuristic();
+    let start = Instant::now();
+    search.run(&configuration, depth);
+    let stats = SearchStats {
+        nodes: search.nodes,
+        cutoffs: search.cutoffs,
+        elapsed: start.elapsed(),
+        max_depth_reached: search.max_depth_reached,
+        ..Default::default()
+    };
+    report("Sequential Alpha - Beta (killers, history)", &stats);
+}
@@ -0,0 +1,112 @@
+use blobwar::board::Board;
+use blobwar::configuration::{Configuration, Movement, Observer, ScoringRule};
+use blobwar::strategy::{parse_strategy, BookBuilder, Random, Strategy};
+
+use std::env::args;
+use std::fs;
+use std::path::Path;
+use std::process::exit;
+
+const USAGE: &str = "usage: book_builder [--p0 STRATEGY] [--p1 STRATEGY] [--board NAME] [--games GAMES]
+       [--plies PLIES] [--random-plies PLIES] [--min-games GAMES] [--output FILE]
+play games between both strategies, starting with a few random moves, and write a book of
+the moves with the best win rate in the first plies";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1)
+}
+
+fn main() {
+    let mut specs = ["alphabeta:3".to_owned(), "alphabeta:3".to_owned()];
+    let mut board_name = None;
+    let mut games = 100;
+    let mut plies = 8;
+    let mut random_plies = 2;
+    let mut min_games = 2;
+    let mut output = None;
+    let mut arguments = args().skip(1);
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .unwrap_or_else(|| fail(&format!("missing value for {}", argument)))
+        };
+        let mut number = || {
+            value()
+                .parse::<usize>()
+                .unwrap_or_else(|_| fail(&format!("invalid value for {}", argument)))
+        };
+        match argument.as_str() {
+            "--p0" => specs[0] = value(),
+            "--p1" => specs[1] = value(),
+            "--board" => board_name = Some(value()),
+            "--games" => games = number(),
+            "--plies" => plies = number(),
+            "--random-plies" => random_plies = number(),
+            "--min-games" => min_games = number() as u32,
+            "--output" => output = Some(value()),
+            _ => fail(&format!("unknown argument {}", argument)),
+        }
+    }
+    let mut players = specs.map(|spec| {
+        parse_strategy(&spec).unwrap_or_else(|error| fail(&error.to_string()))
+    });
+    let board = match board_name {
+        Some(name) => Board::load(Path::new("boards").join(name))
+            .unwrap_or_else(|error| fail(&error.to_string())),
+        None => Default::default(),
+    };
+
+    let mut builder = BookBuilder::new(min_games);
+    for game in 0..games {
+        // random first moves so that games differ
+        let mut random = Random(game as u64);
+        let mut configuration = Configuration::new(&board);
+        let mut played: Vec<(Configuration, Movement)> = Vec::new();
+        for _ in 0..random_plies {
+            if configuration.is_terminal() {
+                break;
+            }
+            match random.compute_next_move(&configuration) {
+                Some(movement) => {
+                    played.push((configuration, movement));
+                    configuration = configuration.play(&movement);
+                }
+                None => configuration = configuration.pass(),
+            }
+        }
+        // then the real players, swapping colors every game
+        let mut before = configuration;
+        let observer: &mut Observer = &mut |after, movement| {
+            if before.must_pass() {
+                before = before.pass();
+            }
+            played.push((before, *movement));
+            before = *after;
+        };
+        let (first, second) = players.split_at_mut(1);
+        let (red, blue) = if game % 2 == 0 {
+            (&mut first[0], &mut second[0])
+        } else {
+            (&mut second[0], &mut first[0])
+        };
+        let outcome = configuration.play_game(
+            red.as_mut(),
+            blue.as_mut(),
+            ScoringRule::default(),
+            None,
+            Some(observer),
+        );
+        for (configuration, movement) in played.iter().take(plies) {
+            builder.record(configuration, *movement, outcome.winner);
+        }
+    }
+
+    let text = builder.to_text();
+    match output {
+        Some(path) => fs::write(&path, text).unwrap_or_else(|error| fail(&error.to_string())),
+        None => print!("{}", text),
+    }
+    eprintln!("{} configurations booked", builder.entries().len());
+}
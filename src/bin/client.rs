@@ -23,6 +23,6 @@ fn main() {
         let game = Configuration::deserialize(&line, &board);
         let next_move = strategy.compute_next_move(&game);
         serde_json::to_writer(&mut sending, &next_move).expect("sending back movement failed");
-        sending.write(b"\n").expect("newline failed");
+        sending.write_all(b"\n").expect("newline failed");
     }
 }
@@ -0,0 +1,75 @@
+use blobwar::board::Board;
+use blobwar::configuration::ScoringRule;
+use blobwar::selfplay::{self_play, write_samples};
+use blobwar::strategy::parse_strategy;
+
+use std::env::args;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+use std::process::exit;
+
+const USAGE: &str = "usage: selfplay [--p0 STRATEGY] [--p1 STRATEGY] [--board NAME] [--games GAMES]
+       [--random-plies PLIES] [--output FILE]
+play games between both strategies, swapping colors every game, and write each chosen move
+with the configuration it was played in and the final result as JSON lines";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1)
+}
+
+fn main() {
+    let mut specs = ["alphabeta:3".to_owned(), "alphabeta:3".to_owned()];
+    let mut board_name = None;
+    let mut games = 10;
+    let mut random_plies = 2;
+    let mut output = None;
+    let mut arguments = args().skip(1);
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .unwrap_or_else(|| fail(&format!("missing value for {}", argument)))
+        };
+        let mut number = || {
+            value()
+                .parse::<usize>()
+                .unwrap_or_else(|_| fail(&format!("invalid value for {}", argument)))
+        };
+        match argument.as_str() {
+            "--p0" => specs[0] = value(),
+            "--p1" => specs[1] = value(),
+            "--board" => board_name = Some(value()),
+            "--games" => games = number(),
+            "--random-plies" => random_plies = number(),
+            "--output" => output = Some(value()),
+            _ => fail(&format!("unknown argument {}", argument)),
+        }
+    }
+    // check specifications once before playing
+    for spec in &specs {
+        parse_strategy(spec).unwrap_or_else(|error| fail(&error.to_string()));
+    }
+    let board = match board_name {
+        Some(name) => Board::load(Path::new("boards").join(name))
+            .unwrap_or_else(|error| fail(&error.to_string())),
+        None => Default::default(),
+    };
+
+    let samples = self_play(&board, games, random_plies, ScoringRule::default(), |game| {
+        let [first, second] = [&specs[0], &specs[1]].map(|spec| parse_strategy(spec).unwrap());
+        if game % 2 == 0 {
+            [first, second]
+        } else {
+            [second, first]
+        }
+    });
+    let written = match output {
+        Some(path) => File::create(&path)
+            .and_then(|file| write_samples(&samples, BufWriter::new(file))),
+        None => write_samples(&samples, io::stdout().lock()),
+    };
+    written.unwrap_or_else(|error| fail(&error.to_string()));
+    eprintln!("{} samples from {} games", samples.len(), games);
+}
@@ -1,9 +1,10 @@
 use blobwar::board::Board;
-use blobwar::configuration::Configuration;
+use blobwar::configuration::{Configuration, ScoringRule};
 use blobwar::strategy::{IterativeDeepening, IterativeStrategy, NetworkPlayer};
 
 use std::env::args;
 use std::net::TcpListener;
+use std::path::Path;
 
 fn main() {
     let map_name = args().nth(1).unwrap_or("standard".to_owned());
@@ -14,10 +15,11 @@ fn main() {
         .map(|c| NetworkPlayer::new(c.expect("accept failed")));
 
     // create board and start game
-    let board = Board::load(map_name).expect("failed loading map");
+    let board = Board::load(Path::new("boards").join(map_name)).expect("failed loading map");
     let mut game = Configuration::new(&board);
     game.battle(
         IterativeDeepening::new(IterativeStrategy::MinMax),
         players.next().unwrap(),
+        ScoringRule::default(),
     );
 }
@@ -0,0 +1,85 @@
+use blobwar::board::Board;
+use blobwar::configuration::ScoringRule;
+use blobwar::sprt::{Hypothesis, Sprt};
+use blobwar::strategy::parse_strategy;
+
+use std::env::args;
+use std::path::Path;
+use std::process::exit;
+
+const USAGE: &str = "usage: sprt --baseline STRATEGY --candidate STRATEGY [--elo0 ELO] [--elo1 ELO]
+       [--alpha ALPHA] [--beta BETA] [--max-games GAMES] [--board NAME]
+play games until telling whether the candidate is at most elo0 (default 0) or at least elo1
+(default 10) points stronger than the baseline";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1)
+}
+
+fn main() {
+    let mut specs = [None, None];
+    let mut parameters = [0.0, 10.0, 0.05, 0.05];
+    let mut max_games = 10_000;
+    let mut board_name = None;
+    let mut arguments = args().skip(1);
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .unwrap_or_else(|| fail(&format!("missing value for {}", argument)))
+        };
+        let mut number = || {
+            value()
+                .parse::<f64>()
+                .unwrap_or_else(|_| fail(&format!("invalid value for {}", argument)))
+        };
+        match argument.as_str() {
+            "--candidate" => specs[0] = Some(value()),
+            "--baseline" => specs[1] = Some(value()),
+            "--elo0" => parameters[0] = number(),
+            "--elo1" => parameters[1] = number(),
+            "--alpha" => parameters[2] = number(),
+            "--beta" => parameters[3] = number(),
+            "--max-games" => max_games = number() as usize,
+            "--board" => board_name = Some(value()),
+            _ => fail(&format!("unknown argument {}", argument)),
+        }
+    }
+    let specs = specs.map(|spec| spec.unwrap_or_else(|| fail("missing strategy")));
+    // check specifications once before playing
+    for spec in &specs {
+        parse_strategy(spec).unwrap_or_else(|error| fail(&error.to_string()));
+    }
+    let board = match board_name {
+        Some(name) => Board::load(Path::new("boards").join(name))
+            .unwrap_or_else(|error| fail(&error.to_string())),
+        None => Default::default(),
+    };
+
+    let [elo0, elo1, alpha, beta] = parameters;
+    let sprt = Sprt::new(elo0, elo1).alpha(alpha).beta(beta);
+    let (lower, upper) = sprt.bounds();
+    let result = sprt.run(
+        &board,
+        max_games,
+        ScoringRule::default(),
+        |_| specs.clone().map(|spec| parse_strategy(&spec).unwrap()),
+        |score, llr| {
+            eprintln!(
+                "{} games: {} (llr {:.2} in [{:.2}, {:.2}])",
+                score.games(),
+                score,
+                llr,
+                lower,
+                upper
+            )
+        },
+    );
+    match result.decision {
+        Some(Hypothesis::Alternative) => println!("candidate is stronger: H1 accepted"),
+        Some(Hypothesis::Null) => println!("candidate is not stronger: H0 accepted"),
+        None => println!("no conclusion after {} games", result.score.games()),
+    }
+    println!("candidate: {} (llr {:.2})", result.score, result.llr);
+}
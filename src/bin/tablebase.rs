@@ -0,0 +1,62 @@
+use blobwar::board::Board;
+use blobwar::configuration::{Configuration, ScoringRule};
+use blobwar::strategy::Tablebase;
+
+use std::env::args;
+use std::path::Path;
+use std::process::exit;
+
+const USAGE: &str = "usage: tablebase (--board NAME | --size WIDTHxHEIGHT) --output FILE
+solve every configuration of a small board and save the outcomes";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1)
+}
+
+/// Parse "WIDTHxHEIGHT".
+fn dimensions(text: &str) -> Option<(usize, usize)> {
+    let (width, height) = text.split_once('x')?;
+    let (width, height) = (width.parse().ok()?, height.parse().ok()?);
+    ((2..=8).contains(&width) && (2..=8).contains(&height)).then_some((width, height))
+}
+
+fn main() {
+    let mut board = None;
+    let mut output = None;
+    let mut arguments = args().skip(1);
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .unwrap_or_else(|| fail(&format!("missing value for {}", argument)))
+        };
+        match argument.as_str() {
+            "--board" => {
+                let path = Path::new("boards").join(value());
+                board = Some(Board::load(path).unwrap_or_else(|error| fail(&error.to_string())))
+            }
+            "--size" => {
+                let (width, height) =
+                    dimensions(&value()).unwrap_or_else(|| fail("invalid board size"));
+                board = Some(Board::empty(width, height))
+            }
+            "--output" => output = Some(value()),
+            _ => fail(&format!("unknown argument {}", argument)),
+        }
+    }
+    let board = board.unwrap_or_else(|| fail("missing board"));
+    let output = output.unwrap_or_else(|| fail("missing output file"));
+
+    let tablebase = Tablebase::generate(&board, ScoringRule::default())
+        .unwrap_or_else(|error| fail(&error.to_string()));
+    tablebase
+        .save(&output)
+        .unwrap_or_else(|error| fail(&error.to_string()));
+    let start = Configuration::new(&board);
+    println!(
+        "starting configuration: {:?} for red, best move {:?}",
+        tablebase.probe(&start).unwrap(),
+        tablebase.best_move(&start)
+    );
+}
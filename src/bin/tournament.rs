@@ -0,0 +1,62 @@
+use blobwar::board::Board;
+use blobwar::configuration::ScoringRule;
+use blobwar::elo::Ratings;
+use blobwar::stats::save;
+use blobwar::strategy::parse_strategy;
+use blobwar::tournament::tournament_on;
+
+use std::env::args;
+use std::path::Path;
+use std::process::exit;
+
+const USAGE: &str = "usage: tournament [--rounds ROUNDS] [--board NAME] [--stats-out FILE]
+       STRATEGY STRATEGY...
+play all pairs of strategies against each other, with both colors, and display the crosstable
+and elo ratings
+--stats-out writes statistics of each game as CSV (JSON if FILE ends with .json)";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1)
+}
+
+fn main() {
+    let mut rounds = 1;
+    let mut board_name = None;
+    let mut stats_path = None;
+    let mut strategies = Vec::new();
+    let mut arguments = args().skip(1);
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .unwrap_or_else(|| fail(&format!("missing value for {}", argument)))
+        };
+        match argument.as_str() {
+            "--rounds" => {
+                rounds = value()
+                    .parse()
+                    .unwrap_or_else(|_| fail(&format!("invalid value for {}", argument)))
+            }
+            "--board" => board_name = Some(value()),
+            "--stats-out" => stats_path = Some(value()),
+            _ if argument.starts_with("--") => fail(&format!("unknown argument {}", argument)),
+            spec => strategies
+                .push(parse_strategy(spec).unwrap_or_else(|error| fail(&error.to_string()))),
+        }
+    }
+    if strategies.len() < 2 {
+        fail("at least two strategies are needed");
+    }
+    let board = match board_name {
+        Some(name) => Board::load(Path::new("boards").join(name))
+            .unwrap_or_else(|error| fail(&error.to_string())),
+        None => Default::default(),
+    };
+    let result = tournament_on(&board, strategies, rounds, ScoringRule::default());
+    println!("{}\n", result);
+    print!("{}", Ratings::from_tournament(&result));
+    if let Some(path) = stats_path {
+        save(&result.records, path).unwrap_or_else(|error| fail(&error.to_string()));
+    }
+}
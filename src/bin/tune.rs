@@ -0,0 +1,75 @@
+use blobwar::board::Board;
+use blobwar::strategy::WeightedEval;
+use blobwar::tuning::Spsa;
+
+use std::env::args;
+use std::path::Path;
+use std::process::exit;
+
+const USAGE: &str = "usage: tune [--start WEIGHTS] [--iterations ITERATIONS] [--games GAMES]
+       [--depth DEPTH] [--board NAME] [--seed SEED]
+tune the weights of the weighted evaluation (material,mobility,corners,edges,compactness)
+by letting close parameterizations play each other";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1)
+}
+
+fn main() {
+    let mut start = WeightedEval::default();
+    let mut iterations = 20;
+    let mut games = 8;
+    let mut depth = 2;
+    let mut board_name = None;
+    let mut seed = 0;
+    let mut arguments = args().skip(1);
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .unwrap_or_else(|| fail(&format!("missing value for {}", argument)))
+        };
+        let mut number = || {
+            value()
+                .parse::<u64>()
+                .unwrap_or_else(|_| fail(&format!("invalid value for {}", argument)))
+        };
+        match argument.as_str() {
+            "--start" => {
+                start = value()
+                    .parse()
+                    .unwrap_or_else(|error: blobwar::strategy::ParseWeightsError| {
+                        fail(&error.to_string())
+                    })
+            }
+            "--iterations" => iterations = number() as usize,
+            "--games" => games = number() as usize,
+            "--depth" => depth = number() as u8,
+            "--board" => board_name = Some(value()),
+            "--seed" => seed = number(),
+            _ => fail(&format!("unknown argument {}", argument)),
+        }
+    }
+    let board = match board_name {
+        Some(name) => Board::load(Path::new("boards").join(name))
+            .unwrap_or_else(|error| fail(&error.to_string())),
+        None => Default::default(),
+    };
+
+    let mut tuner = Spsa::new(start).games(games).depth(depth).seed(seed);
+    for _ in 0..iterations {
+        let iteration = tuner.iterate(&board);
+        println!(
+            "iteration {}: {} against {} won {}, lost {}, drew {} -> {}",
+            iteration.index,
+            iteration.plus,
+            iteration.minus,
+            iteration.score.wins,
+            iteration.score.losses,
+            iteration.score.draws,
+            iteration.weights
+        );
+    }
+    println!("best weights: {}", tuner.weights());
+}
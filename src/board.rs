@@ -1,15 +1,138 @@
 //! Board related features. Provides the `Board` structure storing holes.
 use std::cmp::{max, min};
+use std::error::Error;
+use std::fmt;
 use std::fs::File;
 use std::io;
 use std::io::prelude::*;
 use std::path::Path;
 
 use super::positions::{BoardPosition, Position, Positions};
+#[cfg(feature = "serde")]
+use serde::de::{self, Deserialize, Deserializer};
+#[cfg(feature = "serde")]
+use serde::ser::{Serialize, Serializer};
+
+#[derive(Debug)]
+/// Everything which can go wrong when loading a `Board`.
+pub enum BoardError {
+    /// Reading the file failed.
+    Io(io::Error),
+    /// No cell at all.
+    Empty,
+    /// A row does not have the same width as the first one.
+    RaggedRow {
+        /// Index of the faulty row.
+        row: usize,
+        /// Width of the first row.
+        expected: usize,
+        /// Width of the faulty row.
+        found: usize,
+    },
+    /// Unsupported board dimensions.
+    Dimensions {
+        /// Number of columns.
+        width: usize,
+        /// Number of rows.
+        height: usize,
+    },
+    /// Unknown cell content.
+    InvalidCell {
+        /// Row of the cell.
+        row: usize,
+        /// Column of the cell.
+        column: usize,
+        /// What we found there.
+        character: char,
+    },
+    /// Starting blobs are given for only one player.
+    MissingBlobs,
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoardError::Io(error) => write!(f, "failed reading board: {}", error),
+            BoardError::Empty => write!(f, "board has no cells"),
+            BoardError::RaggedRow {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "row {} has {} cells but previous rows have {}",
+                row, found, expected
+            ),
+            BoardError::Dimensions { width, height } => {
+                write!(f, "unsupported board dimensions: {}x{}", width, height)
+            }
+            BoardError::InvalidCell {
+                row,
+                column,
+                character,
+            } => write!(
+                f,
+                "invalid cell '{}' at row {}, column {}",
+                character, row, column
+            ),
+            BoardError::MissingBlobs => write!(f, "both players need starting blobs"),
+        }
+    }
+}
+
+impl Error for BoardError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BoardError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BoardError {
+    fn from(error: io::Error) -> Self {
+        BoardError::Io(error)
+    }
+}
+
+#[derive(Debug)]
+/// Everything which can go wrong when reading a FEN string (see `Configuration::to_fen`).
+pub enum FenError {
+    /// The grid of cells is invalid.
+    Board(BoardError),
+    /// The player to play is missing or neither "r" nor "b".
+    Player(String),
+    /// Something follows the player to play.
+    Trailing(String),
+    /// Holes or dimensions differ from the ones of the board we play on.
+    BoardMismatch,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::Board(error) => write!(f, "invalid FEN cells: {}", error),
+            FenError::Player(found) => write!(f, "invalid FEN player '{}'", found),
+            FenError::Trailing(found) => write!(f, "unexpected '{}' at the end of FEN", found),
+            FenError::BoardMismatch => write!(f, "FEN is not on the same board"),
+        }
+    }
+}
+
+impl Error for FenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FenError::Board(error) => Some(error),
+            _ => None,
+        }
+    }
+}
 
 /// Board representation.
+#[derive(Clone)]
 pub struct Board {
     /// `Positions` of the holes.
+    /// Cells outside of the board's dimensions are holes too.
     pub holes: Positions,
     /// Associate to each position an uncompressed set of neighbouring positions (at distance 1 and at distance 2)
     /// (prefiltered with holes).
@@ -18,6 +141,51 @@ pub struct Board {
     pub individual_neighbours: [Vec<Vec<Position>>; 2],
     /// Associate to each `Position` the `Positions` of all its neighbours.
     pub neighbours: Vec<Positions>,
+    /// `Positions` of each player's blobs at the start of a game.
+    /// Players do not need to start with the same number of blobs.
+    pub starting_blobs: [Positions; 2],
+    width: usize,
+    height: usize,
+}
+
+/// Boards are equal if they have the same dimensions, holes and starting blobs
+/// (everything else is derived from them).
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.dimensions() == other.dimensions()
+            && self.holes == other.holes
+            && self.starting_blobs == other.starting_blobs
+    }
+}
+
+impl Eq for Board {}
+
+/// Boards are serialized as their text grid (see `Board::parse`).
+#[cfg(feature = "serde")]
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_text())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Board::parse(&text).map_err(de::Error::custom)
+    }
+}
+
+/// Display the grid : '#' for holes, 'x' and 'o' for starting blobs of each player,
+/// '.' for empty cells.
+/// The alternate form (`{:#}`) colors blobs for terminals.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.render(f, |f, position| {
+            let player = (0..2).find(|&player| self.starting_blobs[player].contains(position));
+            write_cell(f, player, self.holes.contains(position))
+        })
+    }
 }
 
 impl Default for Board {
@@ -26,33 +194,299 @@ impl Default for Board {
     }
 }
 
+/// Glyph of holes when displaying.
+pub const HOLE: char = '#';
+/// Glyph of empty cells when displaying.
+pub const EMPTY: char = '.';
+/// Glyphs of each player's blobs when displaying.
+pub const BLOBS: [char; 2] = ['x', 'o'];
+/// ANSI escape codes coloring each player's blobs (red and cyan).
+const COLORS: [&str; 2] = ["\x1b[31m", "\x1b[36m"];
+/// ANSI escape code going back to default color.
+const RESET: &str = "\x1b[0m";
+
+/// Write the glyph of a cell, `player` telling whose blob is there if any.
+/// With the alternate flag (`{:#}`) blobs are colored with ANSI escape codes.
+pub(crate) fn write_cell(f: &mut fmt::Formatter, player: Option<usize>, hole: bool) -> fmt::Result {
+    match player {
+        Some(player) if f.alternate() => write!(f, "{}{}{}", COLORS[player], BLOBS[player], RESET),
+        Some(player) => write!(f, "{}", BLOBS[player]),
+        None if hole => write!(f, "{}", HOLE),
+        None => write!(f, "{}", EMPTY),
+    }
+}
+
+/// Largest supported width or height.
+pub const MAX_SIZE: usize = 8;
+
+/// Blobs of each player in opposite corners of a board with given dimensions.
+fn corners(width: usize, height: usize) -> [Positions; 2] {
+    let cell = |x: usize, y: usize| Positions::single(Position::from_2d(x as u8, y as u8));
+    [
+        cell(0, 0).union_with(cell(width - 1, height - 1)),
+        cell(width - 1, 0).union_with(cell(0, height - 1)),
+    ]
+}
+
+/// Parse a FEN string (see `Configuration::to_fen`) into its board, blobs being the starting
+/// ones, and the player to play.
+pub(crate) fn parse_fen(fen: &str) -> Result<(Board, bool), FenError> {
+    let mut fields = fen.split_whitespace();
+    let cells = fields.next().ok_or(FenError::Board(BoardError::Empty))?;
+    let current_player = match fields.next() {
+        Some("r") => false,
+        Some("b") => true,
+        found => return Err(FenError::Player(found.unwrap_or_default().to_owned())),
+    };
+    if let Some(found) = fields.next() {
+        return Err(FenError::Trailing(found.to_owned()));
+    }
+    let mut holes = 0u64;
+    let mut blobs = [0u64; 2];
+    let mut width = None;
+    let rows: Vec<&str> = cells.split('/').collect();
+    for (y, row) in rows.iter().enumerate() {
+        let mut x = 0;
+        for character in row.chars() {
+            // too large boards are rejected once we know their dimensions
+            let inside = x < MAX_SIZE && y < MAX_SIZE;
+            let bit = || 1u64 << Position::from_2d(x as u8, y as u8);
+            match character {
+                '1'..='8' => {
+                    x += character as usize - '0' as usize;
+                    continue;
+                }
+                '#' if inside => holes |= bit(),
+                'r' if inside => blobs[0] |= bit(),
+                'b' if inside => blobs[1] |= bit(),
+                '#' | 'r' | 'b' => {}
+                _ => {
+                    return Err(FenError::Board(BoardError::InvalidCell {
+                        row: y,
+                        column: x,
+                        character,
+                    }))
+                }
+            }
+            x += 1;
+        }
+        match width {
+            None => width = Some(x),
+            Some(expected) if expected != x => {
+                return Err(FenError::Board(BoardError::RaggedRow {
+                    row: y,
+                    expected,
+                    found: x,
+                }))
+            }
+            Some(_) => {}
+        }
+    }
+    let (width, height) = (width.unwrap_or_default(), rows.len());
+    if !(2..=MAX_SIZE).contains(&width) || !(2..=MAX_SIZE).contains(&height) {
+        return Err(FenError::Board(BoardError::Dimensions { width, height }));
+    }
+    let board = Board::build(
+        width,
+        height,
+        Positions(holes),
+        [Positions(blobs[0]), Positions(blobs[1])],
+    );
+    Ok((board, current_player))
+}
+
+/// Holes of all cells outside of given dimensions.
+pub(crate) fn outside(width: usize, height: usize) -> Positions {
+    let mut holes = Positions::default();
+    for y in 0..MAX_SIZE {
+        for x in 0..MAX_SIZE {
+            if x >= width || y >= height {
+                holes.add(Positions::single(Position::from_2d(x as u8, y as u8)));
+            }
+        }
+    }
+    holes
+}
+
 impl Board {
-    /// Compute new `Board` structure from given holes.
+    /// Compute new 8x8 `Board` structure from given holes.
+    /// Each player starts with two blobs in opposite corners.
     pub fn new(holes: Positions) -> Self {
+        Board::with_starting_blobs(holes, corners(MAX_SIZE, MAX_SIZE))
+    }
+
+    /// Compute new 8x8 `Board` structure from given holes and starting blobs.
+    pub fn with_starting_blobs(holes: Positions, starting_blobs: [Positions; 2]) -> Self {
+        Board::build(MAX_SIZE, MAX_SIZE, holes, starting_blobs)
+    }
+
+    /// Board without holes of given dimensions.
+    /// Each player starts with two blobs in opposite corners.
+    /// Panics if dimensions are not between 2 and `MAX_SIZE`.
+    pub fn empty(width: usize, height: usize) -> Self {
+        Board::check_dimensions(width, height);
+        Board::build(width, height, Default::default(), corners(width, height))
+    }
+
+    /// Board without holes of given dimensions and given starting (x, y) cells for each player.
+    /// Panics if dimensions are not between 2 and `MAX_SIZE` or a cell is outside the board.
+    pub fn with_blobs(
+        width: usize,
+        height: usize,
+        p0_cells: &[(usize, usize)],
+        p1_cells: &[(usize, usize)],
+    ) -> Self {
+        Board::check_dimensions(width, height);
+        let to_positions = |cells: &[(usize, usize)]| {
+            cells.iter().fold(Positions::default(), |positions, &(x, y)| {
+                assert!(x < width && y < height, "cell ({}, {}) outside board", x, y);
+                positions.union_with(Positions::single(Position::from_2d(x as u8, y as u8)))
+            })
+        };
+        Board::build(
+            width,
+            height,
+            Default::default(),
+            [to_positions(p0_cells), to_positions(p1_cells)],
+        )
+    }
+
+    fn check_dimensions(width: usize, height: usize) {
+        assert!(
+            (2..=MAX_SIZE).contains(&width) && (2..=MAX_SIZE).contains(&height),
+            "unsupported board dimensions: {}x{}",
+            width,
+            height
+        );
+    }
+
+    fn build(width: usize, height: usize, holes: Positions, starting_blobs: [Positions; 2]) -> Self {
         let mut board = Board {
-            holes,
+            holes: holes.union_with(outside(width, height)),
             individual_neighbours: [Vec::new(), Vec::new()],
             neighbours: Vec::new(),
+            starting_blobs,
+            width,
+            height,
         };
         board.fill_individual_neighbours();
         board.fill_neighbours();
         board
     }
 
-    /// Load a `Board` from given file.
-    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        let file = File::open(Path::new("boards").join(path))?;
-        let mut bit = 1u64;
-        let mut positions_code = 0;
-        for line in io::BufReader::new(file).lines() {
-            for character in line?.chars() {
-                if character == 'x' {
-                    positions_code |= bit;
+    /// Return (width, height) of the board.
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Load a `Board` from given file. See `Board::parse` for the file format.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BoardError> {
+        let mut text = String::new();
+        File::open(path)?.read_to_string(&mut text)?;
+        Board::parse(&text)
+    }
+
+    /// Parse a `Board` out of a grid of cells, whitespace separating rows.
+    /// Boards can be of any dimensions between 2x2 and 8x8.
+    /// '.' is an empty cell, '#' a hole, '0' and '1' starting blobs of each player.
+    /// Older files with 'O' for empty cells, 'x' for holes and 'r', 'b' for blobs are also
+    /// accepted.
+    /// If no blob is given default starting positions are used.
+    pub fn parse(text: &str) -> Result<Self, BoardError> {
+        let rows: Vec<&str> = text.split_whitespace().collect();
+        let width = rows.first().ok_or(BoardError::Empty)?.chars().count();
+        for (row, line) in rows.iter().enumerate() {
+            let found = line.chars().count();
+            if found != width {
+                return Err(BoardError::RaggedRow {
+                    row,
+                    expected: width,
+                    found,
+                });
+            }
+        }
+        let height = rows.len();
+        if !(2..=MAX_SIZE).contains(&width) || !(2..=MAX_SIZE).contains(&height) {
+            return Err(BoardError::Dimensions { width, height });
+        }
+
+        let mut holes = 0u64;
+        let mut blobs = [0u64; 2];
+        for (y, line) in rows.iter().enumerate() {
+            for (x, character) in line.chars().enumerate() {
+                let bit = 1u64 << Position::from_2d(x as u8, y as u8);
+                match character {
+                    '.' | 'O' => {}
+                    '#' | 'x' => holes |= bit,
+                    '0' | 'r' => blobs[0] |= bit,
+                    '1' | 'b' => blobs[1] |= bit,
+                    _ => {
+                        return Err(BoardError::InvalidCell {
+                            row: y,
+                            column: x,
+                            character,
+                        })
+                    }
                 }
-                bit <<= 1;
             }
         }
-        Ok(Board::new(Positions(positions_code)))
+        let starting_blobs = match blobs {
+            [0, 0] => corners(width, height),
+            [0, _] | [_, 0] => return Err(BoardError::MissingBlobs),
+            _ => [Positions(blobs[0]), Positions(blobs[1])],
+        };
+        Ok(Board::build(width, height, Positions(holes), starting_blobs))
+    }
+
+    /// Board of given FEN string (see `Configuration::to_fen`), its blobs being the starting
+    /// ones. `Configuration::from_fen` then gives the configuration on this board.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        parse_fen(fen).map(|(board, _)| board)
+    }
+
+    /// Save the `Board` to given file, in the format understood by `Board::load`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        File::create(path)?.write_all(self.to_text().as_bytes())
+    }
+
+    /// Return the grid of cells in the canonical format understood by `Board::parse`.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let position = Position::from_2d(x as u8, y as u8);
+                text.push(if self.holes.contains(position) {
+                    '#'
+                } else if self.starting_blobs[0].contains(position) {
+                    '0'
+                } else if self.starting_blobs[1].contains(position) {
+                    '1'
+                } else {
+                    '.'
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Draw the grid with coordinates around it, asking `glyph` what to display in each cell.
+    pub(crate) fn render<F>(&self, f: &mut fmt::Formatter, mut glyph: F) -> fmt::Result
+    where
+        F: FnMut(&mut fmt::Formatter, Position) -> fmt::Result,
+    {
+        let columns: String = (0..self.width).map(|x| x.to_string()).collect();
+        let line = "-".repeat(self.width);
+        writeln!(f, "  {}", columns)?;
+        writeln!(f, " +{}+", line)?;
+        for y in 0..self.height {
+            write!(f, "{}|", y)?;
+            for x in 0..self.width {
+                glyph(f, Position::from_2d(x as u8, y as u8))?;
+            }
+            writeln!(f, "|")?;
+        }
+        write!(f, " +{}+", line)
     }
 
     /// Pre-compute valid neighbours for each position.
@@ -106,3 +540,143 @@ impl Board {
         Board::new(Positions(holes))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::{Configuration, ScoringRule};
+    use crate::strategy::{AlphaBeta, Strategy};
+
+    #[test]
+    fn handicap_favours_the_larger_army() {
+        let board = Board::load(named("handicap")).unwrap();
+        assert_eq!(board.starting_blobs[0].len(), 5);
+        assert_eq!(board.starting_blobs[1].len(), 3);
+        let config = Configuration::new(&board);
+        assert_eq!(config.blob_counts(), [5, 3]);
+        assert!(config.movements().next().is_some());
+        assert!(config.pass().movements().next().is_some());
+        // red plays first, the blue side just "moved" and is two blobs behind
+        assert_eq!(config.value(), -2);
+        assert_eq!(config.winner(ScoringRule::BlobDifference), Some(false));
+        let (movement, value) = AlphaBeta::new(2).compute_next_move_scored(&config);
+        assert!(movement.is_some());
+        assert!(value > 0);
+    }
+
+    /// Path of a board file in the `tests/fixtures` directory.
+    fn fixture(name: &str) -> String {
+        format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    /// Path of a board file in the `boards` directory.
+    fn named(name: &str) -> String {
+        format!("{}/boards/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn fixtures_load() {
+        let board = Board::load(fixture("small_board")).unwrap();
+        assert_eq!(board.dimensions(), (4, 3));
+        assert_eq!(board.holes.len(), 64 - 12 + 2);
+        assert!(board.holes.contains(Position::from_2d(3, 0)));
+        assert!(board.holes.contains(Position::from_2d(0, 2)));
+        assert!(board.starting_blobs[0] == Positions::single(Position::from_2d(0, 0)));
+        assert!(board.starting_blobs[1] == Positions::single(Position::from_2d(3, 2)));
+        // legacy format
+        let board = Board::load(named("x")).unwrap();
+        assert_eq!(board.dimensions(), (8, 8));
+        assert_eq!(board.holes.len(), 12);
+        assert!(board.starting_blobs == Board::default().starting_blobs);
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        match Board::load(fixture("ragged_board")) {
+            Err(BoardError::RaggedRow { row: 1, expected: 8, found: 7 }) => {}
+            other => panic!("unexpected {:?}", other.map(|board| board.to_text())),
+        }
+        match Board::load(fixture("invalid_cell")) {
+            Err(BoardError::InvalidCell { row: 1, column: 1, character: '?' }) => {}
+            other => panic!("unexpected {:?}", other.map(|board| board.to_text())),
+        }
+        assert!(matches!(Board::load(fixture("missing")), Err(BoardError::Io(_))));
+        assert!(matches!(Board::parse("0"), Err(BoardError::Dimensions { width: 1, height: 1 })));
+        assert!(matches!(Board::parse("0.\n.."), Err(BoardError::MissingBlobs)));
+    }
+
+    #[test]
+    fn saved_boards_load_back() {
+        for path in [fixture("small_board"), named("handicap")] {
+            let board = Board::load(&path).unwrap();
+            let saved = std::env::temp_dir().join(format!("blobwar-{}", std::process::id()));
+            board.save(&saved).unwrap();
+            let reloaded = Board::load(&saved).unwrap();
+            assert!(reloaded == board, "{}", path);
+            assert!(reloaded.holes == board.holes);
+            let original = std::fs::read_to_string(&path).unwrap();
+            let text = std::fs::read_to_string(&saved).unwrap();
+            std::fs::remove_file(&saved).unwrap();
+            assert_eq!(text, original);
+            assert_eq!(reloaded.to_text(), original);
+        }
+    }
+
+    #[test]
+    fn relative_paths_are_saved_as_given() {
+        // tests run from the crate directory
+        let saved = format!("target/blobwar-saved-{}", std::process::id());
+        Board::default().save(&saved).unwrap();
+        let absolute = Path::new(env!("CARGO_MANIFEST_DIR")).join(&saved);
+        let reloaded = Board::load(&absolute).unwrap();
+        std::fs::remove_file(&absolute).unwrap();
+        assert!(reloaded == Board::default());
+    }
+
+    #[test]
+    fn corner_blobs_stay_in_bounds() {
+        for size in [3, 8] {
+            let corner = size - 1;
+            let board = Board::with_blobs(size, size, &[(0, 0)], &[(corner, corner)]);
+            assert_eq!(board.dimensions(), (size, size));
+            let config = Configuration::new(&board);
+            for config in [config, config.pass()] {
+                let movements: Vec<_> = config.movements().collect();
+                // cells at distance at most two of a corner, the opponent's corner being
+                // one of them on a 3x3 board
+                assert_eq!(movements.len(), if size == 3 { 7 } else { 8 });
+                for movement in movements {
+                    let (x, y) = movement.destination().to_2d();
+                    assert!((x as usize) < size && (y as usize) < size, "{}", movement);
+                }
+            }
+        }
+        let board = Board::empty(3, 3);
+        assert_eq!(board.holes.len(), 64 - 9);
+        assert!(Configuration::new(&board)
+            .movements()
+            .all(|movement| !board.holes.contains(movement.destination())));
+    }
+
+    #[test]
+    fn rendering_shows_every_cell() {
+        let count = |text: &str, glyph: char| text.chars().filter(|&c| c == glyph).count();
+        let text = Board::default().to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "  01234567");
+        assert_eq!(lines[1], " +--------+");
+        assert_eq!(lines[2], "0|x......o|");
+        assert_eq!(lines[9], "7|o......x|");
+        assert_eq!(lines.len(), 11);
+        assert_eq!(count(&text, BLOBS[0]), 2);
+        assert_eq!(count(&text, BLOBS[1]), 2);
+        assert_eq!(count(&text, EMPTY), 60);
+        assert_eq!(count(&text, HOLE), 0);
+        let text = Board::load(named("x")).unwrap().to_string();
+        assert_eq!(count(&text, HOLE), 12);
+        assert_eq!(count(&text, EMPTY), 48);
+        let board = Board::default();
+        let text = Configuration::new_with_player(&board, true).to_string();
+        assert!(text.ends_with("blue to play (red x: 2, blue o: 2)"), "{}", text);
+    }
+}
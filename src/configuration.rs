@@ -1,13 +1,25 @@
 //! Provide a `Configuration` for storing game state ; a `Movement` for storing moves to play.
-use super::board::Board;
+use super::analysis::{Analyzer, MoveReport};
+use super::board::{parse_fen, write_cell, Board, FenError, BLOBS};
+use super::game::{parse_recorded_move, GameRecord};
 use super::positions::{BoardPosition, Position, Positions};
-use super::strategy::Strategy;
+use super::strategy::{ParseMoveError, Strategy};
+use super::zobrist::{hash_blobs, SIDE_KEY};
+#[cfg(feature = "serde")]
+use serde::de::{self, DeserializeSeed, Deserializer};
+#[cfg(feature = "serde")]
+use serde::ser::{Serialize, Serializer};
+#[cfg(feature = "serde")]
 use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::iter::once;
-use term;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Movements : clone some blob or jump
 pub enum Movement {
     /// For copy we just need the destination
@@ -16,25 +28,187 @@ pub enum Movement {
     Jump(u8, u8),
 }
 
+impl Movement {
+    /// Cell a jump starts from, None for clones.
+    pub fn source(&self) -> Option<Position> {
+        match *self {
+            Movement::Jump(source, _) => Some(source),
+            Movement::Duplicate(_) => None,
+        }
+    }
+
+    /// Cell the new blob lands on.
+    pub fn destination(&self) -> Position {
+        match *self {
+            Movement::Jump(_, destination) | Movement::Duplicate(destination) => destination,
+        }
+    }
+}
+
+/// Clones are displayed as "clone (x,y)" and jumps as "jump (x,y)->(x,y)".
+impl fmt::Display for Movement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Movement::Duplicate(destination) => {
+                let (x, y) = destination.to_2d();
+                write!(f, "clone ({},{})", x, y)
+            }
+            Movement::Jump(source, destination) => {
+                let (from_x, from_y) = source.to_2d();
+                let (to_x, to_y) = destination.to_2d();
+                write!(f, "jump ({},{})->({},{})", from_x, from_y, to_x, to_y)
+            }
+        }
+    }
+}
+
+/// Movements are parsed as written in game records : "x y" for clones and
+/// "from_x from_y to_x to_y" for jumps (or clones, see `parse_move`).
+impl FromStr for Movement {
+    type Err = ParseMoveError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        parse_recorded_move(text)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// How the winner is decided once the game is over (searches do not care).
+pub enum ScoringRule {
+    /// Whoever has more blobs wins.
+    #[default]
+    BlobDifference,
+    /// Whoever holds more than half of the board's cells wins, otherwise it is a draw.
+    MostBlobsWins,
+    /// Empty cells only neighboured by blobs of one player count as blobs of that player,
+    /// then whoever has more wins.
+    TerritoryControl,
+}
+
+impl ScoringRule {
+    /// All scoring rules.
+    pub const ALL: [ScoringRule; 3] = [
+        ScoringRule::BlobDifference,
+        ScoringRule::MostBlobsWins,
+        ScoringRule::TerritoryControl,
+    ];
+
+    /// Name of the rule, as reported by `engine_info`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ScoringRule::BlobDifference => "blob-difference",
+            ScoringRule::MostBlobsWins => "most-blobs-wins",
+            ScoringRule::TerritoryControl => "territory-control",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Time left to each player for all of their remaining moves.
+/// Whoever runs out of time loses.
+pub struct GameClock {
+    /// Time left to red.
+    pub p0: Duration,
+    /// Time left to blue.
+    pub p1: Duration,
+}
+
+impl GameClock {
+    /// Give the same time budget to both players.
+    pub fn new(budget: Duration) -> Self {
+        GameClock {
+            p0: budget,
+            p1: budget,
+        }
+    }
+
+    /// Time left to given player.
+    pub fn remaining(&self, player: bool) -> Duration {
+        if player {
+            self.p1
+        } else {
+            self.p0
+        }
+    }
+
+    /// Take given time from given player. Return false if the player ran out of time.
+    fn spend(&mut self, player: bool, elapsed: Duration) -> bool {
+        let remaining = if player { &mut self.p1 } else { &mut self.p0 };
+        match remaining.checked_sub(elapsed) {
+            Some(left) if !left.is_zero() => {
+                *remaining = left;
+                true
+            }
+            _ => {
+                *remaining = Duration::ZERO;
+                false
+            }
+        }
+    }
+}
+
+/// Callback told about each move of a game, with the configuration it led to.
+pub type Observer<'o, 'a> = dyn FnMut(&Configuration<'a>, &Movement) + 'o;
+
+/// Why the game loop stopped.
+enum Ending {
+    /// Nobody can move anymore.
+    Over,
+    /// A configuration was reached for the third time.
+    Repetition,
+    /// Given player ran out of time.
+    Timeout(bool),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// How a game ended.
+pub struct GameOutcome {
+    /// Winning player (false for red, true for blue), None on a draw.
+    pub winner: Option<bool>,
+    /// Number of blobs of each player at the end.
+    pub blob_counts: [i8; 2],
+    /// Did the game stop because a configuration was reached for the third time ?
+    /// (this is always a draw)
+    pub repetition: bool,
+    /// Player who lost by running out of time, if any.
+    pub timeout: Option<bool>,
+}
+
 #[derive(Copy, Clone)]
 /// Game state. We know, who should play, what is the board and where every blob is located.
 pub struct Configuration<'a> {
     blobs: [Positions; 2],
     board: &'a Board,
+    /// Zobrist hash of the blobs, maintained while playing.
+    blobs_hash: u64,
+    /// Number of blobs of each player, maintained while playing.
+    counts: [i8; 2],
     /// Who should play now.
     pub current_player: bool,
 }
 
 impl<'a> Configuration<'a> {
-    /// Create an initial game configuration out of given `Board`.
+    /// Create an initial game configuration out of given `Board`, red playing first.
     pub fn new(board: &'a Board) -> Self {
+        Configuration::new_with_player(board, false)
+    }
+
+    /// Create an initial game configuration out of given `Board` with given player
+    /// (false for red, true for blue) playing first.
+    pub fn new_with_player(board: &'a Board, current_player: bool) -> Self {
+        Configuration::with_blobs(board, board.starting_blobs, current_player)
+    }
+
+    /// Configuration on given `Board` with given blobs and player to play.
+    pub(crate) fn with_blobs(board: &'a Board, blobs: [Positions; 2], current_player: bool) -> Self {
         Configuration {
-            blobs: [
-                Positions::single(0).union_with(Positions::single(63)),
-                Positions::single(7).union_with(Positions::single(56)),
-            ],
+            blobs,
             board,
-            current_player: false,
+            blobs_hash: hash_blobs(0, blobs[0]) ^ hash_blobs(1, blobs[1]),
+            counts: [blobs[0].len(), blobs[1].len()],
+            current_player,
         }
     }
 
@@ -42,6 +216,7 @@ impl<'a> Configuration<'a> {
     pub fn apply_movement(&mut self, movement: &Movement) {
         let me = self.current_player as usize;
         let him = !self.current_player as usize;
+        let old_blobs = self.blobs;
         let destination = match *movement {
             Movement::Jump(source, destination) => {
                 self.blobs[me].remove(Positions::single(source));
@@ -55,6 +230,14 @@ impl<'a> Configuration<'a> {
         self.blobs[me].add(changing_blobs);
         self.blobs[me].add(Positions::single(destination));
         self.blobs[him].remove(changing_blobs);
+        // jumps move a blob, duplications add one
+        let converted = changing_blobs.len();
+        self.counts[me] += converted + movement.source().is_none() as i8;
+        self.counts[him] -= converted;
+        // only changed cells need to be re-hashed
+        for (player, (old, new)) in old_blobs.iter().zip(&self.blobs).enumerate() {
+            self.blobs_hash ^= hash_blobs(player, Positions(old.0 ^ new.0));
+        }
         self.current_player = !self.current_player;
     }
 
@@ -65,6 +248,12 @@ impl<'a> Configuration<'a> {
         new_configuration
     }
 
+    /// Create a new `Configuration` by passing : the other player plays next.
+    /// This is what a player who must pass does (see `must_pass`).
+    pub fn pass(&self) -> Self {
+        self.skip_play()
+    }
+
     /// Create a new `Configuration` by skipping turn on self.
     pub fn skip_play(&self) -> Self {
         let mut new_configuration = *self;
@@ -86,20 +275,53 @@ impl<'a> Configuration<'a> {
             .positions()
     }
 
-    /// Return the configuration value (#other_player - #current_player)
+    /// Return the configuration value (#other_player - #current_player).
+    /// This is the blob difference seen from the player who just moved (the one *not* about
+    /// to play) : positive when they lead.
+    /// It is not symmetric in the player to play : `skip_play` negates it.
+    /// Searches are negamaxes working from the point of view of the player to play and
+    /// therefore negate it on their leaves.
+    /// Blob counts are maintained when playing so this is O(1).
     pub fn value(&self) -> i8 {
         if self.current_player {
-            (self.blobs[0].len() - self.blobs[1].len()) as i8
+            self.counts[0] - self.counts[1]
         } else {
-            -(self.blobs[0].len() - self.blobs[1].len()) as i8
+            -(self.counts[0] - self.counts[1])
         }
     }
 
+    /// Report on the best move found by searching given number of plies (at least one), with
+    /// its captures, its line and its margin over the second best move (see `MoveReport`).
+    pub fn move_report(&self, depth: u8) -> MoveReport {
+        Analyzer::new(depth).report(self)
+    }
+
+    /// Return how many blobs of the opponent given movement converts.
+    /// Moves converting nothing are quiet moves.
+    pub fn captures(&self, movement: &Movement) -> i8 {
+        self.blobs[!self.current_player as usize]
+            .intersection_with(self.board.neighbours[movement.destination() as usize])
+            .len()
+    }
+
     /// Return if given movement is correct for current configuration.
+    /// This is `is_legal`.
     pub fn check_move(&self, movement: &Movement) -> bool {
+        self.is_legal(movement)
+    }
+
+    /// Return if given movement, possibly coming from outside, can be played by the current
+    /// player : it owns the source of a jump or a neighbour of a clone's destination, the
+    /// destination is an empty cell on the board and is at the right distance.
+    /// This agrees with `movements` without generating them.
+    pub fn is_legal(&self, movement: &Movement) -> bool {
+        let on_board = |position: Position| position < 64;
+        let blobs = self.blobs[self.current_player as usize];
         let destination = match *movement {
             Movement::Jump(source, destination) => {
-                if !(self.blobs[self.current_player as usize].contains(source))
+                if !on_board(source)
+                    || !on_board(destination)
+                    || !blobs.contains(source)
                     || source.distance_to(destination) != 2
                 {
                     return false;
@@ -107,9 +329,10 @@ impl<'a> Configuration<'a> {
                 destination
             }
             Movement::Duplicate(destination) => {
-                if self.board.neighbours[destination as usize]
-                    .intersection_with(self.blobs[self.current_player as usize])
-                    .is_empty()
+                if !on_board(destination)
+                    || self.board.neighbours[destination as usize]
+                        .intersection_with(blobs)
+                        .is_empty()
                 {
                     return false;
                 }
@@ -122,39 +345,285 @@ impl<'a> Configuration<'a> {
     }
 
     /// Play a match between the given players starting from current `Configuration`.
-    pub fn battle<T: Strategy, U: Strategy>(&mut self, mut player_one: T, mut player_two: U) {
-        while !self.game_over() {
-            println!(
-                "{} player's turn (he is losing by {} before playing)",
-                ["red", "blue"][self.current_player as usize],
-                self.value()
-            );
-            println!("{}", self);
-            let play_attempt = if self.current_player {
-                player_two.compute_next_move(self)
-            } else {
-                player_one.compute_next_move(self)
+    /// The game is a draw if the same configuration is reached for the third time.
+    /// Winner is decided with given `ScoringRule`. Return how the game ended.
+    pub fn battle<T: Strategy, U: Strategy>(
+        &mut self,
+        player_one: T,
+        player_two: U,
+        rule: ScoringRule,
+    ) -> GameOutcome {
+        self.battle_into(player_one, player_two, rule, None)
+    }
+
+    /// Like `battle` but also return the record of the game, to save it or replay it later.
+    pub fn battle_recorded<T: Strategy, U: Strategy>(
+        &mut self,
+        player_one: T,
+        player_two: U,
+        rule: ScoringRule,
+    ) -> (GameOutcome, GameRecord) {
+        let mut record = GameRecord::starting_from(self);
+        let outcome = self.battle_into(player_one, player_two, rule, Some(&mut record));
+        (outcome, record)
+    }
+
+    /// Play a `battle`, adding moves to given record if any.
+    fn battle_into<T: Strategy, U: Strategy>(
+        &mut self,
+        mut player_one: T,
+        mut player_two: U,
+        rule: ScoringRule,
+        mut record: Option<&mut GameRecord>,
+    ) -> GameOutcome {
+        let ending = self.play_out(
+            &mut player_one,
+            &mut player_two,
+            None,
+            |configuration| {
+                println!(
+                    "{} player's turn (he is losing by {} before playing)",
+                    ["red", "blue"][configuration.current_player as usize],
+                    configuration.value()
+                );
+                println!("{}", configuration);
+            },
+            |_, movement| {
+                if let Some(record) = record.as_mut() {
+                    record.moves.push(*movement)
+                }
+            },
+        );
+
+        let outcome = self.outcome(ending, rule);
+        match outcome.winner {
+            _ if outcome.repetition => println!("DRAW by threefold repetition!"),
+            Some(false) => println!("RED ({}) wins over BLUE ({})!", player_one, player_two),
+            Some(true) => println!("BLUE ({}) wins over RED ({})!", player_two, player_one),
+            None => println!("DRAW!"),
+        }
+        println!("{}", self);
+        println!(
+            "GAME OVER (red value of {})",
+            outcome.blob_counts[0] - outcome.blob_counts[1]
+        );
+        outcome
+    }
+
+    /// Play a match between the given players starting from current `Configuration`,
+    /// without printing anything. Winner is decided with given `ScoringRule`.
+    /// With a `GameClock`, players are asked to move before running out of time
+    /// (see `Strategy::compute_next_move_until`) and lose if they do not : the deadline they get
+    /// is when their time runs out so they had better keep a margin.
+    /// If given, `observer` is called after each move (but not on passes) with the resulting
+    /// configuration and the move just played.
+    pub fn play_game<T, U>(
+        &self,
+        player_one: &mut T,
+        player_two: &mut U,
+        rule: ScoringRule,
+        clock: Option<GameClock>,
+        mut observer: Option<&mut Observer<'_, 'a>>,
+    ) -> GameOutcome
+    where
+        T: Strategy + ?Sized,
+        U: Strategy + ?Sized,
+    {
+        let mut game = *self;
+        let mut clock = clock;
+        let ending = game.play_out(
+            player_one,
+            player_two,
+            clock.as_mut(),
+            |_| {},
+            |configuration, movement| {
+                if let Some(observer) = observer.as_mut() {
+                    observer(configuration, movement)
+                }
+            },
+        );
+        game.outcome(ending, rule)
+    }
+
+    /// Tell who won, once the game is over.
+    fn outcome(&self, ending: Ending, rule: ScoringRule) -> GameOutcome {
+        let winner = match ending {
+            Ending::Over => self.winner(rule),
+            Ending::Repetition => None,
+            Ending::Timeout(player) => Some(!player),
+        };
+        GameOutcome {
+            winner,
+            blob_counts: self.blob_counts(),
+            repetition: matches!(ending, Ending::Repetition),
+            timeout: match ending {
+                Ending::Timeout(player) => Some(player),
+                _ => None,
+            },
+        }
+    }
+
+    /// Tell who wins (None on a draw) if the game stops here, according to given rule.
+    pub fn winner(&self, rule: ScoringRule) -> Option<bool> {
+        let scores = match rule {
+            ScoringRule::BlobDifference => self.blob_counts(),
+            ScoringRule::MostBlobsWins => {
+                let cells = self.board.holes.invert().len() as i16;
+                let counts = self.blob_counts();
+                return (0..2)
+                    .find(|&player| 2 * counts[player] as i16 > cells)
+                    .map(|player| player == 1);
+            }
+            ScoringRule::TerritoryControl => {
+                let mut scores = self.blob_counts();
+                let empty = self.blobs[0]
+                    .union_with(self.blobs[1])
+                    .union_with(self.board.holes)
+                    .invert();
+                for cell in empty.positions() {
+                    let neighbours = self.board.neighbours[cell as usize];
+                    let touching = [0, 1].map(|player| {
+                        !neighbours.intersection_with(self.blobs[player]).is_empty()
+                    });
+                    match touching {
+                        [true, false] => scores[0] += 1,
+                        [false, true] => scores[1] += 1,
+                        _ => {}
+                    }
+                }
+                scores
+            }
+        };
+        match scores[0] - scores[1] {
+            x if x > 0 => Some(false),
+            x if x < 0 => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Play until the game is over, calling `before_turn` before each turn and `after_move`
+    /// after each move (with the move played).
+    /// This is the game loop shared by `battle` and `play_game`.
+    /// Time spent by each player is taken from `clock` if given.
+    fn play_out<T, U, F, G>(
+        &mut self,
+        player_one: &mut T,
+        player_two: &mut U,
+        mut clock: Option<&mut GameClock>,
+        mut before_turn: F,
+        mut after_move: G,
+    ) -> Ending
+    where
+        T: Strategy + ?Sized,
+        U: Strategy + ?Sized,
+        F: FnMut(&Self),
+        G: FnMut(&Self, &Movement),
+    {
+        let mut seen = HashMap::new();
+        seen.insert(self.zobrist_hash(), 1u8);
+        while !self.is_terminal() {
+            before_turn(self);
+            let player = self.current_player;
+            let start = Instant::now();
+            let deadline = clock.as_deref().map(|clock| start + clock.remaining(player));
+            let play_attempt = match (player, deadline) {
+                (false, None) => player_one.compute_next_move(self),
+                (false, Some(deadline)) => player_one.compute_next_move_until(self, deadline),
+                (true, None) => player_two.compute_next_move(self),
+                (true, Some(deadline)) => player_two.compute_next_move_until(self, deadline),
             };
+            if let Some(clock) = clock.as_deref_mut() {
+                if !clock.spend(player, start.elapsed()) {
+                    return Ending::Timeout(player);
+                }
+            }
             if let Some(ref next_move) = play_attempt {
                 assert!(self.check_move(next_move));
                 self.apply_movement(next_move);
+                after_move(self, next_move);
             } else {
-                self.current_player = !self.current_player;
+                *self = self.pass();
+            }
+            let occurrences = seen.entry(self.zobrist_hash()).or_insert(0);
+            *occurrences += 1;
+            if *occurrences == 3 {
+                return Ending::Repetition;
             }
         }
+        Ending::Over
+    }
 
-        let value = self.blobs[0].len() - self.blobs[1].len();
-        match value {
-            x if x > 0 => println!("RED ({}) wins over BLUE ({})!", player_one, player_two),
-            x if x < 0 => println!("BLUE ({}) wins over RED ({})!", player_two, player_one),
-            _ => println!("DRAW!"),
+    /// Display the configuration, with colors if `tty` is set (see `Display`).
+    /// Callers know where they print, so they tell whether colors can be used.
+    pub fn render_colored(&self, tty: bool) -> String {
+        if tty {
+            format!("{:#}", self)
+        } else {
+            self.to_string()
         }
-        println!("{}", self);
-        println!("GAME OVER (red value of {})", value);
+    }
+
+    /// `Board` the game is played on.
+    pub fn board(&self) -> &'a Board {
+        self.board
+    }
+
+    /// Blobs of each player.
+    pub(crate) fn blobs(&self) -> [Positions; 2] {
+        self.blobs
+    }
+
+    /// Return the number of blobs of each player (red first).
+    /// Counts are maintained when playing so this is O(1).
+    pub fn blob_counts(&self) -> [i8; 2] {
+        self.counts
+    }
+
+    /// Return the number of blobs of given player (false for red, true for blue).
+    /// `value` is the difference between the counts of both players.
+    pub fn blob_count(&self, player: bool) -> u32 {
+        self.counts[player as usize] as u32
+    }
+
+    /// Return the (x, y) coordinates of the cells occupied by given player's blobs,
+    /// line by line.
+    pub fn cells_of(&self, player: bool) -> Vec<(usize, usize)> {
+        self.blobs[player as usize]
+            .positions()
+            .map(|position| {
+                let (x, y) = position.to_2d();
+                (x as usize, y as usize)
+            })
+            .collect()
+    }
+
+    /// Return the number of cells neither holding a blob nor a hole.
+    pub fn empty_cell_count(&self) -> i8 {
+        self.blobs[0]
+            .union_with(self.blobs[1])
+            .union_with(self.board.holes)
+            .invert()
+            .len()
+    }
+
+    /// Return true if the game is over : someone died, the board is full or no player can move.
+    /// A player who is stuck while the other one can still move just passes.
+    /// Cheap checks (someone died, board is full) are done before generating moves.
+    pub fn is_terminal(&self) -> bool {
+        self.game_over()
+            || (self.movements().next().is_none() && self.pass().movements().next().is_none())
+    }
+
+    /// Return true if the player about to play has no move but the game is not over :
+    /// the turn has to be passed.
+    pub fn must_pass(&self) -> bool {
+        !self.game_over()
+            && self.movements().next().is_none()
+            && self.pass().movements().next().is_some()
     }
 
     /// Return true if no empty space remains or someone died.
-    fn game_over(&self) -> bool {
+    pub(crate) fn game_over(&self) -> bool {
         self.blobs[0].is_empty()
             || self.blobs[1].is_empty()
             || self.blobs[0]
@@ -163,6 +632,17 @@ impl<'a> Configuration<'a> {
                 .is_all()
     }
 
+    /// Return the Zobrist hash of the configuration (blobs and player about to play).
+    /// It is maintained incrementally when playing so this is O(1).
+    /// Configurations with the same blobs and player hash equally, whichever moves led there.
+    pub fn zobrist_hash(&self) -> u64 {
+        if self.current_player {
+            self.blobs_hash ^ SIDE_KEY
+        } else {
+            self.blobs_hash
+        }
+    }
+
     /// Iterate on all possible jumps for given player.
     fn jumps<'b>(&'b self) -> impl 'b + Iterator<Item = Movement> {
         self.blobs[self.current_player as usize]
@@ -237,49 +717,526 @@ impl<'a> Configuration<'a> {
             }
             bit <<= 1;
         }
-        Configuration {
+        Configuration::with_blobs(
+            board,
+            [Positions(blobs[0]), Positions(blobs[1])],
+            current_player,
+        )
+    }
+
+    /// Compact description of the configuration, holes and dimensions of the board included,
+    /// in the spirit of chess FEN strings.
+    /// Rows are given from top to bottom, separated by '/' : 'r' is a red blob, 'b' a blue
+    /// one, '#' a hole and digits count consecutive empty cells. The player to play follows
+    /// after a space, "r" or "b".
+    /// The starting configuration of the default board is "r6b/8/8/8/8/8/8/b6r r".
+    pub fn to_fen(&self) -> String {
+        let (width, height) = self.board.dimensions();
+        let mut fen = String::new();
+        for y in 0..height {
+            if y > 0 {
+                fen.push('/');
+            }
+            let mut empty = 0;
+            for x in 0..width {
+                let position = Position::from_2d(x as u8, y as u8);
+                let cell = if self.board.holes.contains(position) {
+                    '#'
+                } else if self.blobs[0].contains(position) {
+                    'r'
+                } else if self.blobs[1].contains(position) {
+                    'b'
+                } else {
+                    empty += 1;
+                    continue;
+                };
+                if empty > 0 {
+                    fen.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                fen.push(cell);
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+        }
+        fen.push_str(if self.current_player { " b" } else { " r" });
+        fen
+    }
+
+    /// Configuration on given board described by given FEN string (see `to_fen`).
+    /// Fails if the string is invalid or if it has other holes or dimensions than the board.
+    /// `Board::from_fen` gives the board of any FEN string.
+    pub fn from_fen(fen: &str, board: &'a Board) -> Result<Self, FenError> {
+        let (fen_board, current_player) = parse_fen(fen)?;
+        if fen_board.dimensions() != board.dimensions() || fen_board.holes != board.holes {
+            return Err(FenError::BoardMismatch);
+        }
+        Ok(Configuration::with_blobs(
             board,
-            blobs: [Positions(blobs[0]), Positions(blobs[1])],
+            fen_board.starting_blobs,
             current_player,
+        ))
+    }
+}
+
+/// Configurations are equal if they have the same board, blobs and player to play.
+impl<'a> PartialEq for Configuration<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.blobs == other.blobs
+            && self.current_player == other.current_player
+            && self.board == other.board
+    }
+}
+
+impl<'a> Eq for Configuration<'a> {}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+/// What we (de)serialize of a `Configuration` : the board is stored separately.
+/// Blobs are given as lists of positions (`y * 8 + x`), which any JSON reader can handle.
+struct ConfigurationData {
+    blobs: [Vec<Position>; 2],
+    current_player: bool,
+}
+
+/// The `Board` is not serialized, only blobs and player to play.
+/// See `ConfigurationSeed` for deserializing. To (de)serialize a configuration together with
+/// its board, go through the snapshot of a `Game` (a `GameState`).
+#[cfg(feature = "serde")]
+impl<'a> Serialize for Configuration<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ConfigurationData {
+            blobs: [
+                self.blobs[0].positions().collect(),
+                self.blobs[1].positions().collect(),
+            ],
+            current_player: self.current_player,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Deserialize a `Configuration` on the given `Board`.
+/// Blobs overlapping each other or holes are refused.
+#[cfg(feature = "serde")]
+pub struct ConfigurationSeed<'a>(pub &'a Board);
+
+#[cfg(feature = "serde")]
+impl<'de, 'a> DeserializeSeed<'de> for ConfigurationSeed<'a> {
+    type Value = Configuration<'a>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let data: ConfigurationData = serde::Deserialize::deserialize(deserializer)?;
+        let mut blobs = [0u64; 2];
+        for (player_blobs, positions) in blobs.iter_mut().zip(&data.blobs) {
+            for &position in positions {
+                if position >= 64 {
+                    return Err(de::Error::custom("blob outside of the board"));
+                }
+                *player_blobs |= 1 << position;
+            }
+        }
+        let [red, blue] = blobs;
+        if red & blue != 0 || (red | blue) & *self.0.holes != 0 {
+            return Err(de::Error::custom("blobs overlap each other or holes"));
         }
+        Ok(Configuration::with_blobs(
+            self.0,
+            [Positions(red), Positions(blue)],
+            data.current_player,
+        ))
     }
 }
 
+impl<'a> Hash for Configuration<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.zobrist_hash().hash(state);
+    }
+}
+
+/// Display the grid (see `Board`'s display for glyphs), who plays and how many blobs
+/// each player has.
+/// The alternate form (`{:#}`) colors blobs for terminals.
 impl<'a> fmt::Display for Configuration<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "\n  01234567 \n")?;
-        write!(f, " +--------+\n")?;
-        for (index, (hole, (red, blue))) in self
-            .board
-            .holes
-            .full_bits()
-            .zip(self.blobs[0].full_bits().zip(self.blobs[1].full_bits()))
-            .enumerate()
-        {
-            if index % 8 == 0 {
-                write!(f, "{}|", index / 8)?;
+        self.board.render(f, |f, position| {
+            let player = (0..2).find(|&player| self.blobs[player].contains(position));
+            write_cell(f, player, self.board.holes.contains(position))
+        })?;
+        let [red, blue] = self.blob_counts();
+        write!(
+            f,
+            "\n{} to play (red {}: {}, blue {}: {})",
+            ["red", "blue"][self.current_player as usize],
+            BLOBS[0],
+            red,
+            BLOBS[1],
+            blue
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::strategy::{AlphaBeta, Greedy, Random, Score};
+    use std::collections::HashSet;
+
+    /// All configurations of a few random games on given board, terminal ones included.
+    fn corpus(board: &Board) -> Vec<Configuration<'_>> {
+        let mut configurations = Vec::new();
+        for seed in 0..4 {
+            let mut random = Random(seed);
+            let mut config = Configuration::new(board);
+            configurations.push(config);
+            while !config.is_terminal() {
+                config = match random.compute_next_move(&config) {
+                    Some(movement) => config.play(&movement),
+                    None => config.pass(),
+                };
+                configurations.push(config);
             }
-            let mut t = term::stdout().unwrap();
-            match (hole, red, blue) {
-                (true, false, false) => write!(f, "x")?,
-                (false, true, false) => {
-                    t.fg(term::color::RED).unwrap();
-                    write!(f, "x")?;
-                    t.reset().unwrap();
-                }
-                (false, false, true) => {
-                    t.fg(term::color::CYAN).unwrap();
-                    write!(f, "o")?;
-                    t.reset().unwrap();
+        }
+        configurations
+    }
+
+    /// Board of given name in the `boards` directory.
+    fn named(name: &str) -> Board {
+        Board::load(format!("{}/boards/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+    }
+
+    fn boards() -> Vec<Board> {
+        ["standard", "cross", "island", "handicap", "x"]
+            .iter()
+            .map(|name| named(name))
+            .chain(vec![Board::empty(3, 3), Board::empty(5, 4)])
+            .collect()
+    }
+
+    #[test]
+    fn movements_display_their_coordinates() {
+        let clone = Movement::Duplicate(Position::from_2d(2, 3));
+        let jump = Movement::Jump(Position::from_2d(2, 3), Position::from_2d(4, 3));
+        assert_eq!(clone.to_string(), "clone (2,3)");
+        assert_eq!(jump.to_string(), "jump (2,3)->(4,3)");
+        // the kind of move follows from the distance in records
+        assert_eq!("1 2 2 3".parse::<Movement>().unwrap().to_string(), "clone (2,3)");
+        assert_eq!("2 3 4 3".parse::<Movement>().unwrap(), jump);
+    }
+
+    #[test]
+    fn counts_and_cells_cover_the_board() {
+        let board = Board::default();
+        let start = Configuration::new(&board);
+        assert_eq!(start.blob_count(false), start.blob_count(true));
+        for board in boards() {
+            for config in corpus(&board) {
+                let [red, blue] = [config.blob_count(false), config.blob_count(true)];
+                assert_eq!(config.cells_of(false).len() as u32, red);
+                assert_eq!(config.cells_of(true).len() as u32, blue);
+                // from the point of view of the player who just moved
+                let lead = red as i8 - blue as i8;
+                let lead = if config.current_player { lead } else { -lead };
+                assert_eq!(config.value(), lead);
+                let empty = config.empty_cell_count() as u32;
+                assert_eq!(red + blue + empty + board.holes.len() as u32, 64, "{}", config);
+            }
+        }
+    }
+
+    #[test]
+    fn either_player_can_start() {
+        let board = Board::from_fen("r1b5/2b5/1b6/8/8/8/8/6rb r").unwrap();
+        assert!(Configuration::new(&board) == Configuration::new_with_player(&board, false));
+        let mut moves = Vec::new();
+        for player in [false, true] {
+            let config = Configuration::new_with_player(&board, player);
+            assert_eq!(config.current_player, player);
+            let (movement, value) = AlphaBeta::new(1).compute_next_move_scored(&config);
+            let movement = movement.unwrap();
+            // the best move converts blobs of the other player
+            let after = config.play(&movement);
+            assert!(after.blob_count(!player) < config.blob_count(!player));
+            assert_eq!(after.value() as Score, value);
+            moves.push(movement);
+        }
+        // red clones next to three blue blobs, blue converts the red corner
+        assert_eq!(moves[0], Movement::Duplicate(Position::from_2d(1, 1)));
+        assert_eq!(moves[1], Movement::Duplicate(Position::from_2d(1, 0)));
+    }
+
+    #[test]
+    fn is_terminal_agrees_with_move_generation() {
+        for board in boards() {
+            let configurations = corpus(&board);
+            assert!(configurations.len() > 4);
+            for config in configurations {
+                let eliminated = config.blob_counts().contains(&0);
+                let stuck = config.movements().next().is_none()
+                    && config.pass().movements().next().is_none();
+                assert_eq!(config.is_terminal(), eliminated || stuck, "{}", config);
+                // clearly terminal configurations never need to generate moves
+                if eliminated || config.empty_cell_count() == 0 {
+                    assert!(config.game_over());
                 }
-                (false, false, false) => write!(f, " ")?,
-                _ => panic!("invalid board: {} {} {}", hole, red, blue),
             }
-            if index % 8 == 7 {
-                write!(f, "|\n")?;
+        }
+    }
+
+    #[test]
+    fn incremental_hashes_match_recomputed_ones() {
+        let board = Board::default();
+        let configurations = corpus(&board);
+        for config in &configurations {
+            let recomputed = Configuration::with_blobs(&board, config.blobs, config.current_player);
+            assert_eq!(config.zobrist_hash(), recomputed.zobrist_hash(), "{}", config);
+            let blobs_hash = hash_blobs(0, config.blobs[0]) ^ hash_blobs(1, config.blobs[1]);
+            let side = if config.current_player { SIDE_KEY } else { 0 };
+            assert_eq!(config.zobrist_hash(), blobs_hash ^ side);
+        }
+        // different configurations of the corpus do not collide
+        let distinct = |hashes: Vec<u64>| hashes.iter().collect::<HashSet<_>>().len();
+        let mut unique = configurations.clone();
+        unique.sort_by_key(|config| config.to_fen());
+        unique.dedup_by_key(|config| config.to_fen());
+        let hashes = unique.iter().map(Configuration::zobrist_hash).collect();
+        assert_eq!(distinct(hashes), unique.len());
+        // undoing a move restores the hash
+        let mut game = Game::new(&board);
+        let mut random = Random(0);
+        for _ in 0..10 {
+            let before = game.configuration().zobrist_hash();
+            let movement = random.compute_next_move(game.configuration()).unwrap();
+            assert!(game.play(movement));
+            assert_ne!(game.configuration().zobrist_hash(), before);
+            game.undo();
+            assert_eq!(game.configuration().zobrist_hash(), before);
+            game.play(movement);
+        }
+    }
+
+    /// Jump a blob back and forth between two cells.
+    struct Shuttle(Movement, Movement);
+
+    impl fmt::Display for Shuttle {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "Shuttle")
+        }
+    }
+
+    impl Strategy for Shuttle {
+        fn compute_next_move(&mut self, configuration: &Configuration) -> Option<Movement> {
+            [self.0, self.1].iter().copied().find(|movement| configuration.check_move(movement))
+        }
+    }
+
+    /// Think for a while before playing like `Greedy`, whatever the clock says.
+    struct Slow(Duration);
+
+    impl fmt::Display for Slow {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "Slow")
+        }
+    }
+
+    impl Strategy for Slow {
+        fn compute_next_move(&mut self, configuration: &Configuration) -> Option<Movement> {
+            std::thread::sleep(self.0);
+            Greedy::new(0).compute_next_move(configuration)
+        }
+    }
+
+    #[test]
+    fn slow_players_lose_on_time() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        let rule = ScoringRule::BlobDifference;
+        let clock = GameClock {
+            p0: Duration::from_millis(20),
+            p1: Duration::from_secs(3600),
+        };
+        let mut slow = Slow(Duration::from_millis(10));
+        let outcome = config.play_game(&mut slow, &mut Greedy::new(0), rule, Some(clock), None);
+        // red runs out of time before the game could end
+        assert_eq!(outcome.timeout, Some(false));
+        assert_eq!(outcome.winner, Some(true));
+        assert!(outcome.blob_counts[0] > 0);
+        // with equal budgets the fastest player is not worried
+        let clock = GameClock::new(Duration::from_millis(200));
+        let mut slow = Slow(Duration::from_millis(50));
+        let outcome = config.play_game(&mut Greedy::new(0), &mut slow, rule, Some(clock), None);
+        assert_eq!(outcome.timeout, Some(true));
+        assert_eq!(outcome.winner, Some(false));
+    }
+
+    #[test]
+    fn oscillations_end_in_a_draw() {
+        let board = Board::default();
+        let mut config = Configuration::from_fen("r7/8/8/8/8/8/8/7b r", &board).unwrap();
+        let [top, bottom] = [Position::from_2d(0, 0), Position::from_2d(0, 2)];
+        let red = Shuttle(Movement::Jump(top, bottom), Movement::Jump(bottom, top));
+        let [top, bottom] = [Position::from_2d(7, 5), Position::from_2d(7, 7)];
+        let blue = Shuttle(Movement::Jump(bottom, top), Movement::Jump(top, bottom));
+        let (outcome, record) = config.battle_recorded(red, blue, ScoringRule::BlobDifference);
+        assert!(outcome.repetition);
+        assert_eq!(outcome.winner, None);
+        assert_eq!(outcome.timeout, None);
+        assert_eq!(outcome.blob_counts, [1, 1]);
+        // the starting configuration comes back every four plies
+        assert_eq!(record.moves.len(), 8);
+        assert_eq!(config.to_fen(), "r7/8/8/8/8/8/8/7b r");
+    }
+
+    #[test]
+    fn silent_games_are_deterministic() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        let play = || {
+            let (mut red, mut blue) = (Greedy::new(0), Greedy::new(1));
+            config.play_game(&mut red, &mut blue, ScoringRule::BlobDifference, None, None)
+        };
+        let outcome = play();
+        assert_eq!(play(), outcome);
+        // looking one reply ahead wipes out the one-ply greedy player
+        let expected = GameOutcome {
+            winner: Some(true),
+            blob_counts: [0, 20],
+            repetition: false,
+            timeout: None,
+        };
+        assert_eq!(outcome, expected);
+        // playing silently does not change the game
+        let mut battle = config;
+        let rule = ScoringRule::BlobDifference;
+        assert_eq!(battle.battle(Greedy::new(0), Greedy::new(1), rule), outcome);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trips() {
+        let board = named("cross");
+        let json = serde_json::to_string(&board).unwrap();
+        let reloaded: Board = serde_json::from_str(&json).unwrap();
+        assert!(reloaded == board);
+        let start = Configuration::new(&reloaded);
+        let middle = corpus(&reloaded)[7];
+        for config in [start, middle, middle.pass()] {
+            let json = serde_json::to_string(&config).unwrap();
+            let mut deserializer = serde_json::Deserializer::from_str(&json);
+            let deserialized = ConfigurationSeed(&board).deserialize(&mut deserializer).unwrap();
+            assert!(deserialized == config, "{}", json);
+            assert_eq!(deserialized.current_player, config.current_player);
+            assert_eq!(deserialized.to_fen(), config.to_fen());
+            for movement in config.movements() {
+                let json = serde_json::to_string(&movement).unwrap();
+                assert_eq!(serde_json::from_str::<Movement>(&json).unwrap(), movement);
             }
         }
-        write!(f, " +--------+")?;
-        Ok(())
+        // blobs on holes are refused
+        let json = r#"{"blobs": [[0], [63]], "current_player": false}"#;
+        let holes = named("x");
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        assert!(ConfigurationSeed(&holes).deserialize(&mut deserializer).is_ok());
+        let json = r#"{"blobs": [[9], [63]], "current_player": false}"#;
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        assert!(ConfigurationSeed(&holes).deserialize(&mut deserializer).is_err());
+    }
+
+    #[test]
+    fn colors_only_on_tty() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        let colored = config.render_colored(true);
+        let plain = config.render_colored(false);
+        assert!(colored.contains("\x1b[31mx\x1b[0m"));
+        assert!(colored.contains("\x1b[36mo\x1b[0m"));
+        assert!(!plain.contains('\x1b'));
+        assert_eq!(plain, config.to_string());
+        let bare = ["\x1b[31m", "\x1b[36m", "\x1b[0m"]
+            .iter()
+            .fold(colored, |text, code| text.replace(code, ""));
+        assert_eq!(bare, plain);
+        assert!(format!("{:#}", board).contains('\x1b'));
+        assert!(!board.to_string().contains('\x1b'));
+    }
+
+    #[test]
+    fn observers_see_every_move() {
+        let board = Board::default();
+        let start = Configuration::new(&board);
+        let mut previous = start;
+        let mut moves = Vec::new();
+        let mut finished = Vec::new();
+        let observer: &mut Observer = &mut |configuration, movement| {
+            // the player about to move may have passed first
+            if !previous.check_move(movement) {
+                previous = previous.pass();
+            }
+            assert!(previous.check_move(movement));
+            assert!(previous.play(movement) == *configuration);
+            previous = *configuration;
+            moves.push(*movement);
+            finished.push(configuration.is_terminal());
+        };
+        let rule = ScoringRule::BlobDifference;
+        let (mut red, mut blue) = (Greedy::new(0), Greedy::new(1));
+        let outcome = start.play_game(&mut red, &mut blue, rule, None, Some(observer));
+        let mut battle = start;
+        let (recorded, record) = battle.battle_recorded(Greedy::new(0), Greedy::new(1), rule);
+        assert_eq!(outcome, recorded);
+        assert_eq!(moves, record.moves);
+        // both players moved and nothing is observed once the game is over
+        assert_eq!(previous.blob_counts(), outcome.blob_counts);
+        assert_eq!(finished.iter().position(|&over| over), Some(moves.len() - 1));
+    }
+
+    #[test]
+    fn scoring_rules_can_disagree() {
+        // both players are out of reach of the empty cells, beyond a wall of holes : the game is
+        // over with 48 cells and red 17 blobs against 15
+        let fen = "rrrrrrrr/rrrrrrrr/rbbbbbbb/bbbbbbbb/########/########/8/8 r";
+        let board = Board::from_fen(fen).unwrap();
+        let config = Configuration::from_fen(fen, &board).unwrap();
+        assert!(config.is_terminal());
+        assert_eq!(config.blob_counts(), [17, 15]);
+        assert_eq!(config.winner(ScoringRule::BlobDifference), Some(false));
+        assert_eq!(config.winner(ScoringRule::MostBlobsWins), None);
+        // no empty cell touches any blob
+        assert_eq!(config.winner(ScoringRule::TerritoryControl), Some(false));
+        // with 31 blobs red holds more than half of the board
+        let fen = "rrrrrrrr/rrrrrrrr/rrrrrrrr/rrrrrrrb/########/########/8/8 r";
+        let config = Configuration::from_fen(fen, &board).unwrap();
+        assert_eq!(config.blob_counts(), [31, 1]);
+        for rule in ScoringRule::ALL.iter() {
+            assert_eq!(config.winner(*rule), Some(false), "{}", rule.name());
+        }
+    }
+
+    #[test]
+    fn stuck_players_pass() {
+        // red is walled in by holes but blue can still play
+        let fen = "r##5/###5/###5/8/8/8/8/7b r";
+        let board = Board::from_fen(fen).unwrap();
+        let config = Configuration::from_fen(fen, &board).unwrap();
+        assert!(config.must_pass());
+        assert!(!config.is_terminal());
+        assert!(!config.pass().must_pass());
+        assert_eq!(Greedy::new(0).compute_next_move(&config), None);
+        let mut movers = Vec::new();
+        let mut observer = |configuration: &Configuration, _: &Movement| {
+            // the player who just moved
+            movers.push(!configuration.current_player);
+        };
+        let (mut red, mut blue) = (Greedy::new(0), Greedy::new(0));
+        let rule = ScoringRule::BlobDifference;
+        let outcome = config.play_game(&mut red, &mut blue, rule, None, Some(&mut observer));
+        // blue fills the board while red passes
+        assert!(movers.len() > 1);
+        assert!(movers.iter().all(|&mover| mover));
+        assert!(!outcome.repetition);
+        assert_eq!(outcome.winner, Some(true));
+        assert_eq!(outcome.blob_counts, [1, 64 - 8 - 1]);
     }
 }
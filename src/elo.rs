@@ -0,0 +1,205 @@
+//! Elo ratings : strength of strategies estimated from the results of their games.
+use super::tournament::{Score, TournamentResult};
+use std::fmt;
+
+/// Rating of new players.
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// Default rating change of a game, for a player scoring one more point than expected.
+pub const K_FACTOR: f64 = 16.0;
+
+/// Expected score (between 0 and 1, a draw being worth half a point) of a player rated given
+/// number of points more than their opponent.
+pub fn expected_score(difference: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-difference / 400.0))
+}
+
+/// Rating difference for which the expected score is the given one, infinite for 0 and 1.
+pub fn rating_difference(score: f64) -> f64 {
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// Rating difference of a player against their opponents, with a confidence interval.
+pub struct EloEstimate {
+    /// Most likely difference.
+    pub difference: f64,
+    /// Lower bound of the 95% confidence interval.
+    pub low: f64,
+    /// Upper bound of the 95% confidence interval.
+    pub high: f64,
+}
+
+impl EloEstimate {
+    /// Estimate the rating difference from given results against opponents of equal strength.
+    /// The interval comes from the standard error of the mean score (scores of games being 0,
+    /// 1/2 or 1) : it is only meaningful after a decent number of games, and infinite while
+    /// some player won everything.
+    pub fn from_score(score: &Score) -> Self {
+        let games = score.games() as f64;
+        if games == 0.0 {
+            return EloEstimate {
+                difference: 0.0,
+                low: f64::NEG_INFINITY,
+                high: f64::INFINITY,
+            };
+        }
+        let (wins, draws, losses) = (
+            score.wins as f64 / games,
+            score.draws as f64 / games,
+            score.losses as f64 / games,
+        );
+        let mean = wins + draws / 2.0;
+        let variance = wins * (1.0 - mean).powi(2)
+            + draws * (0.5 - mean).powi(2)
+            + losses * mean.powi(2);
+        let margin = 1.96 * (variance / games).sqrt();
+        let bounded = |score: f64| rating_difference(score.clamp(0.0, 1.0));
+        EloEstimate {
+            difference: bounded(mean),
+            low: bounded(mean - margin),
+            high: bounded(mean + margin),
+        }
+    }
+}
+
+/// Displayed as the difference followed by the bounds of the interval, like "+35 [-12, +84]".
+impl fmt::Display for EloEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:+.0} [{:+.0}, {:+.0}]",
+            self.difference, self.low, self.high
+        )
+    }
+}
+
+/// Ratings of players, updated after each game with the standard Elo formula : each player
+/// gains `k` times the difference between their score and their expected score.
+/// Results of each player are also kept to estimate how reliable ratings are.
+pub struct Ratings {
+    names: Vec<String>,
+    ratings: Vec<f64>,
+    scores: Vec<Score>,
+    k: f64,
+}
+
+impl Ratings {
+    /// New players with given names, all rated `INITIAL_RATING`.
+    pub fn new(names: Vec<String>) -> Self {
+        Ratings {
+            ratings: vec![INITIAL_RATING; names.len()],
+            scores: vec![Score::default(); names.len()],
+            names,
+            k: K_FACTOR,
+        }
+    }
+
+    /// Change ratings by at most given number of points per game (`K_FACTOR` by default).
+    pub fn k_factor(self, k: f64) -> Self {
+        Ratings { k, ..self }
+    }
+
+    /// Ratings replaying all games of given tournament, in the order they were played.
+    pub fn from_tournament(result: &TournamentResult) -> Self {
+        let mut ratings = Ratings::new(result.names.clone());
+        for (&(red, blue), stats) in result.pairings.iter().zip(&result.records) {
+            ratings.record(red, blue, stats.winner.map(|blue_won| !blue_won));
+        }
+        ratings
+    }
+
+    /// Record a game between given players : `first_won` is None on a draw.
+    pub fn record(&mut self, first: usize, second: usize, first_won: Option<bool>) {
+        let score = match first_won {
+            Some(true) => 1.0,
+            Some(false) => 0.0,
+            None => 0.5,
+        };
+        let change = self.k * (score - expected_score(self.ratings[first] - self.ratings[second]));
+        self.ratings[first] += change;
+        self.ratings[second] -= change;
+        for (player, won) in [(first, first_won), (second, first_won.map(|won| !won))] {
+            let score = &mut self.scores[player];
+            match won {
+                Some(true) => score.wins += 1,
+                Some(false) => score.losses += 1,
+                None => score.draws += 1,
+            }
+        }
+    }
+
+    /// Current rating of given player.
+    pub fn rating(&self, player: usize) -> f64 {
+        self.ratings[player]
+    }
+
+    /// Results of given player in all recorded games.
+    pub fn score(&self, player: usize) -> Score {
+        self.scores[player]
+    }
+
+    /// How much stronger given player is than the average of their opponents, from their
+    /// results.
+    pub fn estimate(&self, player: usize) -> EloEstimate {
+        EloEstimate::from_score(&self.scores[player])
+    }
+}
+
+/// One line per player, best rated first : rating, performance against their opponents with
+/// its confidence interval and results.
+impl fmt::Display for Ratings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut players: Vec<usize> = (0..self.names.len()).collect();
+        players.sort_by(|&first, &second| self.ratings[second].total_cmp(&self.ratings[first]));
+        let width = self.names.iter().map(|name| name.len()).max().unwrap_or(0);
+        for player in players {
+            writeln!(
+                f,
+                "{:width$} {:6.0} {:>20} {:>10}",
+                self.names[player],
+                self.ratings[player],
+                self.estimate(player).to_string(),
+                self.scores[player],
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::ScoringRule;
+    use crate::strategy::{Greedy, Random, Strategy};
+    use crate::tournament::tournament;
+
+    #[test]
+    fn tournaments_are_replayed_in_game_order() {
+        let strategies = vec![Box::new(Greedy::new(0)) as Box<dyn Strategy>, Box::new(Random(1))];
+        let mut result = tournament(strategies, 2, ScoringRule::BlobDifference);
+        // red, blue, draw, red : sequential ratings depend on the order of results
+        let winners = [Some(false), Some(true), None, Some(false)];
+        for (stats, winner) in result.records.iter_mut().zip(winners) {
+            stats.winner = winner;
+        }
+        let ratings = Ratings::from_tournament(&result);
+        let mut expected = Ratings::new(result.names.clone());
+        for (&(red, blue), stats) in result.pairings.iter().zip(&result.records) {
+            assert_eq!(stats.red, result.names[red]);
+            assert_eq!(stats.blue, result.names[blue]);
+            expected.record(red, blue, stats.winner.map(|blue_won| !blue_won));
+        }
+        for player in 0..2 {
+            assert_eq!(ratings.rating(player), expected.rating(player));
+            assert_eq!(ratings.score(player), expected.score(player));
+        }
+        // replaying wins, then losses and then draws would not give the same ratings
+        let mut grouped = Ratings::new(result.names.clone());
+        for first_won in [Some(true), Some(true), Some(false), None] {
+            grouped.record(0, 1, first_won);
+        }
+        assert_ne!(grouped.rating(0), ratings.rating(0));
+    }
+}
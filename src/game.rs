@@ -0,0 +1,557 @@
+//! Provide a `Game` : a `Configuration` remembering how it was reached so moves can be undone.
+use super::board::{Board, BoardError};
+use super::configuration::{Configuration, Movement};
+use super::positions::{BoardPosition, Position, Positions};
+use super::strategy::{parse_move, ParseMoveError};
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Game in progress, with the history of all moves played (and passes).
+/// Prior configurations are kept so that undoing a move restores exactly the blobs
+/// it converted.
+pub struct Game<'a> {
+    configuration: Configuration<'a>,
+    history: Vec<Option<Movement>>,
+    previous: Vec<Configuration<'a>>,
+}
+
+/// Everything needed to resume a `Game` later : the board, the configuration the game started
+/// from and all moves played since (None for passes).
+/// Replaying the moves gives back the player to play and how often each configuration was
+/// reached.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GameState {
+    board: Board,
+    /// Blobs of each player at the start, as lists of positions (`y * 8 + x`).
+    start_blobs: [Vec<Position>; 2],
+    start_player: bool,
+    history: Vec<Option<Movement>>,
+}
+
+/// A finished (or not) game : the board, who started and all moves played, to be replayed
+/// deterministically for analysis or bug reports.
+/// Blobs start where the board puts them. Passes are not recorded : a player without moves
+/// always passes.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GameRecord {
+    /// Board the game is played on.
+    pub board: Board,
+    /// Player playing first (false for red, true for blue).
+    pub start_player: bool,
+    /// All moves played, oldest first.
+    pub moves: Vec<Movement>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// A move of a `GameRecord` cannot be played when replaying it.
+pub struct ReplayError {
+    /// Index of the faulty move in the record (0 for the first move).
+    pub ply: usize,
+    /// The illegal move.
+    pub movement: Movement,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "illegal movement {} at ply {}", self.movement, self.ply)
+    }
+}
+
+impl Error for ReplayError {}
+
+/// Boards are shown in their text format.
+impl fmt::Debug for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GameRecord")
+            .field("board", &self.board.to_text())
+            .field("start_player", &self.start_player)
+            .field("moves", &self.moves)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+/// Everything which can go wrong when loading a `GameRecord`.
+pub enum RecordError {
+    /// Reading the file failed.
+    Io(io::Error),
+    /// Board is not valid.
+    Board(BoardError),
+    /// Player playing first is missing or neither "red" nor "blue".
+    Player(String),
+    /// A move cannot be parsed.
+    Move {
+        /// Index of the faulty move (0 for the first move).
+        ply: usize,
+        /// Why parsing failed.
+        error: ParseMoveError,
+    },
+    /// A move cannot be played.
+    Replay(ReplayError),
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::Io(error) => write!(f, "failed reading game record: {}", error),
+            RecordError::Board(error) => write!(f, "invalid board: {}", error),
+            RecordError::Player(found) => {
+                write!(f, "expected red or blue to play first but got '{}'", found)
+            }
+            RecordError::Move { ply, error } => write!(f, "move at ply {}: {}", ply, error),
+            RecordError::Replay(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for RecordError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RecordError::Io(error) => Some(error),
+            RecordError::Board(error) => Some(error),
+            RecordError::Move { error, .. } => Some(error),
+            RecordError::Replay(error) => Some(error),
+            RecordError::Player(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A list of moves in notation cannot be imported (see `GameRecord::from_notation`).
+pub enum ImportError {
+    /// A move cannot be parsed.
+    Parse {
+        /// Index of the faulty move in the list (0 for the first move).
+        index: usize,
+        /// Why parsing failed.
+        error: ParseMoveError,
+    },
+    /// A move cannot be played.
+    Illegal(ReplayError),
+}
+
+impl ImportError {
+    /// Index in the list of the first move which could not be imported.
+    pub fn index(&self) -> usize {
+        match self {
+            ImportError::Parse { index, .. } => *index,
+            ImportError::Illegal(error) => error.ply,
+        }
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImportError::Parse { index, error } => write!(f, "move {}: {}", index, error),
+            ImportError::Illegal(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for ImportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ImportError::Parse { error, .. } => Some(error),
+            ImportError::Illegal(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for RecordError {
+    fn from(error: io::Error) -> Self {
+        RecordError::Io(error)
+    }
+}
+
+/// Write a move as recorded : "x y" for clones and "from_x from_y to_x to_y" for jumps.
+pub(crate) fn recorded_move(movement: &Movement) -> String {
+    let (x, y) = movement.destination().to_2d();
+    match movement.source() {
+        Some(source) => {
+            let (from_x, from_y) = source.to_2d();
+            format!("{} {} {} {}", from_x, from_y, x, y)
+        }
+        None => format!("{} {}", x, y),
+    }
+}
+
+/// Parse a recorded move : "x y" for clones and "from_x from_y to_x to_y" for jumps.
+pub(crate) fn parse_recorded_move(text: &str) -> Result<Movement, ParseMoveError> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.len() != 2 {
+        return parse_move(text);
+    }
+    let mut coordinates = [0u8; 2];
+    for (coordinate, word) in coordinates.iter_mut().zip(&words) {
+        *coordinate = word
+            .parse()
+            .map_err(|_| ParseMoveError::NotANumber(word.to_string()))?;
+        if *coordinate >= 8 {
+            return Err(ParseMoveError::OutOfBoard(*coordinate));
+        }
+    }
+    Ok(Movement::Duplicate(Position::from_2d(coordinates[0], coordinates[1])))
+}
+
+impl GameRecord {
+    /// Start recording a game on given `Board` with given player playing first.
+    pub fn new(board: Board, start_player: bool) -> Self {
+        GameRecord {
+            board,
+            start_player,
+            moves: Vec::new(),
+        }
+    }
+
+    /// Start recording a game from given configuration : its blobs become the starting blobs
+    /// of the recorded board.
+    pub fn starting_from(configuration: &Configuration) -> Self {
+        let mut board = configuration.board().clone();
+        board.starting_blobs = configuration.blobs();
+        GameRecord::new(board, configuration.current_player)
+    }
+
+    /// Return the record as text : the board in the format of `Board::parse`, a blank line,
+    /// the player playing first ("red" or "blue") and one move per line, "x y" for clones
+    /// and "from_x from_y to_x to_y" for jumps.
+    pub fn to_text(&self) -> String {
+        let mut text = self.board.to_text();
+        text.push('\n');
+        text.push_str(if self.start_player { "blue\n" } else { "red\n" });
+        for movement in &self.moves {
+            text.push_str(&recorded_move(movement));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Parse a record written by `GameRecord::to_text`.
+    /// All moves are replayed to check they are legal.
+    pub fn parse(text: &str) -> Result<Self, RecordError> {
+        let mut lines = text.lines().map(str::trim);
+        let rows: Vec<&str> = lines.by_ref().take_while(|line| !line.is_empty()).collect();
+        let board = Board::parse(&rows.join("\n")).map_err(RecordError::Board)?;
+        let mut lines = lines.filter(|line| !line.is_empty());
+        let start_player = match lines.next() {
+            Some("red") => false,
+            Some("blue") => true,
+            found => return Err(RecordError::Player(found.unwrap_or_default().to_owned())),
+        };
+        let mut record = GameRecord::new(board, start_player);
+        for (ply, line) in lines.enumerate() {
+            let movement =
+                parse_recorded_move(line).map_err(|error| RecordError::Move { ply, error })?;
+            record.moves.push(movement);
+        }
+        record.replay().map_err(RecordError::Replay)?;
+        Ok(record)
+    }
+
+    /// Import a game played on given board by red first, from the notation of each move
+    /// (see `Movement::from_str`), as exported by other tools.
+    /// Moves are checked to be legal in sequence, failing on the first invalid or illegal one.
+    pub fn from_notation(board: &Board, moves: &[&str]) -> Result<Self, ImportError> {
+        let mut record = GameRecord::new(board.clone(), false);
+        let mut configuration = Configuration::new(board);
+        for (index, text) in moves.iter().enumerate() {
+            let movement: Movement =
+                text.parse().map_err(|error| ImportError::Parse { index, error })?;
+            if configuration.must_pass() {
+                configuration = configuration.pass();
+            }
+            if !configuration.check_move(&movement) {
+                return Err(ImportError::Illegal(ReplayError {
+                    ply: index,
+                    movement,
+                }));
+            }
+            configuration.apply_movement(&movement);
+            record.moves.push(movement);
+        }
+        Ok(record)
+    }
+
+    /// Save the record to given file, in the format of `GameRecord::to_text`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    /// Load a record saved by `GameRecord::save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RecordError> {
+        GameRecord::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Play all moves again and return the configuration reached after each of them
+    /// (passing whenever the player to play is stuck).
+    /// Fails on the first move which is not legal when it should be played.
+    pub fn replay(&self) -> Result<Vec<Configuration<'_>>, ReplayError> {
+        let mut configuration = Configuration::new_with_player(&self.board, self.start_player);
+        let mut configurations = Vec::with_capacity(self.moves.len());
+        for (ply, movement) in self.moves.iter().enumerate() {
+            if configuration.must_pass() {
+                configuration = configuration.pass();
+            }
+            if !configuration.check_move(movement) {
+                return Err(ReplayError {
+                    ply,
+                    movement: *movement,
+                });
+            }
+            configuration.apply_movement(movement);
+            configurations.push(configuration);
+        }
+        Ok(configurations)
+    }
+}
+
+impl<'a> From<Configuration<'a>> for Game<'a> {
+    fn from(configuration: Configuration<'a>) -> Self {
+        Game {
+            configuration,
+            history: Vec::new(),
+            previous: Vec::new(),
+        }
+    }
+}
+
+impl<'a> Game<'a> {
+    /// Start a new game on given `Board`.
+    pub fn new(board: &'a Board) -> Self {
+        Configuration::new(board).into()
+    }
+
+    /// Current `Configuration`.
+    pub fn configuration(&self) -> &Configuration<'a> {
+        &self.configuration
+    }
+
+    /// All moves played so far, oldest first, None standing for passes.
+    pub fn history(&self) -> &[Option<Movement>] {
+        &self.history
+    }
+
+    /// How many times the current `Configuration` was reached, including now.
+    pub fn repetitions(&self) -> usize {
+        1 + self
+            .previous
+            .iter()
+            .filter(|&&configuration| configuration == self.configuration)
+            .count()
+    }
+
+    /// Play given move if it is legal. Return whether it was played.
+    pub fn play(&mut self, movement: Movement) -> bool {
+        if !self.configuration.check_move(&movement) {
+            return false;
+        }
+        self.previous.push(self.configuration);
+        self.configuration.apply_movement(&movement);
+        self.history.push(Some(movement));
+        true
+    }
+
+    /// Pass if the player to play cannot move. Return whether it passed.
+    pub fn pass(&mut self) -> bool {
+        if !self.configuration.must_pass() {
+            return false;
+        }
+        self.previous.push(self.configuration);
+        self.configuration = self.configuration.pass();
+        self.history.push(None);
+        true
+    }
+
+    /// Take back the last move played (or pass, given as None) and return it.
+    /// None if nothing was played.
+    pub fn undo(&mut self) -> Option<Option<Movement>> {
+        let movement = self.history.pop()?;
+        self.configuration = self.previous.pop().expect("history out of sync");
+        Some(movement)
+    }
+
+    /// Save the game so it can be resumed with `Game::restore`.
+    pub fn snapshot(&self) -> GameState {
+        let start = self.previous.first().unwrap_or(&self.configuration);
+        let blobs = start.blobs();
+        GameState {
+            board: start.board().clone(),
+            start_blobs: [blobs[0].positions().collect(), blobs[1].positions().collect()],
+            start_player: start.current_player,
+            history: self.history.clone(),
+        }
+    }
+
+    /// Resume a game saved with `Game::snapshot`, on the board stored in the snapshot.
+    /// None if the snapshot is invalid (blobs outside of the board or on holes, illegal moves).
+    pub fn restore(state: &'a GameState) -> Option<Self> {
+        let mut blobs = [Positions::default(); 2];
+        for (player_blobs, positions) in blobs.iter_mut().zip(&state.start_blobs) {
+            for &position in positions {
+                if position >= 64 {
+                    return None;
+                }
+                player_blobs.add(Positions::single(position));
+            }
+        }
+        if !blobs[0].intersection_with(blobs[1]).is_empty()
+            || !blobs[0].union_with(blobs[1]).intersection_with(state.board.holes).is_empty()
+        {
+            return None;
+        }
+        let mut game: Game = Configuration::with_blobs(&state.board, blobs, state.start_player).into();
+        for &movement in &state.history {
+            let played = match movement {
+                Some(movement) => game.play(movement),
+                None => game.pass(),
+            };
+            if !played {
+                return None;
+            }
+        }
+        Some(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::{Observer, ScoringRule};
+    use crate::strategy::{AlphaBeta, Greedy, Random, Strategy};
+
+    #[test]
+    fn notation_is_imported() {
+        let board = Board::default();
+        let record = GameRecord::from_notation(&board, &["1 1", "6 1", "0 0 2 0"]).unwrap();
+        assert_eq!(
+            record.moves,
+            vec![
+                Movement::Duplicate(Position::from_2d(1, 1)),
+                Movement::Duplicate(Position::from_2d(6, 1)),
+                Movement::Jump(Position::from_2d(0, 0), Position::from_2d(2, 0)),
+            ]
+        );
+        assert!(!record.start_player);
+        assert_eq!(record.replay().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn first_bad_move_is_reported() {
+        let board = Board::default();
+        // blue has no blob next to (3,3)
+        let error = GameRecord::from_notation(&board, &["1 1", "3 3", "nowhere"]).unwrap_err();
+        assert_eq!(error.index(), 1);
+        assert!(matches!(error, ImportError::Illegal(_)));
+        let error = GameRecord::from_notation(&board, &["1 1", "nowhere", "3 3"]).unwrap_err();
+        assert_eq!(error.index(), 1);
+        assert!(matches!(error, ImportError::Parse { .. }));
+    }
+
+    #[test]
+    fn undo_restores_captured_blobs() {
+        let board = Board::default();
+        let start = Configuration::from_fen("r1b5/2b5/1b6/8/8/8/8/6rb r", &board).unwrap();
+        let mut game: Game = start.into();
+        // a clone converting three blobs and a jump converting two
+        let clone = Movement::Duplicate(Position::from_2d(1, 1));
+        let jump = Movement::Jump(Position::from_2d(0, 0), Position::from_2d(2, 2));
+        assert_eq!(game.undo(), None);
+        for movement in [clone, jump] {
+            let mut captured = start;
+            captured.apply_movement(&movement);
+            assert!(game.play(movement));
+            assert_eq!(game.configuration().blob_counts(), captured.blob_counts());
+            assert_ne!(game.configuration().blob_counts(), start.blob_counts());
+            assert_eq!(game.undo(), Some(Some(movement)));
+            assert!(*game.configuration() == start);
+            assert_eq!(game.configuration().zobrist_hash(), start.zobrist_hash());
+            assert_eq!(game.configuration().to_fen(), start.to_fen());
+            assert!(game.history().is_empty());
+        }
+    }
+
+    #[test]
+    fn restored_games_go_on_as_uninterrupted_ones() {
+        let board = Board::default();
+        let mut engine = AlphaBeta::new(3);
+        let mut game = Game::new(&board);
+        for _ in 0..3 {
+            let movement = engine.compute_next_move(game.configuration()).unwrap();
+            assert!(game.play(movement));
+        }
+        let state = game.snapshot();
+        let restored = Game::restore(&state).unwrap();
+        assert_eq!(restored.history(), game.history());
+        assert_eq!(restored.repetitions(), game.repetitions());
+        let configuration = *restored.configuration();
+        assert!(configuration == *game.configuration());
+        assert!(configuration.current_player);
+        let next = engine.compute_next_move(game.configuration());
+        assert_eq!(AlphaBeta::new(3).compute_next_move(&configuration), next);
+        assert_eq!(AlphaBeta::new(3).compute_next_move(restored.configuration()), next);
+    }
+
+    #[test]
+    fn passes_are_saved() {
+        // red is walled in by holes and passes while blue plays
+        let fen = "r##5/###5/###5/8/8/8/8/7b r";
+        let board = Board::from_fen(fen).unwrap();
+        let start = Configuration::from_fen(fen, &board).unwrap();
+        let mut game: Game = start.into();
+        let mut blue = Greedy::new(0);
+        assert!(!game.play(Movement::Duplicate(Position::from_2d(1, 1))));
+        assert!(game.pass());
+        assert!(!game.pass());
+        let movement = blue.compute_next_move(game.configuration()).unwrap();
+        assert!(game.play(movement));
+        assert!(game.pass());
+        assert_eq!(game.history(), [None, Some(movement), None]);
+        let state = game.snapshot();
+        let mut restored = Game::restore(&state).unwrap();
+        assert!(restored.configuration() == game.configuration());
+        assert!(restored.configuration().current_player);
+        assert_eq!(restored.undo(), Some(None));
+        assert_eq!(restored.undo(), Some(Some(movement)));
+        assert_eq!(restored.undo(), Some(None));
+        assert!(*restored.configuration() == start);
+        assert_eq!(restored.undo(), None);
+    }
+
+    #[test]
+    fn recorded_games_replay_to_their_end() {
+        let board = Board::default();
+        let start = Configuration::new(&board);
+        let mut record = GameRecord::starting_from(&start);
+        let mut last = start;
+        let observer: &mut Observer = &mut |configuration, movement| {
+            record.moves.push(*movement);
+            last = *configuration;
+        };
+        let (mut red, mut blue) = (Random(3), Greedy::new(1));
+        let rule = ScoringRule::BlobDifference;
+        let outcome = start.play_game(&mut red, &mut blue, rule, None, Some(observer));
+        let configurations = record.replay().unwrap();
+        assert_eq!(configurations.len(), record.moves.len());
+        let end = configurations.last().unwrap();
+        assert_eq!(end.to_fen(), last.to_fen());
+        assert_eq!(end.blob_counts(), outcome.blob_counts);
+        // through text and back
+        let parsed = GameRecord::parse(&record.to_text()).unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.replay().unwrap().last().unwrap().to_fen(), end.to_fen());
+        // an illegal move is reported with its ply
+        let mut broken = record.clone();
+        broken.moves.insert(2, Movement::Duplicate(Position::from_2d(4, 4)));
+        let error = broken.replay().err().unwrap();
+        assert_eq!((error.ply, error.movement), (2, broken.moves[2]));
+        let error = GameRecord::parse(&broken.to_text()).unwrap_err();
+        assert!(matches!(error, RecordError::Replay(ReplayError { ply: 2, .. })));
+    }
+}
@@ -0,0 +1,64 @@
+//! Engine capabilities, for frontends negotiating which features they can use.
+use super::configuration::ScoringRule;
+use super::strategy::STRATEGY_NAMES;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// What this build of the engine is able to do.
+pub struct EngineInfo {
+    /// Crate version.
+    pub version: &'static str,
+    /// Names of all strategies shipped in this build.
+    pub strategies: &'static [&'static str],
+    /// Names of all supported rulesets (see `ScoringRule::name`).
+    pub rulesets: Vec<&'static str>,
+    /// Are searches allowed to run on several threads ?
+    pub parallel: bool,
+    /// Were we compiled for a wasm target ?
+    pub wasm: bool,
+}
+
+/// Report capabilities of this build.
+pub fn engine_info() -> EngineInfo {
+    EngineInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        strategies: STRATEGY_NAMES,
+        rulesets: ScoringRule::ALL.iter().map(|rule| rule.name()).collect(),
+        parallel: cfg!(feature = "parallel"),
+        wasm: cfg!(target_arch = "wasm32"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::parse_strategy;
+
+    #[test]
+    fn shipped_strategies_are_reported() {
+        let info = engine_info();
+        let names = ["human", "greedy", "random", "minmax", "alphabeta", "mcts", "mtdf", "deepening"];
+        for name in names {
+            assert!(info.strategies.contains(&name), "{} is missing", name);
+        }
+        assert_eq!(info.strategies.contains(&"network"), cfg!(feature = "serde"));
+        assert_eq!(info.strategies.contains(&"iterative"), cfg!(unix));
+    }
+
+    #[test]
+    fn parsable_strategies_are_reported() {
+        let specifications = ["human", "greedy", "minmax:2", "alphabeta:2", "mtdf:2", "mcts:10", "random:1"];
+        for specification in specifications {
+            assert!(parse_strategy(specification).is_ok());
+            let name = specification.split(':').next().unwrap();
+            assert!(engine_info().strategies.contains(&name));
+        }
+    }
+
+    #[test]
+    fn rulesets_are_the_scoring_rules() {
+        let info = engine_info();
+        assert_eq!(info.rulesets.len(), ScoringRule::ALL.len());
+        assert!(info.rulesets.contains(&ScoringRule::default().name()));
+        assert!(info.rulesets.contains(&"territory-control"));
+    }
+}
@@ -2,8 +2,21 @@
 #![deny(missing_docs)]
 #![warn(clippy::all)]
 
+pub mod analysis;
 pub mod board;
 pub mod configuration;
+pub mod elo;
+pub mod game;
+pub mod info;
+pub mod perft;
 pub(crate) mod positions;
+#[cfg(feature = "serde")]
+pub mod selfplay;
+#[cfg(unix)]
 pub(crate) mod shmem;
+pub mod sprt;
+pub mod stats;
+pub(crate) mod zobrist;
 pub mod strategy;
+pub mod tournament;
+pub mod tuning;
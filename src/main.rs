@@ -1,11 +1,123 @@
 extern crate blobwar;
-//use blobwar::board::Board;
-use blobwar::configuration::Configuration;
-use blobwar::strategy::{Greedy, Human, MinMax, AlphaBeta};
+use blobwar::board::Board;
+use blobwar::configuration::{Configuration, ScoringRule};
+use blobwar::stats::{play_measured, save};
+use blobwar::strategy::{parse_strategy, OpeningBook, Strategy};
+
+use std::env::args;
+use std::path::Path;
+use std::process::exit;
+
+const USAGE: &str = "usage: blobwar [--p0 STRATEGY] [--p1 STRATEGY] [--board NAME] [--book FILE]
+       [--games GAMES] [--quiet] [--stats-out FILE] [--record FILE]
+--red and --blue are aliases of --p0 and --p1, red playing first
+--stats-out writes statistics of each game as CSV (JSON if FILE ends with .json), playing quietly
+--record saves the moves of the game (of game N in FILE.N when playing several games)
+strategies: human, greedy[:LOOKAHEAD], minmax:DEPTH, alphabeta:DEPTH, mtdf:DEPTH, mcts:ITERATIONS, random:SEED";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1)
+}
 
 fn main() {
-    //let board = Board::load("x").expect("failed loading board");
-    let board = Default::default();
-    let mut game = Configuration::new(&board);
-    game.battle(AlphaBeta(5), MinMax(5));
+    let mut specs = ["alphabeta:5".to_owned(), "minmax:5".to_owned()];
+    let mut board_name = None;
+    let mut book_path = None;
+    let mut games = 1;
+    let mut quiet = false;
+    let mut stats_path = None;
+    let mut record_path = None;
+    let mut arguments = args().skip(1);
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .unwrap_or_else(|| fail(&format!("missing value for {}", argument)))
+        };
+        match argument.as_str() {
+            "--p0" | "--red" => specs[0] = value(),
+            "--p1" | "--blue" => specs[1] = value(),
+            "--board" => board_name = Some(value()),
+            "--book" => book_path = Some(value()),
+            "--games" => {
+                games = value()
+                    .parse::<usize>()
+                    .unwrap_or_else(|_| fail(&format!("invalid value for {}", argument)))
+            }
+            "--quiet" => quiet = true,
+            "--stats-out" => stats_path = Some(value()),
+            "--record" => record_path = Some(value()),
+            _ => fail(&format!("unknown argument {}", argument)),
+        }
+    }
+    let book = book_path
+        .map(|path| OpeningBook::load(path).unwrap_or_else(|error| fail(&error.to_string())));
+    // fresh players for each game, so that no state leaks from one game to the next
+    let players = || {
+        specs.clone().map(|spec| {
+            let strategy = parse_strategy(&spec).unwrap_or_else(|error| fail(&error.to_string()));
+            match &book {
+                // both players play booked moves while they can
+                Some(book) => Box::new(book.clone().before(strategy)) as Box<dyn Strategy>,
+                None => strategy,
+            }
+        })
+    };
+    let board = match board_name {
+        Some(name) => Board::load(Path::new("boards").join(name))
+            .unwrap_or_else(|error| fail(&error.to_string())),
+        None => Default::default(),
+    };
+    // wins of red and blue, then draws
+    let mut results = [0; 3];
+    let mut statistics = Vec::new();
+    for index in 1..=games {
+        let [mut player_one, mut player_two] = players();
+        let mut game = Configuration::new(&board);
+        let (winner, record) = if quiet || stats_path.is_some() {
+            // batch runs : only tell how it ended
+            let stats = play_measured(
+                &game,
+                &mut player_one,
+                &mut player_two,
+                ScoringRule::default(),
+            );
+            match stats.winner {
+                _ if stats.repetition => println!("DRAW by threefold repetition!"),
+                Some(false) => println!("RED ({}) wins over BLUE ({})!", player_one, player_two),
+                Some(true) => println!("BLUE ({}) wins over RED ({})!", player_two, player_one),
+                None => println!("DRAW!"),
+            }
+            println!(
+                "red: {} blobs, blue: {} blobs",
+                stats.blob_counts[0], stats.blob_counts[1]
+            );
+            let outcome = (stats.winner, stats.record.clone());
+            statistics.push(stats);
+            outcome
+        } else {
+            let (outcome, record) =
+                game.battle_recorded(player_one, player_two, ScoringRule::default());
+            (outcome.winner, record)
+        };
+        if let Some(path) = &record_path {
+            let path = if games == 1 {
+                path.clone()
+            } else {
+                format!("{}.{}", path, index)
+            };
+            record.save(path).unwrap_or_else(|error| fail(&error.to_string()));
+        }
+        results[winner.map_or(2, |winner| winner as usize)] += 1;
+    }
+    if let Some(path) = stats_path {
+        save(&statistics, path).unwrap_or_else(|error| fail(&error.to_string()));
+    }
+    if games > 1 {
+        println!(
+            "red won {} games, blue won {} games, {} draws",
+            results[0], results[1], results[2]
+        );
+    }
 }
@@ -0,0 +1,33 @@
+//! Perft : count the configurations reachable in a given number of moves.
+//! Counts are known for standard boards so any change in them reveals a bug in move
+//! generation.
+//! On the default board, depths 1 to 4 give 16, 256, 6496 and 162628 configurations.
+use super::configuration::Configuration;
+
+/// Count the configurations reached at exactly given depth by playing all legal moves.
+/// Configurations where the player to play is stuck before that depth are not counted.
+pub fn perft(config: &Configuration, depth: u8) -> u64 {
+    match depth {
+        0 => 1,
+        1 => config.movements().count() as u64,
+        _ => config
+            .movements()
+            .map(|movement| perft(&config.play(&movement), depth - 1))
+            .sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    #[test]
+    fn default_board_counts() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        let counts: Vec<u64> = (1..=4).map(|depth| perft(&config, depth)).collect();
+        assert_eq!(counts, [16, 256, 6496, 162628], "move generation changed");
+        assert_eq!(perft(&config, 0), 1);
+    }
+}
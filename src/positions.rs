@@ -1,6 +1,5 @@
 //! a `Positions` is a set of 64 bits locating something on the board.
 //! we use it to keep track of blue blobs, red blobs and holes.
-use std;
 use std::fmt;
 use std::iter::repeat;
 use std::ops::Deref;
@@ -31,7 +30,7 @@ impl BoardPosition for Position {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
 /// Set of `Position` as a bitfield (position 0 is bit of lowest weight)
 /// This allows to store any combination of board cells in a very compact manner.
 /// Moreover we can then use bit masking operations to compute intersections and unions...
@@ -73,7 +72,7 @@ impl Positions {
     }
     /// Do we contain nothing ?
     pub fn is_empty(&self) -> bool {
-        self.eq(&0)
+        self.0 == 0
     }
     /// How many bits are set ?
     pub fn len(&self) -> i8 {
@@ -101,13 +100,7 @@ impl Positions {
     }
     /// Do we contain every possible `Position` ?
     pub fn is_all(&self) -> bool {
-        self.0 == std::u64::MAX
-    }
-}
-
-impl Default for Positions {
-    fn default() -> Self {
-        Positions(0)
+        self.0 == u64::MAX
     }
 }
 
@@ -0,0 +1,130 @@
+//! Self-play : games between strategies recorded as training data for evaluation functions.
+use super::board::Board;
+use super::configuration::{Configuration, Movement, Observer, ScoringRule};
+use super::strategy::{Random, Strategy};
+use serde_derive::Serialize;
+use std::io::{self, Write};
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+/// A move chosen by a strategy during a self-play game, with how the game ended.
+pub struct Sample<'a> {
+    /// Configuration the move was played in.
+    pub configuration: Configuration<'a>,
+    /// Chosen move.
+    pub movement: Movement,
+    /// Result for the player to play in `configuration` : 1 for a win, 0 for a draw and -1 for
+    /// a loss.
+    pub result: i8,
+    /// Final blob difference for the player to play in `configuration`.
+    pub margin: i8,
+}
+
+/// What we write of a `Sample`, one JSON object per line.
+#[derive(Serialize)]
+struct Line<'s, 'a> {
+    board: &'s Board,
+    configuration: &'s Configuration<'a>,
+    movement: &'s Movement,
+    result: i8,
+    margin: i8,
+}
+
+/// Play given number of games on given board and return all moves chosen by the strategies.
+/// `players` builds the two strategies of each game (given its index), red first. Games start
+/// with given number of random moves, seeded with the index of the game, so that they differ;
+/// these random moves are not recorded.
+/// With the parallel feature games are played at the same time in the rayon pool.
+/// Samples are returned game by game, in the order of the games.
+pub fn self_play<'a, F>(
+    board: &'a Board,
+    games: usize,
+    random_plies: usize,
+    rule: ScoringRule,
+    players: F,
+) -> Vec<Sample<'a>>
+where
+    F: Fn(usize) -> [Box<dyn Strategy>; 2] + Sync,
+{
+    let play = |game| play_recorded(board, game, random_plies, rule, &players);
+    #[cfg(feature = "parallel")]
+    let samples: Vec<Vec<Sample>> = {
+        use rayon::prelude::*;
+        (0..games).into_par_iter().map(play).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let samples: Vec<Vec<Sample>> = (0..games).map(play).collect();
+    samples.into_iter().flatten().collect()
+}
+
+/// Play game of given index and record its samples.
+fn play_recorded<'a, F>(
+    board: &'a Board,
+    game: usize,
+    random_plies: usize,
+    rule: ScoringRule,
+    players: &F,
+) -> Vec<Sample<'a>>
+where
+    F: Fn(usize) -> [Box<dyn Strategy>; 2],
+{
+    let mut random = Random(game as u64);
+    let mut configuration = Configuration::new(board);
+    for _ in 0..random_plies {
+        if configuration.is_terminal() {
+            break;
+        }
+        configuration = match random.compute_next_move(&configuration) {
+            Some(movement) => configuration.play(&movement),
+            None => configuration.pass(),
+        };
+    }
+    let mut played = Vec::new();
+    let mut before = configuration;
+    let observer: &mut Observer = &mut |after, movement| {
+        if before.must_pass() {
+            before = before.pass();
+        }
+        played.push((before, *movement));
+        before = *after;
+    };
+    let [mut red, mut blue] = players(game);
+    let outcome = configuration.play_game(red.as_mut(), blue.as_mut(), rule, None, Some(observer));
+    let red_margin = outcome.blob_counts[0] - outcome.blob_counts[1];
+    played
+        .into_iter()
+        .map(|(configuration, movement)| {
+            let player = configuration.current_player;
+            Sample {
+                configuration,
+                movement,
+                result: match outcome.winner {
+                    None => 0,
+                    Some(winner) if winner == player => 1,
+                    Some(_) => -1,
+                },
+                margin: if player { -red_margin } else { red_margin },
+            }
+        })
+        .collect()
+}
+
+/// Write given samples as JSON lines, one object per sample with fields
+/// - "board" : the board in the text format of `Board::parse`,
+/// - "configuration" : blobs of red and blue as lists of positions (`y * 8 + x`) and
+///   "current_player" (false for red, true for blue),
+/// - "movement" : `{"Duplicate": destination}` or `{"Jump": [source, destination]}`,
+/// - "result" and "margin" : see `Sample`.
+pub fn write_samples<W: Write>(samples: &[Sample], mut writer: W) -> io::Result<()> {
+    for sample in samples {
+        let line = Line {
+            board: sample.configuration.board(),
+            configuration: &sample.configuration,
+            movement: &sample.movement,
+            result: sample.result,
+            margin: sample.margin,
+        };
+        serde_json::to_writer(&mut writer, &line)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
@@ -3,7 +3,6 @@
 use super::configuration::Movement;
 use libc::off_t;
 use libc::{c_void, size_t};
-use nix;
 use nix::fcntl::{O_CREAT, O_RDWR};
 use nix::sys::mman::MAP_SHARED;
 use nix::sys::mman::{mmap, munmap};
@@ -11,12 +10,62 @@ use nix::sys::mman::{shm_open, shm_unlink};
 use nix::sys::mman::{PROT_READ, PROT_WRITE};
 use nix::sys::stat::{S_IRUSR, S_IWUSR};
 use nix::unistd::{close, ftruncate};
+use std::error::Error;
+use std::fmt;
 use std::os::unix::io::RawFd;
 use std::ptr;
 
 use std::mem;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+#[derive(Debug)]
+/// Everything which can go wrong when sharing moves between processes.
+pub enum ShmemError {
+    /// Opening (or creating) the shared memory failed, for example because nobody created it.
+    Open(nix::Error),
+    /// Resizing the shared memory failed.
+    Resize(nix::Error),
+    /// Mapping the shared memory in our address space failed.
+    Map(nix::Error),
+    /// The shared memory is not mapped anywhere.
+    Unmapped,
+}
+
+impl fmt::Display for ShmemError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShmemError::Open(error) => write!(f, "failed opening shmem: {}", error),
+            ShmemError::Resize(error) => write!(f, "failed resizing shmem: {}", error),
+            ShmemError::Map(error) => write!(f, "failed mapping shmem: {}", error),
+            ShmemError::Unmapped => write!(f, "shmem is not mapped"),
+        }
+    }
+}
+
+impl Error for ShmemError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ShmemError::Open(error) | ShmemError::Resize(error) | ShmemError::Map(error) => {
+                Some(error)
+            }
+            ShmemError::Unmapped => None,
+        }
+    }
+}
+
+/// Name of the shared memory.
+#[cfg(not(test))]
+fn shmem_name() -> String {
+    "blobwar".to_owned()
+}
+
+/// Tests use a shared memory of their own so that they never touch the one of a game running
+/// on the same machine, nor the one of other test runs.
+#[cfg(test)]
+fn shmem_name() -> String {
+    format!("blobwar-test-{}", std::process::id())
+}
+
 struct InnerAtomicMove {
     movements: [Option<Movement>; 2],
     selected: AtomicUsize,
@@ -35,16 +84,17 @@ impl Drop for AtomicMove {
         munmap(self.address, size as size_t).unwrap();
 
         if self.created {
-            shm_unlink("blobwar").unwrap();
+            shm_unlink(shmem_name().as_str()).unwrap();
         }
     }
 }
 
 impl AtomicMove {
-    pub fn new() -> Result<Self, nix::Error> {
+    pub fn new() -> Result<Self, ShmemError> {
         let size = mem::size_of::<InnerAtomicMove>();
-        let fd = shm_open("blobwar", O_CREAT | O_RDWR, S_IRUSR | S_IWUSR)?;
-        ftruncate(fd, size as off_t)?;
+        let fd = shm_open(shmem_name().as_str(), O_CREAT | O_RDWR, S_IRUSR | S_IWUSR)
+            .map_err(ShmemError::Open)?;
+        ftruncate(fd, size as off_t).map_err(ShmemError::Resize)?;
 
         let address = mmap(
             ptr::null_mut(),
@@ -53,7 +103,8 @@ impl AtomicMove {
             MAP_SHARED,
             fd,
             0,
-        )?;
+        )
+        .map_err(ShmemError::Map)?;
 
         let atomic: &mut InnerAtomicMove =
             unsafe { (address as *mut InnerAtomicMove).as_mut().unwrap() };
@@ -68,9 +119,10 @@ impl AtomicMove {
         })
     }
 
-    pub fn connect() -> Result<Self, nix::Error> {
+    pub fn connect() -> Result<Self, ShmemError> {
         let size = mem::size_of::<InnerAtomicMove>();
-        let fd = shm_open("blobwar", O_RDWR, S_IRUSR | S_IWUSR)?;
+        let fd = shm_open(shmem_name().as_str(), O_RDWR, S_IRUSR | S_IWUSR)
+            .map_err(ShmemError::Open)?;
         let address = mmap(
             ptr::null_mut(),
             size as size_t,
@@ -78,7 +130,8 @@ impl AtomicMove {
             MAP_SHARED,
             fd,
             0,
-        )?;
+        )
+        .map_err(ShmemError::Map)?;
         Ok(AtomicMove {
             fd,
             address,
@@ -86,12 +139,13 @@ impl AtomicMove {
         })
     }
 
-    pub fn store(&mut self, movement: Option<Movement>) {
+    pub fn store(&mut self, movement: Option<Movement>) -> Result<(), ShmemError> {
         let atomic: &mut InnerAtomicMove =
-            unsafe { (self.address as *mut InnerAtomicMove).as_mut().unwrap() };
+            unsafe { (self.address as *mut InnerAtomicMove).as_mut() }.ok_or(ShmemError::Unmapped)?;
         let index = atomic.selected.load(Ordering::SeqCst);
         atomic.movements[(index + 1) % 2] = movement;
         atomic.selected.fetch_add(1, Ordering::SeqCst);
+        Ok(())
     }
 
     pub fn load(&self) -> Option<Movement> {
@@ -100,3 +154,22 @@ impl AtomicMove {
         atomic.movements[atomic.selected.load(Ordering::SeqCst) % 2]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::configuration::Configuration;
+    use crate::strategy::{alpha_beta_anytime, min_max_anytime};
+
+    #[test]
+    fn anytime_searches_fail_without_shared_memory() {
+        // a killed test run may have left its shared memory behind
+        let _ = shm_unlink(shmem_name().as_str());
+        assert!(matches!(AtomicMove::connect(), Err(ShmemError::Open(_))));
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        assert!(matches!(alpha_beta_anytime(&config), Err(ShmemError::Open(_))));
+        assert!(matches!(min_max_anytime(&config), Err(ShmemError::Open(_))));
+    }
+}
@@ -0,0 +1,156 @@
+//! Sequential probability ratio test : play games between a candidate and a baseline until we
+//! can tell whether the candidate is stronger.
+use super::board::Board;
+use super::configuration::{Configuration, ScoringRule};
+use super::elo::expected_score;
+use super::strategy::{Random, Strategy};
+use super::tournament::Score;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Conclusion of a test.
+pub enum Hypothesis {
+    /// Candidate is at most `elo0` points stronger than the baseline.
+    Null,
+    /// Candidate is at least `elo1` points stronger than the baseline.
+    Alternative,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// Test of the candidate being `elo0` (null hypothesis) against `elo1` (alternative
+/// hypothesis) points stronger than the baseline, wrongly accepting the alternative with
+/// probability `alpha` and wrongly accepting the null hypothesis with probability `beta`.
+pub struct Sprt {
+    elo0: f64,
+    elo1: f64,
+    alpha: f64,
+    beta: f64,
+}
+
+impl Sprt {
+    /// Test between given elo differences, with 5% error probabilities.
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Sprt {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    /// Probability of accepting the alternative hypothesis while the null one holds.
+    pub fn alpha(self, alpha: f64) -> Self {
+        Sprt { alpha, ..self }
+    }
+
+    /// Probability of accepting the null hypothesis while the alternative one holds.
+    pub fn beta(self, beta: f64) -> Self {
+        Sprt { beta, ..self }
+    }
+
+    /// Log likelihood ratio of given results of the candidate, approximating the
+    /// distribution of game scores (0, 1/2 or 1) by a normal distribution.
+    /// Zero while all games ended the same way.
+    pub fn llr(&self, score: &Score) -> f64 {
+        let games = score.games() as f64;
+        if games == 0.0 {
+            return 0.0;
+        }
+        let mean = (score.wins as f64 + score.draws as f64 / 2.0) / games;
+        let variance = (score.wins as f64 * (1.0 - mean).powi(2)
+            + score.draws as f64 * (0.5 - mean).powi(2)
+            + score.losses as f64 * mean.powi(2))
+            / games;
+        if variance == 0.0 {
+            return 0.0;
+        }
+        let (score0, score1) = (expected_score(self.elo0), expected_score(self.elo1));
+        games * (score1 - score0) * (2.0 * mean - score0 - score1) / (2.0 * variance)
+    }
+
+    /// Log likelihood ratios below the first bound accept the null hypothesis, above the
+    /// second one the alternative.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Hypothesis accepted after given results, None if we need more games.
+    pub fn decision(&self, score: &Score) -> Option<Hypothesis> {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            Some(Hypothesis::Null)
+        } else if llr >= upper {
+            Some(Hypothesis::Alternative)
+        } else {
+            None
+        }
+    }
+
+    /// Play pairs of games between the candidate and the baseline on given board until a
+    /// hypothesis is accepted or given number of games is reached.
+    /// `players` builds the candidate and the baseline for each game (given its index). Both
+    /// games of a pair start with the same two random moves, seeded with the index of the
+    /// pair, the candidate playing red in the first one and blue in the second one.
+    /// `progress` is told about the results after each pair.
+    pub fn run<F, P>(
+        &self,
+        board: &Board,
+        max_games: usize,
+        rule: ScoringRule,
+        players: F,
+        mut progress: P,
+    ) -> SprtResult
+    where
+        F: Fn(usize) -> [Box<dyn Strategy>; 2],
+        P: FnMut(&Score, f64),
+    {
+        let mut score = Score::default();
+        let mut decision = None;
+        while decision.is_none() && score.games() + 2 <= max_games {
+            let pair = score.games() / 2;
+            let mut random = Random(pair as u64);
+            let mut opening = Configuration::new(board);
+            for _ in 0..2 {
+                opening = match random.compute_next_move(&opening) {
+                    Some(movement) => opening.play(&movement),
+                    None => opening.pass(),
+                };
+            }
+            for candidate_red in [true, false] {
+                let [mut candidate, mut baseline] = players(score.games());
+                let (red, blue) = if candidate_red {
+                    (candidate.as_mut(), baseline.as_mut())
+                } else {
+                    (baseline.as_mut(), candidate.as_mut())
+                };
+                let outcome = opening.play_game(red, blue, rule, None, None);
+                match outcome.winner {
+                    None => score.draws += 1,
+                    Some(winner) if winner != candidate_red => score.wins += 1,
+                    Some(_) => score.losses += 1,
+                }
+            }
+            progress(&score, self.llr(&score));
+            decision = self.decision(&score);
+        }
+        SprtResult {
+            score,
+            llr: self.llr(&score),
+            decision,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// How a test ended.
+pub struct SprtResult {
+    /// Results of the candidate against the baseline.
+    pub score: Score,
+    /// Final log likelihood ratio.
+    pub llr: f64,
+    /// Accepted hypothesis, None if we ran out of games first.
+    pub decision: Option<Hypothesis>,
+}
@@ -0,0 +1,140 @@
+//! Statistics of played games, written as CSV or JSON for analysis with other tools.
+use super::configuration::{Configuration, Observer, ScoringRule};
+use super::game::GameRecord;
+use super::strategy::Strategy;
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+/// What happened in one game.
+pub struct GameStats {
+    /// Name of the red strategy (as displayed).
+    pub red: String,
+    /// Name of the blue strategy (as displayed).
+    pub blue: String,
+    /// Winning player (false for red, true for blue), None on a draw.
+    pub winner: Option<bool>,
+    /// Did the game stop on a threefold repetition ?
+    pub repetition: bool,
+    /// Number of moves played by both players (passes excluded).
+    pub moves: usize,
+    /// Average thinking time of each player per move, in milliseconds.
+    pub milliseconds_per_move: [f64; 2],
+    /// Number of blobs of each player at the end.
+    pub blob_counts: [i8; 2],
+    /// All moves of the game (not exported).
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub record: GameRecord,
+}
+
+/// Play a game between given players from given configuration, without printing anything,
+/// and return its statistics.
+/// Thinking times are measured between moves, so they include the time spent passing.
+pub fn play_measured<T, U>(
+    configuration: &Configuration,
+    red: &mut T,
+    blue: &mut U,
+    rule: ScoringRule,
+) -> GameStats
+where
+    T: Strategy + ?Sized,
+    U: Strategy + ?Sized,
+{
+    let mut moves = [0usize; 2];
+    let mut thinking = [0.0; 2];
+    let mut record = GameRecord::starting_from(configuration);
+    let mut last_move = Instant::now();
+    let observer: &mut Observer = &mut |after, movement| {
+        record.moves.push(*movement);
+        let player = !after.current_player as usize;
+        moves[player] += 1;
+        thinking[player] += last_move.elapsed().as_secs_f64() * 1000.0;
+        last_move = Instant::now();
+    };
+    let outcome = configuration.play_game(red, blue, rule, None, Some(observer));
+    let mut milliseconds_per_move = [0.0; 2];
+    for ((average, total), &count) in milliseconds_per_move
+        .iter_mut()
+        .zip(&thinking)
+        .zip(&moves)
+    {
+        *average = total / count.max(1) as f64;
+    }
+    GameStats {
+        red: red.to_string(),
+        blue: blue.to_string(),
+        winner: outcome.winner,
+        repetition: outcome.repetition,
+        moves: moves[0] + moves[1],
+        milliseconds_per_move,
+        blob_counts: outcome.blob_counts,
+        record,
+    }
+}
+
+/// Quote given field for CSV.
+fn quoted(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+/// Write given statistics as CSV, one line per game after a header line.
+/// Columns are red, blue, winner ("red", "blue" or "draw"), repetition, moves,
+/// red_ms_per_move, blue_ms_per_move, red_blobs and blue_blobs.
+pub fn write_csv<W: Write>(games: &[GameStats], mut writer: W) -> io::Result<()> {
+    writeln!(
+        writer,
+        "red,blue,winner,repetition,moves,red_ms_per_move,blue_ms_per_move,red_blobs,blue_blobs"
+    )?;
+    for game in games {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{:.3},{:.3},{},{}",
+            quoted(&game.red),
+            quoted(&game.blue),
+            match game.winner {
+                Some(false) => "red",
+                Some(true) => "blue",
+                None => "draw",
+            },
+            game.repetition,
+            game.moves,
+            game.milliseconds_per_move[0],
+            game.milliseconds_per_move[1],
+            game.blob_counts[0],
+            game.blob_counts[1]
+        )?;
+    }
+    Ok(())
+}
+
+/// Write given statistics as a JSON array of objects with the fields of `GameStats`.
+#[cfg(feature = "serde")]
+pub fn write_json<W: Write>(games: &[GameStats], writer: W) -> io::Result<()> {
+    serde_json::to_writer_pretty(writer, games)?;
+    Ok(())
+}
+
+/// Without the serde feature JSON cannot be written.
+#[cfg(not(feature = "serde"))]
+pub fn write_json<W: Write>(_games: &[GameStats], _writer: W) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "JSON statistics need the serde feature",
+    ))
+}
+
+/// Write given statistics to given file : JSON if its name ends with ".json", CSV otherwise.
+pub fn save<P: AsRef<Path>>(games: &[GameStats], path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    let file = BufWriter::new(File::create(path)?);
+    if path.extension().is_some_and(|extension| extension == "json") {
+        write_json(games, file)
+    } else {
+        write_csv(games, file)
+    }
+}
@@ -1,186 +1,2084 @@
 //! Alpha - Beta algorithm.
 use std::fmt;
 
-use super::Strategy;
+use super::endgame::{solve_exact_with, ENDGAME_EMPTY_CELLS, ENDGAME_NODES};
+use super::ordering::MoveOrderer;
+use super::symmetry::distinct_movements;
+use super::ttable::{Entry, Flag, TranspositionTable};
+use super::{in_pool, BlobCount, Cancellation, Evaluator, Score, Strategy};
 use crate::configuration::{Configuration, Movement};
-use crate::shmem::AtomicMove;
+#[cfg(unix)]
+use crate::shmem::{AtomicMove, ShmemError};
+use crate::zobrist::splitmix64;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicI16, AtomicU64, AtomicU8, Ordering};
+use std::time::{Duration, Instant};
 
-use std::sync::Mutex;
+/// Bound of all windows : evaluations stay within `-INFINITY..=INFINITY` so they can be negated.
+pub(crate) const INFINITY: Score = Score::MAX;
 
+/// Half width of the aspiration windows used by iterative deepening (see `AlphaBeta::aspiration`).
+pub(crate) const ASPIRATION_DELTA: Score = 3;
+
+/// By default, parallel searches run nodes with at most this remaining depth sequentially :
+/// near the leaves there is too little work to pay for spawning tasks.
+pub const SEQUENTIAL_DEPTH: u8 = 2;
+
+#[derive(Debug, Copy, Clone)]
+/// How a search uses threads, and how the nodes it searches sequentially order their moves.
+pub(crate) struct Parallelism {
+    /// Size of a dedicated pool, the global rayon pool is used if None.
+    pub(crate) threads: Option<usize>,
+    /// Nodes with at most this remaining depth are searched sequentially.
+    pub(crate) sequential_depth: u8,
+    /// Do sequential nodes use killer moves (see `Search::killer_moves`) ?
+    pub(crate) killers: bool,
+    /// Do sequential nodes use the history heuristic (see `Search::history_heuristic`) ?
+    pub(crate) history: bool,
+    /// Do sequential nodes use principal variation search
+    /// (see `Search::principal_variation_search`) ?
+    pub(crate) pvs: bool,
+    /// Plies of quiescence search past the leaves (see `Search::quiescence`).
+    pub(crate) quiescence: u8,
+    /// Captures needed by a move to be searched in quiescence (see `Search::capture_threshold`).
+    pub(crate) capture_threshold: i8,
+    /// Depth reduction of null move pruning if enabled (see `Search::null_move`).
+    pub(crate) null_move: Option<u8>,
+    /// Schedule of late move reductions if enabled (see `Search::late_move_reductions`).
+    pub(crate) reductions: Option<LateMoveReductions>,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+/// How a search handles the moves of its root, besides ordering them by value.
+pub(crate) struct RootOptions {
+    /// Movement to search first, if legal (see `AlphaBeta::hint`).
+    pub(crate) hint: Option<Movement>,
+    /// Only search one move amongst the ones equivalent by symmetry (see `AlphaBeta::symmetric`).
+    pub(crate) symmetric: bool,
+    /// Window of the root (see `AlphaBeta::aspiration`), the full one if None.
+    pub(crate) window: Option<(Score, Score)>,
+}
+
+impl RootOptions {
+    /// Bounds of the root window.
+    fn window(self) -> (Score, Score) {
+        self.window.unwrap_or((-INFINITY, INFINITY))
+    }
+}
+
+impl Default for Parallelism {
+    fn default() -> Self {
+        Parallelism {
+            threads: None,
+            sequential_depth: SEQUENTIAL_DEPTH,
+            killers: false,
+            history: false,
+            pvs: false,
+            quiescence: 0,
+            capture_threshold: 1,
+            null_move: None,
+            reductions: None,
+        }
+    }
+}
+
+impl Parallelism {
+    /// Sequential search of a subtree, with the move ordering heuristics asked for.
+    fn sequential<'t, E: Evaluator>(self, search: Search<'t, E>) -> Search<'t, E> {
+        let search = if self.killers { search.killer_moves() } else { search };
+        let search = if self.history { search.history_heuristic() } else { search };
+        let search = if self.pvs { search.principal_variation_search() } else { search };
+        let search = match self.null_move {
+            Some(reduction) => search.null_move(reduction),
+            None => search,
+        };
+        let search = match self.reductions {
+            Some(reductions) => search.late_move_reductions(reductions),
+            None => search,
+        };
+        search
+            .quiescence(self.quiescence)
+            .capture_threshold(self.capture_threshold)
+    }
+}
 
 /// Alpha - Beta algorithm with given maximum number of recursions.
-pub struct AlphaBeta(pub u8);
+/// Leaves are evaluated with given `Evaluator` (blob count by default).
+/// With at most `ENDGAME_EMPTY_CELLS` empty cells left (see `AlphaBeta::endgame_below`) the
+/// game is solved instead when it can be done quickly (see `solve_exact`).
+/// Parallel searches run in the global rayon pool unless a number of threads is given.
+/// Given a margin (see `AlphaBeta::variety`), the move is picked at random amongst the nearly
+/// best ones.
+/// With a transposition table (see `AlphaBeta::transposition_table`) the search is sequential.
+pub struct AlphaBeta<E = BlobCount> {
+    depth: u8,
+    evaluator: E,
+    parallelism: Parallelism,
+    margin: Score,
+    seed: u64,
+    root: RootOptions,
+    table: Option<TranspositionTable>,
+    endgame: i8,
+}
+
+impl AlphaBeta {
+    /// New alpha - beta searching at given depth, counting blobs on leaves.
+    pub fn new(depth: u8) -> Self {
+        AlphaBeta {
+            depth,
+            evaluator: BlobCount,
+            parallelism: Parallelism::default(),
+            margin: 0,
+            seed: 0,
+            root: RootOptions::default(),
+            table: None,
+            endgame: ENDGAME_EMPTY_CELLS,
+        }
+    }
+
+    /// New alpha - beta searching at given depth with principal variation search
+    /// (see `Search::principal_variation_search`), counting blobs on leaves.
+    /// The parallel search already searches brothers of the first move with null windows,
+    /// this extends it to the subtrees searched sequentially.
+    pub fn pvs(depth: u8) -> Self {
+        let alphabeta = AlphaBeta::new(depth);
+        AlphaBeta {
+            parallelism: Parallelism {
+                pvs: true,
+                ..alphabeta.parallelism
+            },
+            ..alphabeta
+        }
+    }
+}
+
+impl<E: Evaluator> AlphaBeta<E> {
+    /// Evaluate leaves with given `Evaluator` instead.
+    /// This empties the transposition table, if any : its values came from the previous one.
+    pub fn evaluator<F: Evaluator>(self, evaluator: F) -> AlphaBeta<F> {
+        AlphaBeta {
+            depth: self.depth,
+            evaluator,
+            parallelism: self.parallelism,
+            margin: self.margin,
+            seed: self.seed,
+            root: self.root,
+            table: self.table.map(|_| TranspositionTable::new()),
+            endgame: self.endgame,
+        }
+    }
+
+    /// Run the search in a dedicated pool of given number of threads
+    /// (1 gives a reproducible single threaded run).
+    pub fn threads(self, threads: usize) -> Self {
+        AlphaBeta {
+            parallelism: Parallelism {
+                threads: Some(threads),
+                ..self.parallelism
+            },
+            ..self
+        }
+    }
+
+    /// Search nodes with at most given remaining depth sequentially instead of in parallel
+    /// (`SEQUENTIAL_DEPTH` by default). This never changes the chosen move.
+    pub fn sequential_below(self, depth: u8) -> Self {
+        AlphaBeta {
+            parallelism: Parallelism {
+                sequential_depth: depth,
+                ..self.parallelism
+            },
+            ..self
+        }
+    }
+
+    /// For less predictable games, pick uniformly at random amongst all moves whose value is
+    /// at most `margin` below the best one (values are blob differences).
+    /// All moves of the root then need to be searched exactly, which prunes less.
+    /// Choices are drawn from a generator seeded with `seed` so that games can be reproduced.
+    /// A margin of 0 (the default) always plays the best move, the first generated one on ties.
+    /// Endgame configurations (see `ENDGAME_EMPTY_CELLS`) always get the best move found.
+    pub fn variety(self, margin: Score, seed: u64) -> Self {
+        AlphaBeta {
+            margin,
+            seed,
+            ..self
+        }
+    }
+
+    /// Search given movement first at the root, if legal there.
+    /// Iterative deepening gives the best move of the previous iteration : it is likely to still
+    /// be the best one and finding its value early prunes more. The chosen move is unchanged.
+    pub fn hint(self, movement: Option<Movement>) -> Self {
+        AlphaBeta {
+            root: RootOptions {
+                hint: movement,
+                ..self.root
+            },
+            ..self
+        }
+    }
+
+    /// Amongst equally promising moves, search first the ones which recently caused a cutoff at
+    /// the same ply (see `Search::killer_moves`).
+    /// Parallel searches only do so near the leaves, in the subtrees searched sequentially
+    /// (see `AlphaBeta::sequential_below`), each thread keeping them from one subtree to the next.
+    /// This only prunes more, the chosen move is unchanged.
+    pub fn killer_moves(self) -> Self {
+        AlphaBeta {
+            parallelism: Parallelism {
+                killers: true,
+                ..self.parallelism
+            },
+            ..self
+        }
+    }
+
+    /// Amongst equally promising moves (and after killer moves), search first the ones which
+    /// caused the most cutoffs so far (see `Search::history_heuristic`).
+    /// Like killer moves, parallel searches only do so in the subtrees searched sequentially,
+    /// each thread keeping its table for the whole search.
+    /// This only prunes more, the chosen move is unchanged.
+    pub fn history_heuristic(self) -> Self {
+        AlphaBeta {
+            parallelism: Parallelism {
+                history: true,
+                ..self.parallelism
+            },
+            ..self
+        }
+    }
+
+    /// Do not evaluate leaves right away but keep on searching moves converting at least
+    /// `capture_threshold` blobs, for at most `depth` more plies (see `Search::quiescence`).
+    /// A move converting many blobs just past the leaves is then not missed anymore.
+    pub fn quiescence(self, depth: u8, capture_threshold: i8) -> Self {
+        AlphaBeta {
+            parallelism: Parallelism {
+                quiescence: depth,
+                capture_threshold,
+                ..self.parallelism
+            },
+            ..self
+        }
+    }
+
+    /// Null move pruning with given depth reduction (see `Search::null_move`).
+    /// This is not safe : blobwar has configurations where passing would be the best move, it
+    /// can miss some moves and change the chosen one.
+    pub fn null_move(self, reduction: u8) -> Self {
+        AlphaBeta {
+            parallelism: Parallelism {
+                null_move: Some(reduction),
+                ..self.parallelism
+            },
+            ..self
+        }
+    }
+
+    /// Late move reductions with given schedule (see `Search::late_move_reductions`).
+    /// This can change the chosen move.
+    pub fn late_move_reductions(self, reductions: LateMoveReductions) -> Self {
+        AlphaBeta {
+            parallelism: Parallelism {
+                reductions: Some(reductions),
+                ..self.parallelism
+            },
+            ..self
+        }
+    }
+
+    /// Try solving configurations with at most given number of empty cells
+    /// (`ENDGAME_EMPTY_CELLS` by default) whatever the depth (see `solve_exact`) : the move
+    /// played is then proven best. The solver gives up after `ENDGAME_NODES` nodes and the
+    /// usual search runs instead. A negative number never tries.
+    pub fn endgame_below(self, empty_cells: i8) -> Self {
+        AlphaBeta {
+            endgame: empty_cells,
+            ..self
+        }
+    }
+
+    /// Remember searched configurations (by Zobrist hash) with their value or bound, so that
+    /// configurations reached again by other move orders are not searched again.
+    /// The table is kept from one move to the next : configurations searched at the previous
+    /// move are found again two plies deeper.
+    /// The search is then sequential (the table is not shared between threads) and values
+    /// found earlier at a higher depth may be re-used, which can change the chosen move.
+    pub fn transposition_table(self) -> Self {
+        AlphaBeta {
+            table: Some(TranspositionTable::new()),
+            ..self
+        }
+    }
+
+    /// Aspiration window : expect the value to be within `delta` of given guess (typically the
+    /// value found by the previous iteration of iterative deepening) and search the root with
+    /// this narrow window, which prunes more.
+    /// If the value turns out to lie outside, the search is run again with the window widened
+    /// on the failing side. The chosen move and its value are unchanged.
+    pub fn aspiration(self, guess: Score, delta: Score) -> Self {
+        let low = std::cmp::max(guess.saturating_sub(delta), -INFINITY);
+        let high = guess.saturating_add(delta);
+        AlphaBeta {
+            root: RootOptions {
+                window: Some((low, high)),
+                ..self.root
+            },
+            ..self
+        }
+    }
+
+    /// When the configuration is symmetric (like most boards at the start), search only one
+    /// of the root moves equivalent by symmetry : the first generated one, which is the one
+    /// picked amongst them anyway. The chosen move and its value are unchanged as long as the
+    /// evaluator does not care about orientation (like `BlobCount`, `Mobility` and
+    /// `WeightedEval`).
+    pub fn symmetric(self) -> Self {
+        AlphaBeta {
+            root: RootOptions {
+                symmetric: true,
+                ..self.root
+            },
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Schedule of late move reductions (see `Search::late_move_reductions`) : which moves are
+/// searched at a lower depth and how much lower.
+pub struct LateMoveReductions {
+    /// Number of moves of each node (the most promising ones) searched at full depth.
+    pub full_depth_moves: usize,
+    /// Nodes with less remaining depth are not reduced.
+    pub min_depth: u8,
+    /// Plies removed from the depth of reduced moves.
+    pub reduction: u8,
+}
+
+impl Default for LateMoveReductions {
+    fn default() -> Self {
+        LateMoveReductions {
+            full_depth_moves: 4,
+            min_depth: 3,
+            reduction: 1,
+        }
+    }
+}
+
+impl LateMoveReductions {
+    /// Plies to remove from the search of the move of given rank (in the ordering) in a node
+    /// of given remaining depth, None if it is searched at full depth.
+    fn reduction(self, rank: usize, depth: u8) -> Option<u8> {
+        let reduction = std::cmp::min(self.reduction, depth - 1);
+        (rank >= self.full_depth_moves && depth >= self.min_depth && reduction > 0)
+            .then_some(reduction)
+    }
+}
 
-fn alpha_beta_par(
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+/// What a search did.
+pub struct SearchStats {
+    /// Number of nodes visited.
+    pub nodes: u64,
+    /// Number of nodes whose remaining moves were pruned.
+    pub cutoffs: u64,
+    /// Time spent searching.
+    pub elapsed: Duration,
+    /// Depth of the deepest node visited (in plies from the root).
+    pub max_depth_reached: u8,
+    /// Number of times the value fell below the aspiration window (see `AlphaBeta::aspiration`)
+    /// and the root was searched again.
+    pub fail_lows: u64,
+    /// Number of times the value rose above the aspiration window and the root was searched
+    /// again.
+    pub fail_highs: u64,
+}
+
+#[cfg(feature = "parallel")]
+/// Counters shared by all threads of a parallel search.
+#[derive(Default)]
+struct Counters {
+    nodes: AtomicU64,
+    cutoffs: AtomicU64,
+    max_depth_reached: AtomicU8,
+}
+
+#[cfg(feature = "parallel")]
+/// Identifier of the next parallel search.
+static NEXT_SEARCH: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(feature = "parallel")]
+/// Everything a parallel search needs besides the node and its window.
+struct Context<'c, E> {
+    /// Identifies the search (see `NEXT_SEARCH`) : sequential subtrees searched by the same
+    /// thread for the same search share their killer moves and history.
+    id: u64,
+    root_depth: u8,
+    parallelism: Parallelism,
+    root: RootOptions,
+    evaluator: &'c E,
+    cancellation: &'c Cancellation,
+    counters: Option<&'c Counters>,
+}
+
+#[cfg(feature = "parallel")]
+impl<'c, E: Evaluator> Context<'c, E> {
+    fn visit(&self, depth: u8) {
+        if let Some(counters) = self.counters {
+            counters.nodes.fetch_add(1, Ordering::Relaxed);
+            counters
+                .max_depth_reached
+                .fetch_max(self.root_depth - depth, Ordering::Relaxed);
+        }
+    }
+
+    fn cutoff(&self) {
+        if let Some(counters) = self.counters {
+            counters.cutoffs.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+/// Parallel negamax at the root : return the best move and its exact value.
+/// Like the sequential search, amongst equally valued moves the first one in generation
+/// order is returned.
+/// The most promising move is searched first, then all others in parallel. To detect ties,
+/// siblings are searched with a lower bound just below the best value found so far.
+/// With a narrow root window, failing high or low returns a bound beyond it instead.
+fn negamax_root<E: Evaluator>(state: &Configuration, context: &Context<E>) -> (Option<Movement>, Score) {
+    let depth = context.root_depth;
+    context.visit(depth);
+    if depth == 0 || state.is_terminal() {
+        return (None, leaf_value(state, context.evaluator));
+    }
+    let movements = order_root(state, context.root);
+    if movements.is_empty() {
+        // stuck : pass and let the other player go on
+        return (None, -negamax_par(&state.pass(), depth - 1, -INFINITY, INFINITY, context));
+    }
+    let (&(first_index, first), others) = movements.split_first().unwrap();
+
+    let (alpha, beta) = context.root.window();
+    let search = |child: &Configuration, alpha: Score, beta: Score| {
+        -negamax_par(child, depth - 1, -beta, -alpha, context)
+    };
+    let first_value = search(&state.play(&first), alpha, beta);
+    if first_value >= beta && beta < INFINITY {
+        return (Some(first), first_value);
+    }
+    let shared_alpha = AtomicI16::new(first_value);
+    let (_, best, value) = others
+        .par_iter()
+        .map(|&(index, movement)| {
+            let child = state.play(&movement);
+            let lower_bound = std::cmp::max(shared_alpha.load(Ordering::Relaxed) - 1, -INFINITY);
+            let mut value = search(&child, lower_bound, lower_bound + 1);
+            if value > lower_bound {
+                let lower_bound = std::cmp::max(shared_alpha.load(Ordering::Relaxed) - 1, -INFINITY);
+                value = search(&child, lower_bound, beta);
+                shared_alpha.fetch_max(value, Ordering::Relaxed);
+            }
+            (index, movement, value)
+        })
+        .chain(rayon::iter::once((first_index, first, first_value)))
+        .max_by_key(|&(index, _, value)| (value, Reverse(index)))
+        .unwrap();
+    (Some(best), value)
+}
+
+#[cfg(feature = "parallel")]
+/// Parallel negamax (fail-soft alpha - beta) in the spirit of "young brothers wait" :
+/// the most promising child is searched first to narrow the window, then its brothers are
+/// searched in parallel, sharing the best bound found so far.
+/// Brothers are first searched with a null window and searched again with the full window if
+/// they beat the shared bound.
+/// Once cancelled, nodes are not expanded anymore and evaluated as leaves.
+/// Close enough to the leaves we switch to the sequential search.
+fn negamax_par<E: Evaluator>(
     node: &Configuration,
     depth: u8,
-    alpha: i8,
-    beta: i8,
-    maximizing_player: bool,
-) -> (Option<Movement>, i8) {
-    if depth == 0 || node.movements().next().is_none() {
-        if maximizing_player == node.current_player {
-            return (None, -node.value());
-        } else {
-            return (None, node.value());
-        };
+    mut alpha: Score,
+    beta: Score,
+    context: &Context<E>,
+) -> Score {
+    if depth <= context.parallelism.sequential_depth {
+        return negamax_seq(node, depth, alpha, beta, context);
+    }
+    context.visit(depth);
+    if depth == 0 || node.is_terminal() || context.cancellation.expired() {
+        return leaf_value(node, context.evaluator);
     }
 
-    let movements: Vec<Movement> = node.movements().collect();
+    if node.movements().next().is_none() {
+        // stuck : pass and let the other player go on
+        return -negamax_par(&node.pass(), depth - 1, -beta, -alpha, context);
+    }
+    with_buffer(|movements| {
+        order_movements(node, None, None, &[], None, movements);
+        let (&(.., first), others) = movements.split_first().unwrap();
+        let search = |child: &Configuration, alpha: Score, beta: Score| {
+            -negamax_par(child, depth - 1, -beta, -alpha, context)
+        };
+        let first_value = search(&node.play(&first), alpha, beta);
 
-    if maximizing_player == node.current_player {
-        movements
-            .into_par_iter()
-            .map_init(|| (i8::MIN, i8::MAX), |(local_alpha, _), child| {
-                let (_, value) = alpha_beta_par(&node.play(&child), depth - 1, *local_alpha, beta, maximizing_player);
-                if value > *local_alpha {
-                    *local_alpha = value;
+        alpha = std::cmp::max(alpha, first_value);
+        if alpha >= beta {
+            context.cutoff();
+            return first_value;
+        }
+        let shared_alpha = AtomicI16::new(alpha);
+        let value = others
+            .par_iter()
+            .filter_map(|&(.., movement)| {
+                let null_alpha = shared_alpha.load(Ordering::Relaxed);
+                if null_alpha >= beta {
+                    return None;
                 }
-                (Some(child.clone()), value)
-            })
-            .reduce_with(|(child1, value1), (child2, value2)| {
-                if value1 > value2 {
-                    (child1, value1)
-                } else {
-                    (child2, value2)
+                let child = node.play(&movement);
+                let mut value = search(&child, null_alpha, null_alpha + 1);
+                // failing high is only a lower bound : get the exact value in the best window
+                // known now
+                if value > null_alpha && value < beta {
+                    value = search(&child, shared_alpha.load(Ordering::Relaxed), beta);
                 }
+                shared_alpha.fetch_max(value, Ordering::Relaxed);
+                Some(value)
             })
-            .unwrap_or((None, i8::MIN))
-    } else {
-        movements
-            .into_par_iter()
-            .map_init(|| (i8::MIN, i8::MAX), |(_, local_beta), child| {
-                let (_, value) = alpha_beta_par(&node.play(&child), depth - 1, alpha, *local_beta, maximizing_player);
-                if value < *local_beta {
-                    *local_beta = value;
-                }
-                (Some(child.clone()), value)
-            })
-            .reduce_with(|(child1, value1), (child2, value2)| {
-                if value1 < value2 {
-                    (child1, value1)
-                } else {
-                    (child2, value2)
-                }
-            })
-            .unwrap_or((None, i8::MAX))
+            .max()
+            .map_or(first_value, |value| std::cmp::max(value, first_value));
+        if value >= beta {
+            context.cutoff();
+        }
+        value
+    })
+}
+
+#[cfg(feature = "parallel")]
+#[derive(Default)]
+/// Move ordering heuristics learnt by the sequential subtrees a thread searched for a parallel
+/// search.
+struct Heuristics {
+    /// Search they were learnt for (see `Context::id`).
+    search: u64,
+    killers: Option<Killers>,
+    history: Option<History>,
+}
+
+#[cfg(feature = "parallel")]
+thread_local! {
+    /// Heuristics of the last parallel search this thread worked for.
+    static HEURISTICS: RefCell<Heuristics> = RefCell::new(Heuristics::default());
+}
+
+#[cfg(feature = "parallel")]
+/// Sequential negamax of given subtree of a parallel search, with the same move ordering.
+/// Killer moves and the history table are kept from one subtree to the next by each thread :
+/// siblings searched by other threads do not share them but each thread still sees most of them,
+/// and history tables are only allocated once per thread.
+fn negamax_seq<E: Evaluator>(
+    node: &Configuration,
+    depth: u8,
+    alpha: Score,
+    beta: Score,
+    context: &Context<E>,
+) -> Score {
+    let parallelism = context.parallelism;
+    let mut search = Parallelism {
+        killers: false,
+        history: false,
+        ..parallelism
+    }
+    .sequential(Search::new())
+    .evaluator(context.evaluator)
+    .cancellation(context.cancellation);
+    // plies are counted from the root of the whole search, like the killers they index
+    search.root_depth = context.root_depth;
+    HEURISTICS.with(|heuristics| {
+        let mut heuristics = heuristics.borrow_mut();
+        if heuristics.search != context.id {
+            *heuristics = Heuristics {
+                search: context.id,
+                ..Default::default()
+            };
+        }
+        search.killers = parallelism
+            .killers
+            .then(|| heuristics.killers.take().unwrap_or_default());
+        search.history = parallelism
+            .history
+            .then(|| heuristics.history.take().unwrap_or_default());
+    });
+    let value = negamax(node, depth, alpha, beta, &mut search, &mut Vec::new());
+    HEURISTICS.with(|heuristics| {
+        let mut heuristics = heuristics.borrow_mut();
+        heuristics.killers = search.killers.take();
+        heuristics.history = search.history.take();
+    });
+    if let Some(counters) = context.counters {
+        counters.nodes.fetch_add(search.nodes, Ordering::Relaxed);
+        counters.cutoffs.fetch_add(search.cutoffs, Ordering::Relaxed);
+        counters
+            .max_depth_reached
+            .fetch_max(search.max_depth_reached, Ordering::Relaxed);
+    }
+    value
+}
+
+/// Killer moves are remembered for at most this number of plies.
+const MAX_DEPTH: usize = 64;
+
+/// Killer moves : for each ply, the last two moves which caused a cutoff.
+/// They are likely to also be good in sibling nodes so they are searched first there,
+/// amongst moves of equal immediate value.
+struct Killers([[Option<Movement>; 2]; MAX_DEPTH]);
+
+impl Default for Killers {
+    fn default() -> Self {
+        Killers([[None; 2]; MAX_DEPTH])
+    }
+}
+
+impl Killers {
+    /// Killers of given ply.
+    fn get(&self, ply: u8) -> &[Option<Movement>] {
+        self.0.get(ply as usize).map_or(&[], |killers| &killers[..])
+    }
+
+    /// Remember given movement caused a cutoff at given ply.
+    fn store(&mut self, ply: u8, movement: Movement) {
+        if let Some(killers) = self.0.get_mut(ply as usize) {
+            if killers[0] != Some(movement) {
+                killers[1] = killers[0];
+                killers[0] = Some(movement);
+            }
+        }
+    }
+}
+
+/// History heuristic : for each move (by source and destination cells), how much it caused
+/// cutoffs, anywhere in the tree. Cutoffs far from the leaves weigh more.
+/// Clones have no source, we index them by their destination twice (jumps never do that).
+struct History(Box<[[u32; 64]; 64]>);
+
+impl Default for History {
+    fn default() -> Self {
+        History(Box::new([[0; 64]; 64]))
+    }
+}
+
+impl History {
+    /// Score of given movement, the higher the better.
+    fn get(&self, movement: &Movement) -> u32 {
+        let destination = movement.destination();
+        let source = movement.source().unwrap_or(destination);
+        self.0[source as usize][destination as usize]
     }
+
+    /// Remember given movement caused a cutoff with given remaining depth.
+    fn store(&mut self, movement: &Movement, depth: u8) {
+        let destination = movement.destination();
+        let source = movement.source().unwrap_or(destination);
+        let score = &mut self.0[source as usize][destination as usize];
+        *score = score.saturating_add(depth as u32 * depth as u32);
+    }
+}
+
+/// Bookkeeping shared by all nodes of a sequential alpha - beta search.
+/// Leaves are evaluated with given `Evaluator` (blob count by default).
+#[derive(Default)]
+pub struct Search<'t, E = BlobCount> {
+    /// Number of nodes visited so far.
+    pub nodes: u64,
+    /// Number of nodes whose remaining moves were pruned.
+    pub cutoffs: u64,
+    /// Depth of the deepest node visited (in plies from the root of the last run).
+    pub max_depth_reached: u8,
+    /// Number of times the moves of a node were generated (see `Search::move_cache`).
+    pub generations: u64,
+    table: Option<&'t mut TranspositionTable>,
+    evaluator: E,
+    quiescence: u8,
+    capture_threshold: i8,
+    cancellation: Option<&'t Cancellation>,
+    killers: Option<Killers>,
+    history: Option<History>,
+    root: RootOptions,
+    fail_hard: bool,
+    pvs: bool,
+    null_move: Option<u8>,
+    reductions: Option<LateMoveReductions>,
+    /// Was the move leading to the current node a null move ?
+    after_null_move: bool,
+    root_depth: u8,
+    move_cache: bool,
+    orderer: Option<&'t dyn MoveOrderer>,
 }
 
-fn alpha_beta(
+impl<'t> Search<'t> {
+    /// New search without transposition table.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// New search storing and re-using results in given `TranspositionTable`.
+    pub fn with_table(table: &'t mut TranspositionTable) -> Self {
+        Search {
+            table: Some(table),
+            ..Default::default()
+        }
+    }
+}
+
+impl<'t, E: Evaluator> Search<'t, E> {
+    /// Evaluate leaves with given `Evaluator` instead.
+    /// A transposition table should not be shared between searches using different evaluators.
+    pub fn evaluator<F: Evaluator>(self, evaluator: F) -> Search<'t, F> {
+        Search {
+            nodes: self.nodes,
+            cutoffs: self.cutoffs,
+            max_depth_reached: self.max_depth_reached,
+            generations: self.generations,
+            table: self.table,
+            evaluator,
+            quiescence: self.quiescence,
+            capture_threshold: self.capture_threshold,
+            cancellation: self.cancellation,
+            killers: self.killers,
+            history: self.history,
+            root: self.root,
+            fail_hard: self.fail_hard,
+            pvs: self.pvs,
+            null_move: self.null_move,
+            reductions: self.reductions,
+            after_null_move: self.after_null_move,
+            root_depth: self.root_depth,
+            move_cache: self.move_cache,
+            orderer: self.orderer,
+        }
+    }
+
+    /// Once cancelled, nodes are not expanded anymore and evaluated as leaves.
+    pub(crate) fn cancellation(self, cancellation: &'t Cancellation) -> Self {
+        Search {
+            cancellation: Some(cancellation),
+            ..self
+        }
+    }
+
+    fn expired(&self) -> bool {
+        self.cancellation.is_some_and(|cancellation| cancellation.expired())
+    }
+
+    /// Amongst equally promising moves, search first the killer moves : the ones which recently
+    /// caused a cutoff at the same ply.
+    /// This only prunes more, the chosen move is unchanged.
+    pub fn killer_moves(self) -> Self {
+        Search {
+            killers: Some(Killers::default()),
+            ..self
+        }
+    }
+
+    /// Amongst equally promising moves (and after killer moves), search first the ones which
+    /// caused the most cutoffs so far : the history heuristic.
+    /// This only prunes more, the chosen move is unchanged.
+    pub fn history_heuristic(self) -> Self {
+        Search {
+            history: Some(History::default()),
+            ..self
+        }
+    }
+
+    /// Clamp all values returned by nodes to their window (fail-hard alpha - beta) instead of
+    /// returning bounds beyond it (fail-soft, the default).
+    /// Both find the same move and value, fail-soft bounds are just tighter which helps the
+    /// transposition table.
+    pub fn fail_hard(self) -> Self {
+        Search {
+            fail_hard: true,
+            ..self
+        }
+    }
+
+    /// Principal variation search (NegaScout) : only the first move of each node is searched
+    /// with the full window. The others are expected to be worse and first searched with a
+    /// null window just proving it, they are searched again with the full window when they
+    /// turn out better. With good move ordering this can prune more (not so much with coarse
+    /// evaluations like blob counts, full of ties). The chosen move is unchanged.
+    pub fn principal_variation_search(self) -> Self {
+        Search { pvs: true, ..self }
+    }
+
+    /// Null move pruning : before searching the moves of a node, let the other player play
+    /// twice in a row by passing, and search that with given depth reduction and a null window.
+    /// If we still reach beta, a real move would do even better and the node is cut.
+    /// Passing is usually the worst move but not always, so this can miss good moves.
+    /// It is never done at the root nor while searching another null move.
+    pub fn null_move(self, reduction: u8) -> Self {
+        Search {
+            null_move: Some(reduction),
+            ..self
+        }
+    }
+
+    /// Late move reductions : search moves coming late in the ordering at a lower depth
+    /// (as given by the schedule) and only search them again at full depth if they beat alpha.
+    /// Like null move pruning, this can miss good moves.
+    pub fn late_move_reductions(self, reductions: LateMoveReductions) -> Self {
+        Search {
+            reductions: Some(reductions),
+            ..self
+        }
+    }
+
+    /// Generate the moves of each node once and re-use them to check whether it is terminal,
+    /// whether the player has to pass and to order them, instead of generating them for each.
+    /// Results are unchanged, only `Search::generations` gets lower.
+    pub fn move_cache(self) -> Self {
+        Search {
+            move_cache: true,
+            ..self
+        }
+    }
+
+    /// Order the moves of nodes below the root with given `MoveOrderer` instead of by their
+    /// immediate value (killer moves and the history heuristic, if enabled, still come first).
+    /// The chosen move and its value are unchanged.
+    pub fn orderer(self, orderer: &'t dyn MoveOrderer) -> Self {
+        Search {
+            orderer: Some(orderer),
+            ..self
+        }
+    }
+
+    /// Search given movement first at the root, if legal there (see `AlphaBeta::hint`).
+    pub fn hint(self, movement: Option<Movement>) -> Self {
+        Search {
+            root: RootOptions {
+                hint: movement,
+                ..self.root
+            },
+            ..self
+        }
+    }
+
+    /// Only search one of the root moves equivalent by symmetry (see `AlphaBeta::symmetric`).
+    pub fn symmetric(self) -> Self {
+        Search {
+            root: RootOptions {
+                symmetric: true,
+                ..self.root
+            },
+            ..self
+        }
+    }
+
+    /// Search the root with given window instead of the full one : failing low returns an
+    /// upper bound and failing high a lower bound, only values strictly inside are exact.
+    pub(crate) fn set_window(&mut self, alpha: Score, beta: Score) {
+        self.root.window = Some((alpha, beta));
+    }
+
+    /// Instead of evaluating leaves right away, keep on searching capturing moves
+    /// (up to given number of plies) until a quiet configuration is reached.
+    /// This avoids stopping the search in the middle of an exchange.
+    /// 0 (the default) disables quiescence search.
+    pub fn quiescence(self, depth: u8) -> Self {
+        Search {
+            quiescence: depth,
+            ..self
+        }
+    }
+
+    /// Only search moves converting at least given number of blobs during quiescence search
+    /// (1 by default) : configurations where no move converts that many are quiet enough.
+    pub fn capture_threshold(self, captures: i8) -> Self {
+        Search {
+            capture_threshold: captures,
+            ..self
+        }
+    }
+
+    /// Run a sequential alpha - beta of given depth for the player about to play.
+    /// Return best move and its value.
+    /// Moves are searched best first but amongst equally valued moves the first one in
+    /// generation order is returned, exactly as without ordering.
+    pub fn run(&mut self, state: &Configuration, depth: u8) -> (Option<Movement>, Score) {
+        let (line, value) = self.principal_variation(state, depth);
+        (line.first().copied(), value)
+    }
+
+    /// Like `run` but return the whole line of moves expected from both players.
+    /// Without quiescence search, playing it from `state` leads to a configuration whose value
+    /// is the returned one.
+    /// The line can be cut short by transposition table hits.
+    pub fn principal_variation(&mut self, state: &Configuration, depth: u8) -> (Vec<Movement>, Score) {
+        let mut line = Vec::new();
+        self.root_depth = depth;
+        if depth == 0 || state.is_terminal() || state.movements().next().is_none() {
+            // leaves and passes are handled by negamax
+            let value = negamax(state, depth, -INFINITY, INFINITY, self, &mut line);
+            return (line, value);
+        }
+        self.nodes += 1;
+        let movements = order_root(state, self.root);
+
+        // with a narrow window, failing low returns the best upper bound and failing high
+        // the first lower bound above the window
+        let (low, beta) = self.root.window();
+        let mut alpha = low;
+        let mut best_index = None;
+        let mut child_line = Vec::new();
+        for (index, movement) in movements {
+            // moves generated before the current best one also need to detect equality
+            let earlier = best_index.is_some_and(|best_index| index < best_index);
+            let lower_bound = if earlier { std::cmp::max(alpha - 1, -INFINITY) } else { alpha };
+            // below the window everything is a failure anyway
+            let lower_bound = std::cmp::max(lower_bound, low);
+            let value = -negamax(
+                &state.play(&movement),
+                depth - 1,
+                -beta,
+                -lower_bound,
+                self,
+                &mut child_line,
+            );
+            if value > alpha || best_index.is_none() || (earlier && value == alpha) {
+                alpha = value;
+                best_index = Some(index);
+                extend_line(&mut line, movement, &child_line);
+            }
+            if alpha >= beta && beta < INFINITY {
+                break;
+            }
+        }
+        (line, alpha)
+    }
+}
+
+/// Return the principal variation : the sequence of moves expected from both players
+/// when searching at given depth.
+pub fn principal_variation(state: &Configuration, depth: u8) -> Vec<Movement> {
+    Search::new().principal_variation(state, depth).0
+}
+
+/// Movements of the root with their generation index, most promising first : given hint if
+/// it is one of them and then by immediate value.
+/// Generation indices let root searches return the same move whatever the order.
+/// Moves equivalent by symmetry to earlier generated ones are dropped if asked to.
+fn order_root(state: &Configuration, root: RootOptions) -> Vec<(usize, Movement)> {
+    let mut movements: Vec<(usize, Movement)> = state.movements().enumerate().collect();
+    if root.symmetric {
+        distinct_movements(state, &mut movements);
+    }
+    movements.sort_by_cached_key(|&(_, movement)| {
+        (Some(movement) != root.hint, Reverse(state.play(&movement).value()))
+    });
+    movements
+}
+
+/// A movement with what we sort it by : immediate value, killer rank, history score and
+/// generation order.
+type Candidate = (Reverse<i8>, usize, Reverse<u32>, usize, Movement);
+
+thread_local! {
+    /// Movement buffers not in use by a node of this thread.
+    static BUFFERS: RefCell<Vec<Vec<Candidate>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Run `f` with an empty buffer, re-using the ones of already searched nodes
+/// to avoid allocating at each node.
+fn with_buffer<R, F: FnOnce(&mut Vec<Candidate>) -> R>(f: F) -> R {
+    let mut buffer = BUFFERS.with(|buffers| buffers.borrow_mut().pop()).unwrap_or_default();
+    buffer.clear();
+    let result = f(&mut buffer);
+    BUFFERS.with(|buffers| buffers.borrow_mut().push(buffer));
+    result
+}
+
+/// Fill `movements` so that the most promising ones for the player about to play come first.
+/// They are generated unless already `cached` (in generation order).
+/// We just look at the immediate value of the resulting configuration, or follow given
+/// orderer instead.
+/// Amongst equally valued moves, given killers come first, then the best ones in given history
+/// and then generation (or orderer) order is kept.
+fn order_movements(
+    node: &Configuration,
+    cached: Option<&[Movement]>,
+    orderer: Option<&dyn MoveOrderer>,
+    killers: &[Option<Movement>],
+    history: Option<&History>,
+    movements: &mut Vec<Candidate>,
+) {
+    let candidate = |(index, movement): (usize, Movement)| {
+        let rank = killers
+            .iter()
+            .position(|&killer| killer == Some(movement))
+            .unwrap_or(killers.len());
+        let score = history.map_or(0, |history| history.get(&movement));
+        let value = if orderer.is_some() { 0 } else { node.play(&movement).value() };
+        (Reverse(value), rank, Reverse(score), index, movement)
+    };
+    match (orderer, cached) {
+        (Some(orderer), _) => {
+            let mut ordered: Vec<Movement> = match cached {
+                Some(cached) => cached.to_vec(),
+                None => node.movements().collect(),
+            };
+            orderer.order(node, &mut ordered);
+            movements.extend(ordered.into_iter().enumerate().map(candidate));
+        }
+        (None, Some(cached)) => movements.extend(cached.iter().copied().enumerate().map(candidate)),
+        (None, None) => movements.extend(node.movements().enumerate().map(candidate)),
+    }
+    movements.sort_unstable_by_key(|&(value, rank, score, index, _)| (value, rank, score, index));
+}
+
+/// Replace `line` by `movement` followed by `child_line`.
+fn extend_line(line: &mut Vec<Movement>, movement: Movement, child_line: &[Movement]) {
+    line.clear();
+    line.push(movement);
+    line.extend_from_slice(child_line);
+}
+
+/// Sequential negamax, used as the reference search.
+/// Values are seen from the player about to play in `node`.
+/// The exact value is returned if it lies strictly inside the window.
+/// Otherwise, fail-soft, we return a bound which can lie beyond the window : at least the value
+/// on a cutoff (value >= beta), at most the value when all moves fail low (value <= alpha).
+/// With `Search::fail_hard`, bounds are clamped to the window instead.
+/// The best line found is stored in `line` (left empty on leaves).
+fn negamax<E: Evaluator>(
     node: &Configuration,
     depth: u8,
-    mut alpha: i8,
-    mut beta: i8,
-    maximizing_player: bool,
-) -> (Option<Movement>, i8) {
-    if depth == 0 || node.movements().next().is_none() {
-        if maximizing_player == node.current_player {
-            return (None, -node.value());
-        } else {
-            return (None, node.value());
-        };
+    alpha: Score,
+    beta: Score,
+    search: &mut Search<E>,
+    line: &mut Vec<Movement>,
+) -> Score {
+    let value = negamax_soft(node, depth, alpha, beta, search, line);
+    if search.fail_hard {
+        value.clamp(alpha, beta)
+    } else {
+        value
     }
+}
 
-    let movements: Vec<Movement> = node.movements().collect();
+/// Fail-soft alpha - beta behind `negamax`.
+/// Movements are ordered best first to get cutoffs early.
+/// A move only replaces the current best one if strictly better.
+/// The transposition table stores the best value along with what it is : exact, lower bound
+/// (it caused a cutoff) or upper bound (no move beat alpha).
+fn negamax_soft<E: Evaluator>(
+    node: &Configuration,
+    depth: u8,
+    mut alpha: Score,
+    mut beta: Score,
+    search: &mut Search<E>,
+    line: &mut Vec<Movement>,
+) -> Score {
+    search.nodes += 1;
+    search.max_depth_reached = std::cmp::max(search.max_depth_reached, search.root_depth - depth);
+    line.clear();
+    if depth == 0 && search.quiescence > 0 {
+        let quiescence = search.quiescence;
+        return quiesce(node, quiescence, alpha, beta, search);
+    }
+    if depth == 0 || node.game_over() || search.expired() {
+        return leaf_value(node, &search.evaluator);
+    }
+    // without the move cache, each check below generates the moves again
+    let cache = search.move_cache.then(|| {
+        search.generations += 1;
+        node.movements().collect::<Vec<_>>()
+    });
+    let terminal = match &cache {
+        Some(movements) => movements.is_empty() && node.pass().movements().next().is_none(),
+        None => {
+            search.generations += 1;
+            node.is_terminal()
+        }
+    };
+    if terminal {
+        return leaf_value(node, &search.evaluator);
+    }
+    let stuck = match &cache {
+        Some(movements) => movements.is_empty(),
+        None => {
+            search.generations += 1;
+            node.movements().next().is_none()
+        }
+    };
+    if stuck {
+        // stuck : pass and let the other player go on, the line stops there
+        let value = -negamax(&node.pass(), depth - 1, -beta, -alpha, search, line);
+        line.clear();
+        return value;
+    }
 
-    if maximizing_player == node.current_player {
-        let mut best_value = i8::MIN;
-        let mut best_child = None;
+    if let Some(entry) = search.table.as_ref().and_then(|table| table.get(node)) {
+        if entry.depth >= depth {
+            match entry.flag {
+                Flag::Exact => {
+                    line.extend(entry.movement);
+                    return entry.value;
+                }
+                Flag::LowerBound => alpha = std::cmp::max(alpha, entry.value),
+                Flag::UpperBound => beta = std::cmp::min(beta, entry.value),
+            }
+            if alpha >= beta {
+                line.extend(entry.movement);
+                return entry.value;
+            }
+        }
+    }
+    let (original_alpha, original_beta) = (alpha, beta);
 
-        for child in movements {
-            let (_, child_value) = alpha_beta(&node.play(&child), depth - 1, alpha, beta, maximizing_player);
+    let ply = search.root_depth - depth;
+    if let Some(reduction) = search.null_move {
+        if ply > 0 && !search.after_null_move && beta < INFINITY && depth > reduction + 1 {
+            search.after_null_move = true;
+            let value = -negamax(&node.pass(), depth - 1 - reduction, -beta, -beta + 1, search, line);
+            search.after_null_move = false;
+            line.clear();
+            if value >= beta {
+                search.cutoffs += 1;
+                return value;
+            }
+        }
+    }
+    let mut child_line = Vec::new();
+    let best_value = with_buffer(|movements| {
+        let killers = search.killers.as_ref().map_or(&[][..], |killers| killers.get(ply));
+        let history = search.history.as_ref();
+        order_movements(node, cache.as_deref(), search.orderer, killers, history, movements);
+        search.generations += cache.is_none() as u64;
+        let mut best_value = -INFINITY;
+        for (rank, &(.., child)) in movements.iter().enumerate() {
+            let child_node = node.play(&child);
+            // late move reductions : a reduced null window search is enough to discard the
+            // move, if it beats alpha we search it normally
+            let reduced = search
+                .reductions
+                .and_then(|reductions| reductions.reduction(rank, depth))
+                .map(|reduction| {
+                    -negamax(&child_node, depth - 1 - reduction, -alpha - 1, -alpha, search, &mut child_line)
+                })
+                .filter(|&value| value <= alpha);
+            let child_value = if let Some(value) = reduced {
+                value
+            } else if search.pvs && rank > 0 && beta > alpha + 1 {
+                // principal variation search : prove the move is not better than the best one
+                // so far with a null window, search it again with the full window if it is
+                let value = -negamax(&child_node, depth - 1, -alpha - 1, -alpha, search, &mut child_line);
+                if value > alpha && value < beta {
+                    -negamax(&child_node, depth - 1, -beta, -alpha, search, &mut child_line)
+                } else {
+                    value
+                }
+            } else {
+                -negamax(&child_node, depth - 1, -beta, -alpha, search, &mut child_line)
+            };
+            best_value = std::cmp::max(best_value, child_value);
             if child_value > alpha {
                 alpha = child_value;
-                best_child = Some(child);
-                best_value = child_value;
+                extend_line(line, child, &child_line);
             }
             if alpha >= beta {
+                search.cutoffs += 1;
+                if let Some(killers) = search.killers.as_mut() {
+                    killers.store(ply, child);
+                }
+                if let Some(history) = search.history.as_mut() {
+                    history.store(&child, depth);
+                }
                 break;
             }
         }
+        best_value
+    });
 
-        return (best_child, best_value);
-    } else {
-        let mut best_value = i8::MAX;
-        let mut best_child = None;
+    // once cancelled, values are not to be trusted and must not outlive the search
+    let expired = search.expired();
+    if let Some(table) = search.table.as_mut().filter(|_| !expired) {
+        let flag = if best_value >= original_beta {
+            Flag::LowerBound
+        } else if best_value <= original_alpha {
+            Flag::UpperBound
+        } else {
+            Flag::Exact
+        };
+        table.insert(
+            node,
+            Entry {
+                depth,
+                value: best_value,
+                flag,
+                movement: line.first().copied(),
+            },
+        );
+    }
 
-        for child in movements {
-            let (_, child_value) = alpha_beta(&node.play(&child), depth - 1, alpha, beta, maximizing_player);
-            if child_value < beta {
-                beta = child_value;
-                best_child = Some(child);
-                best_value = child_value;
-            }
+    best_value
+}
+
+/// Value of given leaf for the player about to play.
+fn leaf_value<E: Evaluator>(node: &Configuration, evaluator: &E) -> Score {
+    -evaluator.evaluate(node)
+}
+
+/// Quiescence search : only look at moves capturing at least the capture threshold, for at
+/// most `depth` plies.
+/// The player about to play can always decline to capture so the static value is a lower bound.
+fn quiesce<E: Evaluator>(
+    node: &Configuration,
+    depth: u8,
+    mut alpha: Score,
+    beta: Score,
+    search: &mut Search<E>,
+) -> Score {
+    let standing = leaf_value(node, &search.evaluator);
+    if depth == 0 || node.is_terminal() || standing >= beta {
+        return standing;
+    }
+    alpha = std::cmp::max(alpha, standing);
+
+    let threshold = std::cmp::max(search.capture_threshold, 1);
+    with_buffer(|captures| {
+        captures.extend(node.movements().enumerate().filter_map(|(index, movement)| {
+            let captured = node.captures(&movement);
+            (captured >= threshold).then_some((Reverse(captured), 0, Reverse(0), index, movement))
+        }));
+        captures.sort_unstable_by_key(|&(captured, _, _, index, _)| (captured, index));
+
+        let mut best_value = standing;
+        for &(.., movement) in captures.iter() {
+            search.nodes += 1;
+            let value = -quiesce(&node.play(&movement), depth - 1, -beta, -alpha, search);
+            best_value = std::cmp::max(best_value, value);
+            alpha = std::cmp::max(alpha, value);
             if alpha >= beta {
                 break;
             }
         }
+        best_value
+    })
+}
 
-        return (best_child, best_value);
+/// Negamax search of given depth for the player about to play, filling `stats` if given.
+/// This is what `AlphaBeta` runs without transposition table.
+/// Moves of the root are handled as given in `RootOptions`.
+/// Amongst equally valued moves the first one in generation order is returned.
+#[cfg(feature = "parallel")]
+pub(crate) fn search<E: Evaluator>(
+    state: &Configuration,
+    depth: u8,
+    root: RootOptions,
+    evaluator: &E,
+    parallelism: Parallelism,
+    cancellation: &Cancellation,
+    stats: Option<&mut SearchStats>,
+) -> (Option<Movement>, Score) {
+    let counters = stats.as_ref().map(|_| Counters::default());
+    let context = Context {
+        id: NEXT_SEARCH.fetch_add(1, Ordering::Relaxed),
+        root_depth: depth,
+        parallelism,
+        root,
+        evaluator,
+        cancellation,
+        counters: counters.as_ref(),
+    };
+    let result = in_pool(parallelism.threads, || negamax_root(state, &context));
+    if let (Some(stats), Some(counters)) = (stats, counters) {
+        stats.nodes = counters.nodes.into_inner();
+        stats.cutoffs = counters.cutoffs.into_inner();
+        stats.max_depth_reached = counters.max_depth_reached.into_inner();
     }
+    result
 }
 
-fn alpha_beta_fonc(
-    node: &Configuration,
+/// Without the parallel feature we use the sequential search.
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn search<E: Evaluator>(
+    state: &Configuration,
     depth: u8,
-    alpha: i8,
-    beta: i8,
-    maximizing_player: bool,
-) -> (Option<Movement>, i8) {
-    if depth == 0 || node.movements().next().is_none() {
-        if maximizing_player == node.current_player {
-            return (None, -node.value());
-        } else {
-            return (None, node.value());
+    root: RootOptions,
+    evaluator: &E,
+    parallelism: Parallelism,
+    cancellation: &Cancellation,
+    stats: Option<&mut SearchStats>,
+) -> (Option<Movement>, Score) {
+    // killer moves always help when the whole search is sequential
+    let mut search = parallelism
+        .sequential(Search::new())
+        .evaluator(evaluator)
+        .cancellation(cancellation)
+        .killer_moves();
+    search.root = root;
+    // searches are sequential all the way down anyway
+    let _ = parallelism.sequential_depth;
+    let result = in_pool(parallelism.threads, || search.run(state, depth));
+    if let Some(stats) = stats {
+        stats.nodes = search.nodes;
+        stats.cutoffs = search.cutoffs;
+        stats.max_depth_reached = search.max_depth_reached;
+    }
+    result
+}
+
+impl<E: Evaluator> AlphaBeta<E> {
+    fn search(
+        &mut self,
+        state: &Configuration,
+        cancellation: &Cancellation,
+        mut stats: Option<&mut SearchStats>,
+    ) -> (Option<Movement>, Score) {
+        // close to the end : no need to guess if the game can be solved
+        let solved = (state.empty_cell_count() <= self.endgame)
+            .then(|| {
+                solve_exact_with(state, cancellation, Some(ENDGAME_NODES), stats.as_deref_mut())
+            })
+            .flatten();
+        let (movement, score) = match solved {
+            Some(solved) => solved,
+            None if self.margin > 0 && state.movements().next().is_some() => {
+                self.search_with_variety(state, cancellation, stats)
+            }
+            None => self.search_aspiring(state, cancellation, stats),
         };
+        self.fallback(state, movement, score)
     }
 
-    let movements: Vec<Movement> = node.movements().collect();
+    /// Search the root with our aspiration window if any, widening it until the value lies
+    /// inside.
+    fn search_aspiring(
+        &mut self,
+        state: &Configuration,
+        cancellation: &Cancellation,
+        mut stats: Option<&mut SearchStats>,
+    ) -> (Option<Movement>, Score) {
+        let mut root = self.root;
+        loop {
+            let mut child_stats = SearchStats::default();
+            let result = self.search_at(
+                state,
+                self.depth,
+                root,
+                cancellation,
+                stats.as_ref().map(|_| &mut child_stats),
+            );
+            if let Some(stats) = stats.as_deref_mut() {
+                stats.nodes += child_stats.nodes;
+                stats.cutoffs += child_stats.cutoffs;
+                stats.max_depth_reached =
+                    std::cmp::max(stats.max_depth_reached, child_stats.max_depth_reached);
+            }
+            let (alpha, beta) = root.window();
+            if result.1 <= alpha && alpha > -INFINITY {
+                root.window = Some((-INFINITY, beta));
+                if let Some(stats) = stats.as_deref_mut() {
+                    stats.fail_lows += 1;
+                }
+            } else if result.1 >= beta && beta < INFINITY {
+                root.window = Some((alpha, INFINITY));
+                if let Some(stats) = stats.as_deref_mut() {
+                    stats.fail_highs += 1;
+                }
+            } else {
+                return result;
+            }
+        }
+    }
 
-    if maximizing_player == node.current_player {
-        movements
-            .iter()
-            .map(|child| {
-                let (_, child_value) = alpha_beta_fonc(&node.play(child), depth - 1, alpha, beta, maximizing_player);
-                (Some(child.clone()), child_value)
-            })
-            .max_by_key(|&(_, value)| value)
-            .unwrap_or((None, i8::MIN))
-    } else {
-        movements
-            .iter()
-            .map(|child| {
-                let (_, child_value) = alpha_beta_fonc(&node.play(child), depth - 1, alpha, beta, maximizing_player);
-                (Some(child.clone()), child_value)
-            })
-            .min_by_key(|&(_, value)| value)
-            .unwrap_or((None, i8::MAX))
+    /// Search given configuration at given depth, with our transposition table if any.
+    fn search_at(
+        &mut self,
+        state: &Configuration,
+        depth: u8,
+        root: RootOptions,
+        cancellation: &Cancellation,
+        stats: Option<&mut SearchStats>,
+    ) -> (Option<Movement>, Score) {
+        let table = match self.table.as_mut() {
+            Some(table) => table,
+            None => {
+                return search(
+                    state,
+                    depth,
+                    root,
+                    &self.evaluator,
+                    self.parallelism,
+                    cancellation,
+                    stats,
+                )
+            }
+        };
+        let mut search = self
+            .parallelism
+            .sequential(Search::with_table(table))
+            .evaluator(&self.evaluator)
+            .cancellation(cancellation)
+            .killer_moves();
+        search.root = root;
+        let result = search.run(state, depth);
+        if let Some(stats) = stats {
+            stats.nodes = search.nodes;
+            stats.cutoffs = search.cutoffs;
+            stats.max_depth_reached = search.max_depth_reached;
+        }
+        result
     }
+
+    /// Search each move of the root exactly and pick one within the margin of the best.
+    /// If the deadline is hit we pick amongst the moves searched so far.
+    fn search_with_variety(
+        &mut self,
+        state: &Configuration,
+        cancellation: &Cancellation,
+        mut stats: Option<&mut SearchStats>,
+    ) -> (Option<Movement>, Score) {
+        let mut scored = Vec::new();
+        for movement in state.movements() {
+            if cancellation.expired() {
+                break;
+            }
+            let mut child_stats = SearchStats::default();
+            let (_, value) = self.search_at(
+                &state.play(&movement),
+                self.depth.saturating_sub(1),
+                RootOptions::default(),
+                cancellation,
+                stats.as_ref().map(|_| &mut child_stats),
+            );
+            if let Some(stats) = stats.as_deref_mut() {
+                stats.nodes += child_stats.nodes;
+                stats.cutoffs += child_stats.cutoffs;
+                stats.max_depth_reached =
+                    std::cmp::max(stats.max_depth_reached, child_stats.max_depth_reached + 1);
+            }
+            scored.push((movement, -value));
+        }
+        if let Some(stats) = stats {
+            // the root itself
+            stats.nodes += 1;
+        }
+        let best = match scored.iter().map(|&(_, value)| value).max() {
+            Some(best) => best,
+            None => return (None, 0),
+        };
+        scored.retain(|&(_, value)| value >= best.saturating_sub(self.margin));
+        let (next_seed, random) = splitmix64(self.seed);
+        self.seed = next_seed;
+        let index = ((random as u128 * scored.len() as u128) >> 64) as usize;
+        let (movement, value) = scored[index];
+        (Some(movement), value)
+    }
+
+    fn fallback(
+        &self,
+        state: &Configuration,
+        movement: Option<Movement>,
+        score: Score,
+    ) -> (Option<Movement>, Score) {
+        if movement.is_none() && !state.is_terminal() {
+            // deadline was hit before even looking at the root's children
+            if let Some(movement) = state.movements().next() {
+                return (Some(movement), self.evaluator.evaluate(&state.play(&movement)));
+            }
+        }
+        (movement, score)
+    }
+
+    /// Like `compute_next_move` but also return statistics about the search.
+    pub fn compute_next_move_with_stats(
+        &mut self,
+        state: &Configuration,
+    ) -> (Option<Movement>, SearchStats) {
+        let mut stats = SearchStats::default();
+        let start = Instant::now();
+        let (movement, _) = self.search(state, &Cancellation::never(), Some(&mut stats));
+        stats.elapsed = start.elapsed();
+        (movement, stats)
+    }
+
+    /// Like `compute_next_move_until` but also return the value of the move, filling `stats`
+    /// if given.
+    pub(crate) fn compute_next_move_scored_until(
+        &mut self,
+        state: &Configuration,
+        deadline: Instant,
+        stats: Option<&mut SearchStats>,
+    ) -> (Option<Movement>, Score) {
+        self.search(state, &Cancellation::at(deadline), stats)
+    }
+}
+
+/// Strength against speed of alpha - beta on given position : for each given depth, the value
+/// of the best move found at that depth and the number of nodes visited to find it.
+/// Searches run on a single thread so that node counts can be reproduced.
+pub fn strength_curve(position: &Configuration, depths: &[u8]) -> Vec<(u8, i32, u64)> {
+    depths
+        .iter()
+        .map(|&depth| {
+            let mut stats = SearchStats::default();
+            let mut alphabeta = AlphaBeta::new(depth).threads(1);
+            let (_, value) = alphabeta.search(position, &Cancellation::never(), Some(&mut stats));
+            (depth, value as i32, stats.nodes)
+        })
+        .collect()
 }
 
+/// What iterative deepening guesses of an iteration from earlier ones : the best move of the
+/// previous iteration to search first, and an aspiration window around the value of the one two
+/// iterations earlier, whose leaves were also reached by the same player (values of consecutive
+/// iterations swing with whoever plays last).
+#[derive(Default)]
+pub(crate) struct Guesses {
+    /// Best moves and values of the last two iterations, the oldest first.
+    results: [Option<(Option<Movement>, Score)>; 2],
+}
+
+impl Guesses {
+    /// Give given search of the next iteration from given configuration its aspiration window
+    /// and hint, if we can guess them.
+    pub(crate) fn apply<E: Evaluator>(
+        &self,
+        state: &Configuration,
+        alphabeta: AlphaBeta<E>,
+    ) -> AlphaBeta<E> {
+        let alphabeta = match self.results[0] {
+            Some((_, guess)) => alphabeta.aspiration(guess, ASPIRATION_DELTA),
+            None => alphabeta,
+        };
+        let hint = self.results[1]
+            .and_then(|(movement, _)| movement)
+            .filter(|movement| state.is_legal(movement));
+        alphabeta.hint(hint)
+    }
+
+    /// Remember the best move and value found by the last iteration.
+    pub(crate) fn store(&mut self, movement: Option<Movement>, value: Score) {
+        self.results = [self.results[1], Some((movement, value))];
+    }
+}
 
-impl Strategy for AlphaBeta {
+impl<E: Evaluator> Strategy for AlphaBeta<E> {
     fn compute_next_move(&mut self, state: &Configuration) -> Option<Movement> {
-        alpha_beta_par(state, self.0 - 1, i8::MAX, i8::MIN, state.current_player).0
+        self.search(state, &Cancellation::never(), None).0
+    }
+
+    fn compute_next_move_until(
+        &mut self,
+        state: &Configuration,
+        deadline: Instant,
+    ) -> Option<Movement> {
+        self.search(state, &Cancellation::at(deadline), None).0
+    }
+
+    fn compute_next_move_scored(&mut self, state: &Configuration) -> (Option<Movement>, Score) {
+        self.search(state, &Cancellation::never(), None)
     }
 }
 
-impl fmt::Display for AlphaBeta {
+impl<E> fmt::Display for AlphaBeta<E> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Alpha - Beta (max level: {})", self.0)
+        if self.parallelism.pvs {
+            write!(f, "Principal Variation Search (max level: {})", self.depth)
+        } else {
+            write!(f, "Alpha - Beta (max level: {})", self.depth)
+        }
     }
 }
 
+#[cfg(unix)]
 /// Anytime alpha beta algorithm.
 /// Any time algorithms will compute until a deadline is hit and the process is killed.
 /// They are therefore run in another process and communicate through shared memory.
 /// This function is intended to be called from blobwar_iterative_deepening.
-pub fn alpha_beta_anytime(state: &Configuration) {
-    let mut movement = AtomicMove::connect().expect("failed connecting to shmem");
+/// It fails if the shared memory was not created by the master process.
+pub fn alpha_beta_anytime(state: &Configuration) -> Result<(), ShmemError> {
+    let mut movement = AtomicMove::connect()?;
+    let mut guesses = Guesses::default();
     for depth in 1..100 {
-        let chosen_movement = AlphaBeta(depth).compute_next_move(state);
-        movement.store(chosen_movement);
+        let (chosen_movement, value) = guesses
+            .apply(state, AlphaBeta::new(depth))
+            .compute_next_move_scored(state);
+        movement.store(chosen_movement)?;
+        guesses.store(chosen_movement, value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::positions::{BoardPosition, Position};
+    use crate::strategy::MinMax;
+
+    /// Full width negamax counting blobs on leaves, without any pruning : the reference value
+    /// of all searches.
+    pub(crate) fn minimax(node: &Configuration, depth: u8) -> Score {
+        if depth == 0 || node.is_terminal() {
+            return leaf_value(node, &BlobCount);
+        }
+        if node.movements().next().is_none() {
+            return -minimax(&node.pass(), depth - 1);
+        }
+        node.movements()
+            .map(|movement| -minimax(&node.play(&movement), depth - 1))
+            .max()
+            .unwrap()
+    }
+
+    /// Configurations of the default board reached after a few random moves, where
+    /// ordering matters.
+    const MIDDLE_GAMES: [&str; 2] = ["r7/8/7r/5r2/8/7b/5r2/b7 b", "8/8/8/5b2/1r4b1/8/3r4/b7 b"];
+
+    /// A middle game configuration of the default board.
+    fn middle_game(board: &Board) -> Configuration<'_> {
+        Configuration::from_fen("1r6/3bbb2/2b4b/r2b4/8/8/r7/r7 r", board).unwrap()
+    }
+
+    /// The start, `middle_game` and `MIDDLE_GAMES`.
+    fn positions(board: &Board) -> Vec<Configuration<'_>> {
+        let mut configs = vec![Configuration::new(board), middle_game(board)];
+        configs.extend(MIDDLE_GAMES.iter().map(|fen| Configuration::from_fen(fen, board).unwrap()));
+        configs
+    }
+
+    #[test]
+    fn alphabeta_scores_like_minmax() {
+        // `MinMax` searches the whole tree, without sharing anything with alpha - beta
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        for depth in 1..=5 {
+            let (movement, value) = AlphaBeta::new(depth).compute_next_move_scored(&config);
+            let minmax = MinMax::new(depth).compute_next_move_scored(&config);
+            assert_eq!((movement, value), minmax, "depth {}", depth);
+            if depth <= 4 {
+                assert_eq!(value, minimax(&config, depth));
+            }
+        }
+    }
+
+    /// Alpha - beta search collecting and sorting the moves of each node in a new `Vec`, as
+    /// before buffers were re-used.
+    fn collecting_search(node: &Configuration, depth: u8, mut alpha: Score, beta: Score) -> Score {
+        if depth == 0 || node.is_terminal() {
+            return leaf_value(node, &BlobCount);
+        }
+        let mut movements: Vec<Movement> = node.movements().collect();
+        if movements.is_empty() {
+            return -collecting_search(&node.pass(), depth - 1, -beta, -alpha);
+        }
+        movements.sort_by_cached_key(|movement| Reverse(node.play(movement).value()));
+        let mut best_value = -INFINITY;
+        for movement in movements {
+            let value = -collecting_search(&node.play(&movement), depth - 1, -beta, -alpha);
+            best_value = std::cmp::max(best_value, value);
+            alpha = std::cmp::max(alpha, value);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_value
+    }
+
+    #[test]
+    fn buffers_do_not_change_results() {
+        let board = Board::default();
+        let configs = positions(&board);
+        for config in &configs {
+            for depth in 1..=4 {
+                // ties go to the first generated move at the root
+                let expected = config
+                    .movements()
+                    .map(|movement| {
+                        let child = config.play(&movement);
+                        (Some(movement), -collecting_search(&child, depth - 1, -INFINITY, INFINITY))
+                    })
+                    .fold((None, -INFINITY), |best, candidate| {
+                        if candidate.1 > best.1 {
+                            candidate
+                        } else {
+                            best
+                        }
+                    });
+                assert_eq!(Search::new().run(config, depth), expected, "depth {}", depth);
+                // one buffer per ply in use at once, however many nodes were searched
+                assert!(BUFFERS.with(|buffers| buffers.borrow().len()) <= 4);
+            }
+        }
+    }
+
+    /// Keep moves in generation order, as before ordering by immediate value.
+    struct GenerationOrder;
+
+    impl MoveOrderer for GenerationOrder {
+        fn order(&self, _config: &Configuration, _movements: &mut [Movement]) {}
+    }
+
+    #[test]
+    fn ordering_prunes_more() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        let mut ordered = Search::new();
+        let mut unordered = Search::new().orderer(&GenerationOrder);
+        assert_eq!(ordered.run(&config, 5), unordered.run(&config, 5));
+        assert!(ordered.nodes < unordered.nodes);
+    }
+
+    #[test]
+    fn deadlines_stop_searches() {
+        let board = Board::default();
+        let config = middle_game(&board);
+        let start = Instant::now();
+        let deadline = start + Duration::from_millis(50);
+        let movement = AlphaBeta::new(30).compute_next_move_until(&config, deadline);
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(movement.is_some_and(|movement| config.is_legal(&movement)));
+    }
+
+    #[test]
+    fn ties_go_to_the_first_generated_move() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        // at depth 1 all clones are worth one blob
+        assert_eq!(Search::new().run(&config, 1), (config.movements().next(), 1));
+        for depth in 1..=3 {
+            let values: Vec<(Movement, Score)> = config
+                .movements()
+                .map(|movement| {
+                    let (_, value) = Search::new().run(&config.play(&movement), depth - 1);
+                    (movement, -value)
+                })
+                .collect();
+            let best_value = values.iter().map(|&(_, value)| value).max().unwrap();
+            let first_best = values.iter().find(|&&(_, value)| value == best_value).unwrap();
+            assert!(values.iter().filter(|&&(_, value)| value == best_value).count() > 1);
+            let searches = [
+                Search::new(),
+                Search::new().killer_moves().history_heuristic(),
+                Search::new().principal_variation_search(),
+            ];
+            for mut search in searches {
+                assert_eq!(search.run(&config, depth), (Some(first_best.0), first_best.1));
+            }
+            let mut alphabeta = AlphaBeta::new(depth).threads(1);
+            assert_eq!(alphabeta.compute_next_move(&config), Some(first_best.0));
+        }
+    }
+
+    #[test]
+    fn nodes_grow_with_depth() {
+        let board = Board::default();
+        let curve = strength_curve(&middle_game(&board), &[1, 2, 3, 4, 5]);
+        assert_eq!(curve.len(), 5);
+        for (&(depth, ..), expected) in curve.iter().zip(1..) {
+            assert_eq!(depth, expected);
+        }
+        for pair in curve.windows(2) {
+            assert!(pair[0].2 <= pair[1].2);
+        }
+    }
+
+    #[test]
+    fn move_cache_generates_less() {
+        let board = Board::default();
+        let config = middle_game(&board);
+        let mut plain = Search::new().killer_moves();
+        let mut cached = Search::new().killer_moves().move_cache();
+        assert_eq!(plain.run(&config, 4), cached.run(&config, 4));
+        assert_eq!(plain.nodes, cached.nodes);
+        assert!(cached.generations < plain.generations);
+    }
+
+    #[test]
+    fn principal_variation_leads_to_the_root_score() {
+        let board = Board::default();
+        for config in [Configuration::new(&board), middle_game(&board)] {
+            for depth in 1..=4 {
+                let (line, value) = Search::new().principal_variation(&config, depth);
+                let expected = AlphaBeta::new(depth).threads(1).compute_next_move(&config);
+                assert_eq!(line.first().copied(), expected, "depth {}", depth);
+                assert_eq!(line.len(), depth as usize);
+                let mut node = config;
+                for movement in &line {
+                    assert!(node.is_legal(movement));
+                    node = node.play(movement);
+                }
+                // values are seen from the player who just moved
+                let sign = if line.len() % 2 == 1 { 1 } else { -1 };
+                assert_eq!(node.value() as Score * sign, value, "depth {}", depth);
+            }
+        }
+    }
+
+    #[test]
+    fn quiescence_sees_recaptures() {
+        let board = Board::default();
+        let config = Configuration::from_fen("r5r1/8/8/5b2/4b3/8/8/8 r", &board).unwrap();
+        // jumping next to (5,3) converts it, but then blue clones in (4,3) and converts both
+        let jump = Movement::Jump(Position::from_2d(6, 0), Position::from_2d(4, 2));
+        let mut plain = AlphaBeta::new(1).threads(1);
+        assert_eq!(plain.compute_next_move_scored(&config), (Some(jump), 2));
+        assert_eq!(Search::new().run(&config.play(&jump), 1).1, 3);
+        let mut quiet = AlphaBeta::new(1).threads(1).quiescence(4, 1);
+        let (movement, value) = quiet.compute_next_move_scored(&config);
+        assert_ne!(movement, Some(jump));
+        assert!(value < 2);
+        assert_eq!(movement, AlphaBeta::new(5).threads(1).compute_next_move(&config));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_search_prunes() {
+        // a small board keeps the full width search affordable at depth 6
+        let board = Board::empty(4, 3);
+        let config = Configuration::new(&board);
+        let depth = 6;
+        let mut parallel = AlphaBeta::new(depth).sequential_below(0);
+        let (movement, stats) = parallel.compute_next_move_with_stats(&config);
+        let mut sequential = Search::new();
+        let expected = sequential.run(&config, depth);
+        assert_eq!(movement, expected.0);
+        assert_eq!(parallel.compute_next_move_scored(&config), expected);
+        let (_, full_width) = MinMax::new(depth).compute_next_move_with_stats(&config);
+        assert!(
+            stats.nodes < full_width.nodes / 10,
+            "{} nodes against {} for min-max",
+            stats.nodes,
+            full_width.nodes
+        );
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        for depth in 1..=3 {
+            let mut parallel = AlphaBeta::new(depth).sequential_below(0);
+            assert_eq!(parallel.compute_next_move_scored(&config).1, minimax(&config, depth));
+        }
+    }
+
+    #[test]
+    fn single_threaded_searches_match_the_global_pool() {
+        let board = Board::default();
+        for config in [Configuration::new(&board), middle_game(&board)] {
+            for depth in [3, 5] {
+                let pooled = AlphaBeta::new(depth).compute_next_move_scored(&config);
+                let single = AlphaBeta::new(depth).threads(1).compute_next_move_scored(&config);
+                assert_eq!(single, pooled, "depth {}", depth);
+                if depth <= 3 {
+                    let minmax = MinMax::new(depth).threads(1).compute_next_move_scored(&config);
+                    assert_eq!(minmax, pooled, "depth {}", depth);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn statistics_show_pruning() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        for mut alphabeta in [AlphaBeta::new(5), AlphaBeta::new(5).threads(1)] {
+            let (movement, stats) = alphabeta.compute_next_move_with_stats(&config);
+            assert_eq!(movement, alphabeta.compute_next_move(&config));
+            assert!(stats.nodes > 0);
+            assert!(stats.cutoffs > 0);
+            assert!(stats.nodes < (0..=5).map(|depth| crate::perft::perft(&config, depth)).sum());
+            assert_eq!(stats.max_depth_reached, 5);
+            assert!(stats.elapsed > Duration::ZERO);
+        }
+    }
+
+    #[test]
+    fn builds_agree_on_shallow_searches() {
+        // pinned so that builds with and without the parallel feature play the same moves
+        let board = Board::default();
+        let start = Configuration::new(&board);
+        let clone = Some(Movement::Duplicate(Position::from_2d(1, 0)));
+        let jump = Some(Movement::Jump(Position::from_2d(1, 0), Position::from_2d(3, 2)));
+        let expected = [
+            (start, 1, (clone, 1)),
+            (start, 2, (clone, 0)),
+            (start, 3, (clone, 1)),
+            (middle_game(&board), 1, (jump, 6)),
+            (middle_game(&board), 2, (jump, -3)),
+            (middle_game(&board), 3, (jump, 3)),
+        ];
+        for (config, depth, result) in expected {
+            assert_eq!(AlphaBeta::new(depth).compute_next_move_scored(&config), result);
+            assert_eq!(MinMax::new(depth).compute_next_move_scored(&config), result);
+            assert_eq!(Search::new().run(&config, depth), result);
+        }
+    }
+
+    #[test]
+    fn negamax_chooses_like_full_minimax() {
+        let board = Board::default();
+        for (config, max_depth) in [(Configuration::new(&board), 4), (middle_game(&board), 3)] {
+            for depth in 1..=max_depth {
+                // first best move in generation order, without any pruning
+                let mut best = None;
+                for movement in config.movements() {
+                    let value = -minimax(&config.play(&movement), depth - 1);
+                    if best.is_none_or(|(_, best_value)| value > best_value) {
+                        best = Some((movement, value));
+                    }
+                }
+                let (movement, value) = best.unwrap();
+                let expected = (Some(movement), value);
+                assert_eq!(Search::new().run(&config, depth), expected, "depth {}", depth);
+                let parallel = AlphaBeta::new(depth).compute_next_move_scored(&config);
+                assert_eq!(parallel, expected, "depth {}", depth);
+            }
+        }
+    }
+
+    #[test]
+    fn killer_moves_prune_more() {
+        let board = Board::default();
+        // from the start all moves are alike and killers do not help much
+        let config = Configuration::from_fen(MIDDLE_GAMES[1], &board).unwrap();
+        let mut ordered = Search::new();
+        let mut killers = Search::new().killer_moves();
+        assert_eq!(killers.run(&config, 6), ordered.run(&config, 6));
+        assert!(killers.nodes < ordered.nodes);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn heuristics_carry_across_parallel_subtrees() {
+        // sequential subtrees are shallow : killers and history only help once learnt from
+        // earlier ones
+        let board = Board::default();
+        let (mut plain, mut killers, mut both) = (0, 0, 0);
+        for config in positions(&board).into_iter().skip(1) {
+            let mut alphabeta = AlphaBeta::new(6).threads(1);
+            let (movement, stats) = alphabeta.compute_next_move_with_stats(&config);
+            let mut alphabeta = alphabeta.killer_moves();
+            let (killer_movement, killer_stats) = alphabeta.compute_next_move_with_stats(&config);
+            let mut alphabeta = alphabeta.history_heuristic();
+            let (history_movement, history_stats) = alphabeta.compute_next_move_with_stats(&config);
+            assert_eq!(killer_movement, movement, "{}", config);
+            assert_eq!(history_movement, movement, "{}", config);
+            plain += stats.nodes;
+            killers += killer_stats.nodes;
+            both += history_stats.nodes;
+        }
+        assert!(killers < plain, "{} nodes with killers, {} without", killers, plain);
+        assert!(both < killers, "{} nodes with history, {} without", both, killers);
+    }
+
+    #[test]
+    fn history_and_killers_prune_more() {
+        let board = Board::default();
+        for fen in MIDDLE_GAMES.iter() {
+            let config = Configuration::from_fen(fen, &board).unwrap();
+            let mut ordered = Search::new();
+            let mut both = Search::new().killer_moves().history_heuristic();
+            assert_eq!(both.run(&config, 6), ordered.run(&config, 6), "{}", fen);
+            assert!(both.nodes < ordered.nodes, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn guesses_hint_the_previous_best_move() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        let movements: Vec<Movement> = config.movements().take(2).collect();
+        let mut guesses = Guesses::default();
+        assert!(guesses.apply(&config, AlphaBeta::new(1)).root.hint.is_none());
+        guesses.store(Some(movements[0]), 1);
+        let second = guesses.apply(&config, AlphaBeta::new(2)).root;
+        assert_eq!(second.hint, Some(movements[0]));
+        assert!(second.window.is_none());
+        guesses.store(Some(movements[1]), 0);
+        let third = guesses.apply(&config, AlphaBeta::new(3)).root;
+        assert_eq!(third.hint, Some(movements[1]));
+        assert_eq!(third.window, Some((1 - ASPIRATION_DELTA, 1 + ASPIRATION_DELTA)));
+        // moves which cannot be played are not hinted
+        let illegal = Movement::Duplicate(Position::from_2d(4, 4));
+        guesses.store(Some(illegal), 0);
+        assert!(guesses.apply(&config, AlphaBeta::new(4)).root.hint.is_none());
+    }
+
+    #[test]
+    fn variety_is_reproducible() {
+        let board = Board::default();
+        let config = middle_game(&board);
+        let best = AlphaBeta::new(2).compute_next_move(&config);
+        assert_eq!(AlphaBeta::new(2).variety(0, 7).compute_next_move(&config), best);
+        let mut differs = false;
+        for seed in 0..4 {
+            let mut first = AlphaBeta::new(2).variety(2, seed);
+            let mut second = AlphaBeta::new(2).variety(2, seed);
+            for _ in 0..2 {
+                let movement = first.compute_next_move(&config);
+                assert_eq!(second.compute_next_move(&config), movement);
+                differs |= movement != best;
+            }
+        }
+        assert!(differs);
+    }
+
+    #[test]
+    fn bounds_are_fail_soft() {
+        let board = Board::default();
+        let configs = positions(&board);
+        let mut beyond_window = false;
+        for config in &configs {
+            for depth in 1..=3 {
+                let exact = minimax(config, depth);
+                // bounds stored by narrow windows are re-used by the next searches
+                let mut table = TranspositionTable::new();
+                let (low, high) = (exact - 1, exact + 1);
+                for (alpha, beta) in [(low - 2, low), (high, high + 2), (low, high)] {
+                    let mut search = Search::with_table(&mut table);
+                    search.root_depth = depth;
+                    let value = negamax(config, depth, alpha, beta, &mut search, &mut Vec::new());
+                    if value <= alpha {
+                        assert!(exact <= value, "{} <= {} at depth {}", exact, value, depth);
+                    } else if value >= beta {
+                        assert!(exact >= value, "{} >= {} at depth {}", exact, value, depth);
+                    } else {
+                        assert_eq!(value, exact);
+                    }
+                    beyond_window |= value < alpha || value > beta;
+                }
+                let (movement, value) = Search::with_table(&mut table).run(config, depth);
+                assert_eq!(value, exact);
+                assert_eq!(-minimax(&config.play(&movement.unwrap()), depth - 1), exact);
+                assert_eq!(Search::new().fail_hard().run(config, depth).1, exact);
+            }
+        }
+        assert!(beyond_window);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn sequential_subtrees_prune_more() {
+        let board = Board::default();
+        let mut fewer_nodes = false;
+        for fen in MIDDLE_GAMES.iter() {
+            let config = Configuration::from_fen(fen, &board).unwrap();
+            // a single thread gives reproducible node counts
+            let search = |below| AlphaBeta::new(5).threads(1).sequential_below(below);
+            let (expected, everywhere) = search(0).compute_next_move_with_stats(&config);
+            for below in [1, SEQUENTIAL_DEPTH, 5] {
+                let (movement, stats) = search(below).compute_next_move_with_stats(&config);
+                assert_eq!(movement, expected, "{} below {}", fen, below);
+                assert!(stats.nodes <= everywhere.nodes, "{} below {}", fen, below);
+                fewer_nodes |= below == SEQUENTIAL_DEPTH && stats.nodes < everywhere.nodes;
+            }
+        }
+        assert!(fewer_nodes);
     }
 }
@@ -0,0 +1,435 @@
+//! Opening book : moves to play right away in known configurations.
+use super::human::{parse_move, ParseMoveError};
+use super::{Score, Strategy};
+use crate::board::{Board, BoardError};
+use crate::configuration::{Configuration, Movement};
+use crate::game::{GameRecord, ReplayError};
+use crate::positions::{BoardPosition, Position};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Instant;
+
+#[derive(Debug)]
+/// Everything which can go wrong when loading an `OpeningBook`.
+pub enum BookError {
+    /// Reading the file failed.
+    Io(io::Error),
+    /// An entry has no line for the player to play and the move.
+    MissingMove(usize),
+    /// Player to play is neither "red" nor "blue".
+    Player {
+        /// Index of the faulty entry.
+        entry: usize,
+        /// What we found instead.
+        found: String,
+    },
+    /// Configuration of an entry is not a valid board.
+    Board {
+        /// Index of the faulty entry.
+        entry: usize,
+        /// Why parsing failed.
+        error: BoardError,
+    },
+    /// Move of an entry cannot be parsed.
+    Move {
+        /// Index of the faulty entry.
+        entry: usize,
+        /// Why parsing failed.
+        error: ParseMoveError,
+    },
+    /// Move of an entry cannot be played in its configuration.
+    Illegal {
+        /// Index of the faulty entry.
+        entry: usize,
+        /// The illegal move.
+        movement: Movement,
+    },
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BookError::Io(error) => write!(f, "failed reading book: {}", error),
+            BookError::MissingMove(entry) => write!(f, "entry {} has no move", entry),
+            BookError::Player { entry, found } => write!(
+                f,
+                "entry {}: expected red or blue to play but got '{}'",
+                entry, found
+            ),
+            BookError::Board { entry, error } => write!(f, "entry {}: {}", entry, error),
+            BookError::Move { entry, error } => write!(f, "entry {}: {}", entry, error),
+            BookError::Illegal { entry, movement } => {
+                write!(f, "entry {}: illegal movement {}", entry, movement)
+            }
+        }
+    }
+}
+
+impl Error for BookError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BookError::Io(error) => Some(error),
+            BookError::Board { error, .. } => Some(error),
+            BookError::Move { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BookError {
+    fn from(error: io::Error) -> Self {
+        BookError::Io(error)
+    }
+}
+
+/// Recommended moves, indexed by the Zobrist hash of the configuration they are played in.
+/// Only blobs and the player to play are hashed : several boards can share entries.
+#[derive(Default, Clone)]
+pub struct OpeningBook {
+    movements: HashMap<u64, Movement>,
+}
+
+impl OpeningBook {
+    /// Create a new empty book.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Load a book from given file (see `OpeningBook::parse` for the format).
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BookError> {
+        OpeningBook::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse a book made of entries separated by blank lines.
+    /// Each entry is a configuration in the format understood by `Board::parse`
+    /// (blobs being the starting ones) followed by a line with the player to play
+    /// ("red" or "blue") and the move in the format understood by `parse_move`.
+    /// We store configurations rather than hashes so books do not depend on the Zobrist keys.
+    pub fn parse(text: &str) -> Result<Self, BookError> {
+        let mut book = OpeningBook::new();
+        let mut lines = Vec::new();
+        let mut entries = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() {
+                if !lines.is_empty() {
+                    entries.push(std::mem::take(&mut lines));
+                }
+            } else {
+                lines.push(line);
+            }
+        }
+        if !lines.is_empty() {
+            entries.push(lines);
+        }
+        for (entry, lines) in entries.iter().enumerate() {
+            let (last, rows) = lines.split_last().unwrap();
+            let (player, movement) = last
+                .split_once(char::is_whitespace)
+                .ok_or(BookError::MissingMove(entry))?;
+            let current_player = match player {
+                "red" => false,
+                "blue" => true,
+                _ => {
+                    return Err(BookError::Player {
+                        entry,
+                        found: player.to_string(),
+                    })
+                }
+            };
+            let board = Board::parse(&rows.join("\n")).map_err(|error| BookError::Board { entry, error })?;
+            let movement = parse_move(movement).map_err(|error| BookError::Move { entry, error })?;
+            let configuration = Configuration::new_with_player(&board, current_player);
+            if !configuration.check_move(&movement) {
+                return Err(BookError::Illegal { entry, movement });
+            }
+            book.insert(&configuration, movement);
+        }
+        Ok(book)
+    }
+
+    /// Recommend given movement in given `Configuration`, replacing any previous one.
+    pub fn insert(&mut self, configuration: &Configuration, movement: Movement) {
+        self.movements.insert(configuration.zobrist_hash(), movement);
+    }
+
+    /// Recommend each move of given recorded game in the configuration it was played in, for
+    /// instance to book the first moves of games known to go well.
+    /// Fails without booking anything if a move is illegal.
+    pub fn insert_record(&mut self, record: &GameRecord) -> Result<(), ReplayError> {
+        let configurations = record.replay()?;
+        let mut configuration = Configuration::new_with_player(&record.board, record.start_player);
+        for (movement, next) in record.moves.iter().zip(configurations) {
+            if configuration.must_pass() {
+                configuration = configuration.pass();
+            }
+            self.insert(&configuration, *movement);
+            configuration = next;
+        }
+        Ok(())
+    }
+
+    /// Return the recommended movement in given `Configuration`, if any.
+    pub fn get(&self, configuration: &Configuration) -> Option<Movement> {
+        self.movements.get(&configuration.zobrist_hash()).copied()
+    }
+
+    /// How many configurations are in the book ?
+    pub fn len(&self) -> usize {
+        self.movements.len()
+    }
+
+    /// Is the book empty ?
+    pub fn is_empty(&self) -> bool {
+        self.movements.is_empty()
+    }
+
+    /// Play booked moves when possible and let given strategy search otherwise.
+    pub fn before<S: Strategy>(self, strategy: S) -> Booked<S> {
+        Booked {
+            book: self,
+            strategy,
+        }
+    }
+}
+
+/// Write an entry of a book file (see `OpeningBook::parse`) recommending given movement in
+/// given `Configuration`.
+pub fn format_entry(configuration: &Configuration, movement: &Movement) -> String {
+    let board = configuration.board();
+    let blobs = configuration.blobs();
+    let (width, height) = board.dimensions();
+    let mut entry = String::new();
+    for y in 0..height as u8 {
+        for x in 0..width as u8 {
+            let position = Position::from_2d(x, y);
+            entry.push(if board.holes.contains(position) {
+                '#'
+            } else if blobs[0].contains(position) {
+                '0'
+            } else if blobs[1].contains(position) {
+                '1'
+            } else {
+                '.'
+            });
+        }
+        entry.push('\n');
+    }
+    // clones are written from any blob next to their destination
+    let destination = movement.destination();
+    let source = movement.source().unwrap_or_else(|| {
+        board.neighbours[destination as usize]
+            .intersection_with(blobs[configuration.current_player as usize])
+            .positions()
+            .next()
+            .expect("clone without any blob next to it")
+    });
+    let (from_x, from_y) = source.to_2d();
+    let (to_x, to_y) = destination.to_2d();
+    let player = if configuration.current_player { "blue" } else { "red" };
+    entry.push_str(&format!("{} {} {} {} {}\n", player, from_x, from_y, to_x, to_y));
+    entry
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+/// Results of the games where a move was played in a given configuration.
+pub struct MoveStats {
+    /// Number of games.
+    pub games: u32,
+    /// Two points per game won by the player who moved, one per draw.
+    pub points: u32,
+}
+
+impl MoveStats {
+    /// Fraction of the points the player who moved got (draws counting half).
+    pub fn win_rate(&self) -> f64 {
+        self.points as f64 / (2 * self.games) as f64
+    }
+}
+
+/// Gather results of many games to build an `OpeningBook` : each configuration gets the move
+/// with the best win rate amongst the ones played there in enough games.
+pub struct BookBuilder<'a> {
+    min_games: u32,
+    positions: HashMap<u64, (Configuration<'a>, Vec<(Movement, MoveStats)>)>,
+}
+
+impl<'a> BookBuilder<'a> {
+    /// New builder only booking moves played in at least given number of games.
+    pub fn new(min_games: u32) -> Self {
+        BookBuilder {
+            min_games,
+            positions: HashMap::new(),
+        }
+    }
+
+    /// Remember given movement was played in given `Configuration` during a game won by
+    /// given player (None for a draw).
+    pub fn record(&mut self, configuration: &Configuration<'a>, movement: Movement, winner: Option<bool>) {
+        let (_, movements) = self
+            .positions
+            .entry(configuration.zobrist_hash())
+            .or_insert_with(|| (*configuration, Vec::new()));
+        let index = match movements.iter().position(|&(played, _)| played == movement) {
+            Some(index) => index,
+            None => {
+                movements.push((movement, MoveStats::default()));
+                movements.len() - 1
+            }
+        };
+        let stats = &mut movements[index].1;
+        stats.games += 1;
+        stats.points += match winner {
+            Some(winner) if winner == configuration.current_player => 2,
+            Some(_) => 0,
+            None => 1,
+        };
+    }
+
+    /// Best move of each configuration : the best win rate (the most games on ties) amongst
+    /// moves played in enough games. Configurations come in a deterministic order.
+    pub fn entries(&self) -> Vec<(Configuration<'a>, Movement, MoveStats)> {
+        let mut hashes: Vec<&u64> = self.positions.keys().collect();
+        hashes.sort_unstable();
+        hashes
+            .into_iter()
+            .filter_map(|hash| {
+                let (configuration, movements) = &self.positions[hash];
+                movements
+                    .iter()
+                    .filter(|(_, stats)| stats.games >= self.min_games)
+                    .max_by(|(_, first), (_, second)| {
+                        first
+                            .win_rate()
+                            .total_cmp(&second.win_rate())
+                            .then(first.games.cmp(&second.games))
+                    })
+                    .map(|&(movement, stats)| (*configuration, movement, stats))
+            })
+            .collect()
+    }
+
+    /// Return the book.
+    pub fn build(&self) -> OpeningBook {
+        let mut book = OpeningBook::new();
+        for (configuration, movement, _) in self.entries() {
+            book.insert(&configuration, movement);
+        }
+        book
+    }
+
+    /// Write the book in the format understood by `OpeningBook::parse`.
+    pub fn to_text(&self) -> String {
+        self.entries()
+            .iter()
+            .map(|(configuration, movement, _)| format_entry(configuration, movement))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// A strategy consulting an `OpeningBook` before searching (see `OpeningBook::before`).
+pub struct Booked<S> {
+    book: OpeningBook,
+    strategy: S,
+}
+
+impl<S> Booked<S> {
+    /// Booked move for given `Configuration`, if any (and legal there).
+    fn lookup(&self, configuration: &Configuration) -> Option<Movement> {
+        self.book
+            .get(configuration)
+            .filter(|movement| configuration.check_move(movement))
+    }
+}
+
+impl<S: fmt::Display> fmt::Display for Booked<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (with opening book)", self.strategy)
+    }
+}
+
+impl<S: Strategy> Strategy for Booked<S> {
+    fn compute_next_move(&mut self, configuration: &Configuration) -> Option<Movement> {
+        self.lookup(configuration)
+            .or_else(|| self.strategy.compute_next_move(configuration))
+    }
+
+    fn compute_next_move_until(
+        &mut self,
+        configuration: &Configuration,
+        deadline: Instant,
+    ) -> Option<Movement> {
+        self.lookup(configuration)
+            .or_else(|| self.strategy.compute_next_move_until(configuration, deadline))
+    }
+
+    fn compute_next_move_scored(&mut self, configuration: &Configuration) -> (Option<Movement>, Score) {
+        match self.lookup(configuration) {
+            Some(movement) => (Some(movement), configuration.play(&movement).value() as Score),
+            None => self.strategy.compute_next_move_scored(configuration),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::AlphaBeta;
+
+    /// Counts how many times the wrapped strategy is asked for a move.
+    struct Counted<S> {
+        strategy: S,
+        calls: usize,
+    }
+
+    impl<S: Strategy> Strategy for Counted<S> {
+        fn compute_next_move(&mut self, configuration: &Configuration) -> Option<Movement> {
+            self.calls += 1;
+            self.strategy.compute_next_move(configuration)
+        }
+    }
+
+    impl<S> fmt::Display for Counted<S> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "counted")
+        }
+    }
+
+    #[test]
+    fn booked_moves_are_played_without_searching() {
+        let board = Board::default();
+        let start = Configuration::new(&board);
+        // an opening jump, which no search would pick over a clone
+        let booked = start
+            .movements()
+            .find(|movement| movement.source().is_some())
+            .unwrap();
+        assert_ne!(AlphaBeta::new(3).compute_next_move(&start), Some(booked));
+        let mut inserted = OpeningBook::new();
+        inserted.insert(&start, booked);
+        // the book survives being written and read back
+        let book = OpeningBook::parse(&format_entry(&start, &booked)).unwrap();
+        assert_eq!(book.len(), 1);
+        assert_eq!(book.get(&start), inserted.get(&start));
+        let mut engine = book.before(Counted {
+            strategy: AlphaBeta::new(3),
+            calls: 0,
+        });
+        assert_eq!(engine.compute_next_move(&start), Some(booked));
+        assert_eq!(engine.strategy.calls, 0);
+
+        // once out of book the engine plays its own moves
+        let reply = start.play(&booked);
+        let reply = reply.play(&AlphaBeta::new(3).compute_next_move(&reply).unwrap());
+        assert!(engine.book.get(&reply).is_none());
+        assert_eq!(
+            engine.compute_next_move(&reply),
+            AlphaBeta::new(3).compute_next_move(&reply)
+        );
+        assert_eq!(engine.strategy.calls, 1);
+    }
+}
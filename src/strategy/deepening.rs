@@ -0,0 +1,219 @@
+//! Iterative deepening alpha - beta running inside the calling process.
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use super::alphabeta::Guesses;
+use super::endgame::solve_exact_with;
+use super::{AlphaBeta, Cancellation, SearchStats, Strategy, ENDGAME_EMPTY_CELLS, ENDGAME_NODES};
+use crate::configuration::{Configuration, Movement};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// How often the aspiration windows of iterative deepening missed, to tune them.
+/// Both lists have one entry per completed iteration, the first one for depth 1.
+pub struct AspirationStats {
+    /// Number of times the value fell below the window and the root was searched again.
+    pub fail_lows: Vec<u64>,
+    /// Number of times the value rose above the window and the root was searched again.
+    pub fail_highs: Vec<u64>,
+}
+
+impl AspirationStats {
+    /// Total number of root searches run again, at all depths.
+    pub fn re_searches(&self) -> u64 {
+        self.fail_lows.iter().chain(&self.fail_highs).sum()
+    }
+}
+
+/// Iterative deepening with given time budget.
+/// Unlike `IterativeDeepening` no other process is needed : we search at increasing depths
+/// until the budget is spent. The deadline is checked inside the searches, which stop right
+/// away once it is hit.
+/// Each iteration searches first the best move of the previous one, with an aspiration window
+/// around the value of earlier ones.
+/// Close to the end (see `ENDGAME_EMPTY_CELLS`) the game is solved first if it can be done
+/// quickly, the depth returned is then the one of the longest line followed.
+pub struct InProcessDeepening(pub Duration);
+
+impl InProcessDeepening {
+    /// Return the move found by the deepest completed iteration and that depth.
+    /// The iteration interrupted by the deadline is dropped, unless it is the first one :
+    /// a move is returned whenever one exists.
+    pub fn search(&self, state: &Configuration) -> (Option<Movement>, u8) {
+        self.search_until(state, Instant::now() + self.0, u8::MAX).0
+    }
+
+    /// Like `search` but stop after given depth and also return how the aspiration windows
+    /// of completed iterations did.
+    pub fn search_with_stats(
+        &self,
+        state: &Configuration,
+        max_depth: u8,
+    ) -> (Option<Movement>, u8, AspirationStats) {
+        let deadline = Instant::now() + self.0;
+        let ((movement, depth), stats) = self.search_until(state, deadline, max_depth);
+        (movement, depth, stats)
+    }
+
+    fn search_until(
+        &self,
+        state: &Configuration,
+        deadline: Instant,
+        max_depth: u8,
+    ) -> ((Option<Movement>, u8), AspirationStats) {
+        let mut best = (None, 0);
+        let mut guesses = Guesses::default();
+        let mut aspiration_stats = AspirationStats::default();
+        // close to the end the game is solved at once if it can be, the solver is not tried
+        // again at each depth
+        if state.empty_cell_count() <= ENDGAME_EMPTY_CELLS {
+            let cancellation = Cancellation::at(deadline);
+            let mut stats = SearchStats::default();
+            let budget = Some(ENDGAME_NODES);
+            let solved = solve_exact_with(state, &cancellation, budget, Some(&mut stats));
+            if let Some((movement @ Some(_), _)) = solved {
+                return ((movement, stats.max_depth_reached), aspiration_stats);
+            }
+        }
+        for depth in 1..=max_depth {
+            let mut stats = SearchStats::default();
+            let (movement, value) = guesses
+                .apply(state, AlphaBeta::new(depth).endgame_below(-1))
+                .compute_next_move_scored_until(state, deadline, Some(&mut stats));
+            if movement.is_none() {
+                break;
+            }
+            let interrupted = Instant::now() >= deadline;
+            if interrupted && best.0.is_some() {
+                break;
+            }
+            best = (movement, depth);
+            guesses.store(movement, value);
+            aspiration_stats.fail_lows.push(stats.fail_lows);
+            aspiration_stats.fail_highs.push(stats.fail_highs);
+            if interrupted {
+                break;
+            }
+        }
+        (best, aspiration_stats)
+    }
+}
+
+impl Strategy for InProcessDeepening {
+    fn compute_next_move(&mut self, state: &Configuration) -> Option<Movement> {
+        self.search(state).0
+    }
+
+    fn compute_next_move_until(
+        &mut self,
+        state: &Configuration,
+        deadline: Instant,
+    ) -> Option<Movement> {
+        let deadline = std::cmp::min(deadline, Instant::now() + self.0);
+        let ((movement, _), _) = self.search_until(state, deadline, u8::MAX);
+        movement
+    }
+}
+
+impl fmt::Display for InProcessDeepening {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Alpha - Beta (in process iterative deepening {}ms)",
+            self.0.as_millis()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::positions::{BoardPosition, Position};
+
+    /// Re-searches of iterative deepening from given FEN, up to depth 5.
+    fn re_searches(fen: &str) -> (u8, AspirationStats) {
+        let board = Board::from_fen(fen).unwrap();
+        let deepening = InProcessDeepening(Duration::from_secs(60));
+        let (movement, depth, stats) = deepening.search_with_stats(&Configuration::new(&board), 5);
+        assert!(movement.is_some());
+        (depth, stats)
+    }
+
+    #[test]
+    fn longer_budgets_search_deeper() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        let (movement, short_depth) = InProcessDeepening(Duration::from_millis(50)).search(&config);
+        assert!(movement.is_some_and(|movement| config.is_legal(&movement)));
+        assert!(short_depth >= 1);
+        let (movement, long_depth) = InProcessDeepening(Duration::from_millis(500)).search(&config);
+        assert!(movement.is_some());
+        assert!(long_depth >= short_depth);
+    }
+
+    #[test]
+    fn volatile_positions_are_searched_again() {
+        // blue's group is about to be taken and retaken : the value of depth 1 is far from
+        // the one of depth 3
+        let (depth, stats) = re_searches("1r6/3bbb2/2b4b/r2b4/8/8/r7/r7 r");
+        assert_eq!(depth, 5);
+        assert_eq!(stats.fail_lows.len(), 5);
+        assert!(stats.re_searches() >= 1);
+    }
+
+    #[test]
+    fn stable_positions_are_rarely_searched_again() {
+        // blobs far apart : values barely move with depth
+        let (depth, stats) = re_searches("r7/8/8/8/8/8/8/7b r");
+        assert_eq!(depth, 5);
+        assert!(stats.re_searches() <= 1);
+    }
+
+    /// Nodes of all iterations of a single threaded deepening from given FEN up to given depth
+    /// and the move chosen, searching first the best moves of earlier iterations if `reuse`
+    /// is set.
+    fn deepening_nodes(fen: &str, max_depth: u8, reuse: bool) -> (u64, Option<Movement>) {
+        let board = Board::default();
+        let config = Configuration::from_fen(fen, &board).unwrap();
+        let mut guesses = Guesses::default();
+        let mut nodes = 0;
+        let mut movement = None;
+        for depth in 1..=max_depth {
+            let mut alphabeta = guesses.apply(&config, AlphaBeta::new(depth).threads(1));
+            if !reuse {
+                alphabeta = alphabeta.hint(None);
+            }
+            let mut stats = SearchStats::default();
+            let deadline = Instant::now() + Duration::from_secs(600);
+            let (best, value) =
+                alphabeta.compute_next_move_scored_until(&config, deadline, Some(&mut stats));
+            guesses.store(best, value);
+            nodes += stats.nodes;
+            movement = best;
+        }
+        (nodes, movement)
+    }
+
+    #[test]
+    fn earlier_best_moves_save_nodes() {
+        let mut nodes = [0, 0];
+        for fen in ["2r4b/rr6/8/3b4/8/7r/8/8 b", "8/1r6/8/4b3/3b4/8/6r1/7r r"] {
+            let (reused, movement) = deepening_nodes(fen, 6, true);
+            let (fresh, expected) = deepening_nodes(fen, 6, false);
+            assert_eq!(movement, expected, "{}", fen);
+            assert!(reused <= fresh, "{}", fen);
+            nodes[0] += reused;
+            nodes[1] += fresh;
+        }
+        assert!(nodes[0] < nodes[1], "{:?}", nodes);
+        // moves which cannot be played are not searched first
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        let illegal = Some(Movement::Duplicate(Position::from_2d(4, 4)));
+        let search = || AlphaBeta::new(3).threads(1);
+        let expected = search().compute_next_move_with_stats(&config);
+        let hinted = search().hint(illegal).compute_next_move_with_stats(&config);
+        assert_eq!(hinted.0, expected.0);
+        assert_eq!(hinted.1.nodes, expected.1.nodes);
+    }
+}
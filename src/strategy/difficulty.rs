@@ -0,0 +1,58 @@
+//! Difficulty levels : engine settings behind human friendly names.
+use super::{AlphaBeta, Greedy, InProcessDeepening, Strategy, WeightedEval};
+use std::time::Duration;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// How strong the engine plays.
+pub enum Difficulty {
+    /// Greedy player, only looking at the next move.
+    Easy,
+    /// Short alpha - beta search, with the weighted evaluation.
+    Medium,
+    /// Deeper alpha - beta search, with the weighted evaluation.
+    Hard,
+    /// Iterative deepening for about a second per move.
+    Expert,
+}
+
+impl Difficulty {
+    /// All levels, from easiest to hardest.
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Expert,
+    ];
+
+    /// Build the engine playing at this level.
+    /// This is the only place to tune levels.
+    pub fn strategy(&self) -> Box<dyn Strategy> {
+        match self {
+            Difficulty::Easy => Box::new(Greedy::new(0)),
+            Difficulty::Medium => Box::new(AlphaBeta::new(3).evaluator(WeightedEval::default())),
+            Difficulty::Hard => Box::new(AlphaBeta::new(5).evaluator(WeightedEval::default())),
+            Difficulty::Expert => Box::new(InProcessDeepening(Duration::from_secs(1))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::configuration::Configuration;
+
+    #[test]
+    fn every_level_plays_legal_moves() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        for difficulty in Difficulty::ALL.iter() {
+            let movement = difficulty.strategy().compute_next_move(&config);
+            assert!(
+                movement.is_some_and(|movement| config.is_legal(&movement)),
+                "{:?}",
+                difficulty
+            );
+        }
+    }
+}
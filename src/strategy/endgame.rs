@@ -0,0 +1,346 @@
+//! Endgame solver : with few empty cells left we can afford to search until the game ends.
+use super::alphabeta::{SearchStats, INFINITY};
+use super::ttable::{Entry, Flag, TranspositionTable};
+use super::{Cancellation, Score};
+use crate::configuration::{Configuration, Movement};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Searches try `solve_exact` once at most this number of cells are empty.
+pub const ENDGAME_EMPTY_CELLS: i8 = 3;
+
+/// Searches trying `solve_exact` give up after visiting this number of nodes and search as
+/// usual.
+pub const ENDGAME_NODES: u64 = 50_000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// How the game ends, for the player about to play.
+pub enum Outcome {
+    /// They end with more blobs.
+    Win,
+    /// Both players end with as many blobs.
+    Draw,
+    /// They end with fewer blobs.
+    Loss,
+}
+
+impl Outcome {
+    /// Outcome of given final blob difference (for the player about to play).
+    pub fn of(value: Score) -> Self {
+        match value.cmp(&0) {
+            std::cmp::Ordering::Greater => Outcome::Win,
+            std::cmp::Ordering::Equal => Outcome::Draw,
+            std::cmp::Ordering::Less => Outcome::Loss,
+        }
+    }
+}
+
+/// Like `solve_exact` but only tell how the game ends under perfect play.
+pub fn endgame_outcome(config: &Configuration) -> (Option<Movement>, Outcome) {
+    let (movement, value) = solve_exact(config);
+    (movement, Outcome::of(value.into()))
+}
+
+/// No final blob difference is larger.
+const MOST_BLOBS: Score = 64;
+
+/// Solve given configuration : search every line until the game is over (passing when stuck)
+/// and return the best move with the final blob difference (for the player about to play)
+/// under perfect play.
+/// Jumps do not fill the board so lines can come back to a configuration they went through :
+/// such games can go on forever and, like threefold repetitions, they are draws (value 0).
+/// Lines of jumps can also wander for very long before ending or coming back, so searches are
+/// deepened until the lines they stop early do not matter : each depth bounds the value from
+/// below (stopped lines are lost) and from above (stopped lines are won), the value is proven
+/// once both bounds meet. Lines still going on after `u8::MAX` plies are draws as well.
+/// The cost explodes with the number of empty cells and with the length of the lines of jumps
+/// the losing side can play to hold on : on the default board two empty cells are often
+/// solved in a fraction of a second, three can take minutes.
+pub fn solve_exact(config: &Configuration) -> (Option<Movement>, i8) {
+    let (movement, value) = solve_exact_with(config, &Cancellation::never(), None, None)
+        .expect("solver gave up without limits");
+    (movement, value as i8)
+}
+
+/// `solve_exact` giving up (returning None) once cancelled or after visiting given number of
+/// nodes, and filling `stats` if given.
+pub(crate) fn solve_exact_with(
+    config: &Configuration,
+    cancellation: &Cancellation,
+    budget: Option<u64>,
+    stats: Option<&mut SearchStats>,
+) -> Option<(Option<Movement>, Score)> {
+    let mut solver = Solver {
+        cancellation,
+        budget: budget.unwrap_or(u64::MAX),
+        proven: TranspositionTable::new(),
+        tables: Default::default(),
+        line: HashMap::new(),
+        lower: true,
+        nodes: 0,
+        cutoffs: 0,
+        max_depth_reached: 0,
+    };
+    // lines made of clones fill the board within two plies per empty cell (plus two for passes)
+    let mut depth = 2 * config.empty_cell_count() as u8 + 2;
+    let (mut low, mut high) = (-MOST_BLOBS, MOST_BLOBS);
+    let mut movement = None;
+    loop {
+        let found = solver.bound(config, depth, true, (low - 1, high + 1));
+        if found.value >= low {
+            low = found.value;
+            movement = found.movement;
+        }
+        let found = solver.bound(config, depth, false, (low - 1, high + 1));
+        high = std::cmp::min(high, std::cmp::max(found.value, low));
+        if low == high || solver.gave_up() || depth == u8::MAX {
+            break;
+        }
+        depth = depth.saturating_add(2);
+    }
+    if let Some(stats) = stats {
+        stats.nodes = solver.nodes;
+        stats.cutoffs = solver.cutoffs;
+        stats.max_depth_reached = solver.max_depth_reached;
+    }
+    (!solver.gave_up()).then_some((movement, low))
+}
+
+/// Depth limited alpha - beta searches behind `solve_exact`.
+struct Solver<'c> {
+    cancellation: &'c Cancellation,
+    /// Give up after visiting this number of nodes.
+    budget: u64,
+    /// Bounds of real values, found without stopping lines early.
+    proven: TranspositionTable,
+    /// Results of the searches for upper bounds, then for lower ones.
+    tables: [TranspositionTable; 2],
+    /// Configurations (by hash) of the line being searched, with their ply.
+    line: HashMap<u64, u8>,
+    /// Are lines stopped before the end lost for the player to play at the root (the search
+    /// gives a lower bound of the value) or won (an upper bound) ?
+    lower: bool,
+    nodes: u64,
+    cutoffs: u64,
+    max_depth_reached: u8,
+}
+
+/// What `Solver::solve` found about a configuration.
+struct Found {
+    movement: Option<Movement>,
+    value: Score,
+    /// Ply of the earliest configuration of the line above which came back below, if any :
+    /// the value then only holds when reaching the configuration along this line.
+    repeated: Option<u8>,
+    /// Did the value rely on lines stopped before the end ?
+    stopped: bool,
+}
+
+impl Found {
+    fn leaf(value: Score, stopped: bool) -> Self {
+        Found {
+            movement: None,
+            value,
+            repeated: None,
+            stopped,
+        }
+    }
+}
+
+impl<'c> Solver<'c> {
+    /// Should we stop now ?
+    fn gave_up(&self) -> bool {
+        self.nodes > self.budget || self.cancellation.expired()
+    }
+
+    /// Bound the value of `root` (from below if `lower`, else from above) searching `depth`
+    /// plies deep, with the move reaching this bound.
+    fn bound(
+        &mut self,
+        root: &Configuration,
+        depth: u8,
+        lower: bool,
+        (alpha, beta): (Score, Score),
+    ) -> Found {
+        self.lower = lower;
+        self.solve(root, 0, depth, alpha, beta)
+    }
+
+    /// Fail-soft alpha - beta search of `node`, values being seen from the player about to play.
+    fn solve(
+        &mut self,
+        node: &Configuration,
+        ply: u8,
+        depth: u8,
+        mut alpha: Score,
+        mut beta: Score,
+    ) -> Found {
+        self.nodes += 1;
+        self.max_depth_reached = std::cmp::max(self.max_depth_reached, ply);
+        if node.is_terminal() {
+            return Found::leaf(-Score::from(node.value()), false);
+        }
+        let hash = node.zobrist_hash();
+        if let Some(&repeated) = self.line.get(&hash) {
+            return Found {
+                repeated: Some(repeated),
+                ..Found::leaf(0, false)
+            };
+        }
+        if ply == u8::MAX {
+            return Found::leaf(0, true);
+        }
+        if depth == 0 || self.gave_up() {
+            // plies alternate between the player at the root and their opponent
+            let lost = self.lower == ply.is_multiple_of(2);
+            return Found::leaf(if lost { -MOST_BLOBS } else { MOST_BLOBS }, true);
+        }
+        if node.movements().next().is_none() {
+            self.line.insert(hash, ply);
+            let found = self.solve(&node.pass(), ply + 1, depth - 1, -beta, -alpha);
+            self.line.remove(&hash);
+            return Found {
+                movement: None,
+                value: -found.value,
+                repeated: found.repeated.filter(|&repeated| repeated < ply),
+                stopped: found.stopped,
+            };
+        }
+
+        // real values bound what any search finds
+        let proven = self.proven.get(node);
+        let entry = self.tables[self.lower as usize].get(node);
+        let stored = proven.into_iter().chain(entry.filter(|entry| entry.depth >= depth));
+        for (index, entry) in stored.enumerate() {
+            match entry.flag {
+                Flag::Exact => alpha = std::cmp::max(alpha, entry.value),
+                Flag::LowerBound => alpha = std::cmp::max(alpha, entry.value),
+                Flag::UpperBound => beta = std::cmp::min(beta, entry.value),
+            }
+            if entry.flag == Flag::Exact || alpha >= beta {
+                return Found {
+                    movement: entry.movement,
+                    stopped: index > 0 || proven.is_none(),
+                    ..Found::leaf(entry.value, false)
+                };
+            }
+        }
+        let (original_alpha, original_beta) = (alpha, beta);
+        self.line.insert(hash, ply);
+
+        // best move found so far first, then moves ending with the most blobs
+        let hint = entry.or(proven).and_then(|entry| entry.movement);
+        let mut movements: Vec<Movement> = node.movements().collect();
+        movements.sort_by_key(|movement| {
+            (hint != Some(*movement), Reverse(node.play(movement).value()))
+        });
+        let mut best = Found::leaf(-INFINITY, false);
+        for movement in movements {
+            let found = self.solve(&node.play(&movement), ply + 1, depth - 1, -beta, -alpha);
+            best.repeated = best.repeated.into_iter().chain(found.repeated).min();
+            best.stopped |= found.stopped;
+            if -found.value > best.value {
+                best.movement = Some(movement);
+                best.value = -found.value;
+            }
+            alpha = std::cmp::max(alpha, best.value);
+            if alpha >= beta {
+                self.cutoffs += 1;
+                break;
+            }
+        }
+        self.line.remove(&hash);
+
+        // coming back to `node` itself is part of its own value
+        best.repeated = best.repeated.filter(|&repeated| repeated < ply);
+        if best.repeated.is_none() {
+            let flag = if best.value <= original_alpha {
+                Flag::UpperBound
+            } else if best.value >= original_beta {
+                Flag::LowerBound
+            } else {
+                Flag::Exact
+            };
+            let entry = Entry {
+                depth,
+                value: best.value,
+                flag,
+                movement: best.movement,
+            };
+            if !best.stopped {
+                self.proven.insert(node, entry);
+            }
+            self.tables[self.lower as usize].insert(node, entry);
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::configuration::ScoringRule;
+    use crate::positions::{BoardPosition, Position};
+    use crate::strategy::{AlphaBeta, Random, Strategy, Tablebase};
+
+    #[test]
+    fn exact_solver_finds_wins_shallow_searches_miss() {
+        let board = Board::default();
+        let fen = "rrbbbbbb/rrbbbbbb/1rrbbbbb/rrrrbbbb/rrrrrbbb/rrrrrbbb/rrr1rrbb/rrrrrrbb b";
+        let config = Configuration::from_fen(fen, &board).unwrap();
+        assert_eq!(config.empty_cell_count(), 2);
+        // blue wins by jumping next to the last empty cell rather than cloning there
+        let win = Movement::Jump(Position::from_2d(5, 5), Position::from_2d(3, 6));
+        assert_eq!(solve_exact(&config), (Some(win), 4));
+        // three plies see a capture but it only draws
+        let mut shallow = AlphaBeta::new(3).endgame_below(0);
+        let missed = shallow.compute_next_move(&config).unwrap();
+        assert_eq!(missed, Movement::Jump(Position::from_2d(2, 1), Position::from_2d(0, 2)));
+        assert_eq!(solve_exact(&config.play(&missed)).1, 0);
+        assert_eq!(endgame_outcome(&config), (Some(win), Outcome::Win));
+        assert_eq!(endgame_outcome(&config.play(&missed)).1, Outcome::Draw);
+        // by default close to the end alpha - beta solves the game
+        assert_eq!(AlphaBeta::new(3).compute_next_move(&config), Some(win));
+    }
+
+    #[test]
+    fn searches_go_on_when_endgames_are_too_long_to_solve() {
+        let board = Board::default();
+        let fen = "b1bbbrbb/brbbbrrb/1rrrrrrb/rrrrrrr1/rrrrrrrr/rbbbbbrr/rbbbbbbr/bbbbbbbr r";
+        let config = Configuration::from_fen(fen, &board).unwrap();
+        assert_eq!(config.empty_cell_count(), 3);
+        let budget = Some(ENDGAME_NODES);
+        let mut stats = SearchStats::default();
+        let never = Cancellation::never();
+        assert!(solve_exact_with(&config, &never, budget, Some(&mut stats)).is_none());
+        assert!(stats.nodes > ENDGAME_NODES);
+        // alpha - beta then plays what three plies find
+        let shallow = AlphaBeta::new(3).endgame_below(0).compute_next_move(&config);
+        assert_eq!(shallow, Some(Movement::Duplicate(Position::from_2d(7, 3))));
+        assert_eq!(AlphaBeta::new(3).compute_next_move(&config), shallow);
+    }
+
+    #[test]
+    fn exact_solver_agrees_with_tablebases() {
+        // two holes let blobs jump around each other
+        let board = Board::parse("0..#
+....
+#..1").unwrap();
+        let tablebase = Tablebase::generate(&board, ScoringRule::BlobDifference).unwrap();
+        for seed in 0..20 {
+            let mut config = Configuration::new(&board);
+            let mut players = [Random(seed), Random(seed + 100)];
+            while !config.is_terminal() {
+                let (movement, value) = solve_exact(&config);
+                let outcome = tablebase.probe(&config);
+                assert_eq!(Some(Outcome::of(value.into())), outcome, "{}", config.to_fen());
+                assert!(movement.is_none_or(|movement| config.is_legal(&movement)));
+                config = match players[config.current_player as usize].compute_next_move(&config) {
+                    Some(movement) => config.play(&movement),
+                    None => config.pass(),
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! Evaluation functions used by searches on their leaves.
+use crate::configuration::Configuration;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Values computed by evaluators and searches.
+/// Wider than blob differences so that evaluators can weigh several terms.
+pub type Score = i16;
+
+/// Highest value returned by evaluators.
+/// It stays strictly below the bound of search windows (`Score::MAX`) so that no evaluation
+/// is ever mistaken for it.
+pub const SCORE_MAX: Score = Score::MAX - 1;
+
+/// Lowest value returned by evaluators, the opposite of `SCORE_MAX`.
+pub const SCORE_MIN: Score = -SCORE_MAX;
+
+/// Clamp given sum of weighted terms to `SCORE_MIN..=SCORE_MAX`.
+fn clamp_score(value: i32) -> Score {
+    value.clamp(SCORE_MIN as i32, SCORE_MAX as i32) as Score
+}
+
+/// Estimate how good a `Configuration` is.
+/// Like `Configuration::value`, the estimation is seen from the player who just moved
+/// (the one *not* about to play) : the higher the better for them.
+/// Searches negate it to get the value for the player to play.
+/// Returned values must lie between `SCORE_MIN` and `SCORE_MAX` so that they can be negated.
+/// Searches, `Greedy` included, can evaluate with any of them instead of the blob count.
+pub trait Evaluator: Sync {
+    /// Evaluate given `Configuration`.
+    fn evaluate(&self, config: &Configuration) -> Score;
+}
+
+impl<E: Evaluator + ?Sized> Evaluator for &E {
+    fn evaluate(&self, config: &Configuration) -> Score {
+        (**self).evaluate(config)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+/// Blob count difference, exactly like `Configuration::value`.
+pub struct BlobCount;
+
+impl Evaluator for BlobCount {
+    fn evaluate(&self, config: &Configuration) -> Score {
+        config.value() as Score
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+/// Blob count difference multiplied by given weight, plus the difference in number of
+/// available movements.
+/// A player with many possible moves is less likely to get stuck or cornered.
+pub struct Mobility(pub Score);
+
+impl Default for Mobility {
+    fn default() -> Self {
+        Mobility(4)
+    }
+}
+
+impl Evaluator for Mobility {
+    fn evaluate(&self, config: &Configuration) -> Score {
+        let opponent_movements = config.movements().count() as i32;
+        let own_movements = config.skip_play().movements().count() as i32;
+        let value = (self.0 as i32)
+            .saturating_mul(config.value() as i32)
+            .saturating_add(own_movements - opponent_movements);
+        clamp_score(value)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Weighted sum of several differences between the player who just moved and their opponent.
+/// Blob count alone plays badly in the middle game : it grabs blobs it cannot keep.
+/// Weights can be negative and zero disables a term.
+/// Default weights were picked by playing against `BlobCount` at low depths : safe blobs
+/// matter most, mobility (which is also the most expensive term) did not help.
+pub struct WeightedEval {
+    /// Weight of the blob difference.
+    pub material: Score,
+    /// Weight of the difference in number of available movements.
+    pub mobility: Score,
+    /// Weight of the difference in number of blobs in corners (cells with at most three
+    /// neighbours), which can only be taken from few directions.
+    pub corners: Score,
+    /// Weight of the difference in number of blobs on edges (cells with at most five
+    /// neighbours, corners excluded), next to the border or to holes.
+    pub edges: Score,
+    /// Weight of the difference in number of blobs without empty neighbours : compact
+    /// clusters whose inner blobs no move can convert.
+    pub compactness: Score,
+}
+
+impl Default for WeightedEval {
+    fn default() -> Self {
+        WeightedEval {
+            material: 8,
+            mobility: 0,
+            corners: 1,
+            edges: 0,
+            compactness: 4,
+        }
+    }
+}
+
+impl WeightedEval {
+    /// Number of weights.
+    pub const WEIGHTS: usize = 5;
+
+    /// All weights : material, mobility, corners, edges and compactness.
+    pub fn weights(&self) -> [Score; WeightedEval::WEIGHTS] {
+        [
+            self.material,
+            self.mobility,
+            self.corners,
+            self.edges,
+            self.compactness,
+        ]
+    }
+
+    /// Evaluation with given weights, in the order of `WeightedEval::weights`.
+    pub fn from_weights(weights: [Score; WeightedEval::WEIGHTS]) -> Self {
+        let [material, mobility, corners, edges, compactness] = weights;
+        WeightedEval {
+            material,
+            mobility,
+            corners,
+            edges,
+            compactness,
+        }
+    }
+
+    /// Corner, edge and safe blobs of given player.
+    fn shape(config: &Configuration, player: bool) -> [i32; 3] {
+        let board = config.board();
+        let blobs = config.blobs();
+        let occupied = blobs[0].union_with(blobs[1]).union_with(board.holes);
+        let mut shape = [0; 3];
+        for position in blobs[player as usize].positions() {
+            match board.individual_neighbours[0][position as usize].len() {
+                0..=3 => shape[0] += 1,
+                4..=5 => shape[1] += 1,
+                _ => {}
+            }
+            if board.neighbours[position as usize]
+                .intersection_with(occupied.invert())
+                .is_empty()
+            {
+                shape[2] += 1;
+            }
+        }
+        shape
+    }
+}
+
+impl Evaluator for WeightedEval {
+    fn evaluate(&self, config: &Configuration) -> Score {
+        let mobility = if self.mobility == 0 {
+            0
+        } else {
+            config.skip_play().movements().count() as i32 - config.movements().count() as i32
+        };
+        let [own_corners, own_edges, own_safe] = WeightedEval::shape(config, !config.current_player);
+        let [opponent_corners, opponent_edges, opponent_safe] =
+            WeightedEval::shape(config, config.current_player);
+        let terms = [
+            (self.material, config.value() as i32),
+            (self.mobility, mobility),
+            (self.corners, own_corners - opponent_corners),
+            (self.edges, own_edges - opponent_edges),
+            (self.compactness, own_safe - opponent_safe),
+        ];
+        let value = terms.iter().fold(0i32, |sum, &(weight, difference)| {
+            sum.saturating_add((weight as i32).saturating_mul(difference))
+        });
+        clamp_score(value)
+    }
+}
+
+/// Weights are displayed comma separated, in the order of `WeightedEval::weights`
+/// (like "8,0,1,0,4"), which is what parsing expects.
+impl fmt::Display for WeightedEval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [material, mobility, corners, edges, compactness] = self.weights();
+        write!(
+            f,
+            "{},{},{},{},{}",
+            material, mobility, corners, edges, compactness
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Text is not a valid list of `WeightedEval` weights.
+pub struct ParseWeightsError(pub String);
+
+impl fmt::Display for ParseWeightsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "expected {} comma separated weights but got '{}'",
+            WeightedEval::WEIGHTS,
+            self.0
+        )
+    }
+}
+
+impl Error for ParseWeightsError {}
+
+impl FromStr for WeightedEval {
+    type Err = ParseWeightsError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || ParseWeightsError(text.to_owned());
+        let mut weights = [0; WeightedEval::WEIGHTS];
+        let mut parts = text.split(',');
+        for weight in &mut weights {
+            *weight = parts
+                .next()
+                .and_then(|part| part.trim().parse().ok())
+                .ok_or_else(error)?;
+        }
+        match parts.next() {
+            Some(_) => Err(error()),
+            None => Ok(WeightedEval::from_weights(weights)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::configuration::Movement;
+    use crate::strategy::{AlphaBeta, MinMax, Strategy};
+
+    #[test]
+    fn extreme_weights_are_clamped() {
+        let board = Board::default();
+        // red clones twice : two blobs ahead for the player who just moved
+        let config = Configuration::new(&board)
+            .play(&Movement::Duplicate(9))
+            .pass()
+            .play(&Movement::Duplicate(10));
+        assert_eq!(WeightedEval::from_weights([Score::MAX; 5]).evaluate(&config), SCORE_MAX);
+        assert_eq!(WeightedEval::from_weights([Score::MIN; 5]).evaluate(&config), SCORE_MIN);
+        assert_eq!(Mobility(Score::MAX).evaluate(&config), SCORE_MAX);
+        assert_eq!(Mobility(Score::MIN).evaluate(&config), SCORE_MIN);
+    }
+
+    #[test]
+    fn searches_survive_extreme_weights() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        for weight in [Score::MAX, Score::MIN] {
+            let evaluator = WeightedEval::from_weights([weight; 5]);
+            let (movement, value) =
+                AlphaBeta::new(3).evaluator(evaluator).compute_next_move_scored(&config);
+            assert!(movement.is_some());
+            assert!((SCORE_MIN..=SCORE_MAX).contains(&value));
+        }
+    }
+
+    #[test]
+    fn mobility_changes_choices() {
+        let board = Board::default();
+        // all clones of the first move are worth one blob but do not open as many cells
+        let config = Configuration::new(&board);
+        let counting = AlphaBeta::new(1).compute_next_move(&config);
+        let mobile = AlphaBeta::new(1).evaluator(Mobility::default()).compute_next_move(&config);
+        assert_ne!(counting, mobile);
+        let mobile = mobile.unwrap();
+        let movements = |movement: Movement| config.play(&movement).skip_play().movements().count();
+        assert!(movements(mobile) > movements(counting.unwrap()));
+        let mut minmax = MinMax::new(1).evaluator(Mobility::default());
+        assert_eq!(minmax.compute_next_move(&config), Some(mobile));
+    }
+}
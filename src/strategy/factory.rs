@@ -0,0 +1,149 @@
+//! Build strategies out of textual specifications like "alphabeta:5".
+use super::{AlphaBeta, Greedy, Human, Mcts, MinMax, MtdF, Random, Strategy};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Everything which can go wrong when parsing a strategy specification.
+pub enum StrategyParseError {
+    /// No strategy has this name.
+    Unknown(String),
+    /// Strategy needs a parameter (like a depth) but none was given.
+    MissingParameter(String),
+    /// Strategy takes no parameter but one was given.
+    UnexpectedParameter(String),
+    /// Parameter is not a valid number.
+    InvalidParameter {
+        /// Name of the strategy.
+        strategy: String,
+        /// What we got as parameter.
+        parameter: String,
+    },
+}
+
+impl fmt::Display for StrategyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StrategyParseError::Unknown(name) => write!(f, "unknown strategy '{}'", name),
+            StrategyParseError::MissingParameter(name) => {
+                write!(f, "strategy '{}' needs a parameter (like '{}:4')", name, name)
+            }
+            StrategyParseError::UnexpectedParameter(name) => {
+                write!(f, "strategy '{}' takes no parameter", name)
+            }
+            StrategyParseError::InvalidParameter {
+                strategy,
+                parameter,
+            } => write!(
+                f,
+                "invalid parameter '{}' for strategy '{}'",
+                parameter, strategy
+            ),
+        }
+    }
+}
+
+impl Error for StrategyParseError {}
+
+/// Build a strategy out of given specification : a name, possibly followed by ':' and a
+/// parameter.
+/// We understand "human", "greedy" (or "greedy:<lookahead>"), "minmax:<depth>",
+/// "alphabeta:<depth>", "mtdf:<depth>", "mcts:<iterations>" and "random:<seed>".
+pub fn parse_strategy(spec: &str) -> Result<Box<dyn Strategy>, StrategyParseError> {
+    let (name, parameter) = match spec.find(':') {
+        Some(index) => (&spec[..index], Some(&spec[index + 1..])),
+        None => (spec, None),
+    };
+    let number = |parameter: &str| {
+        parameter
+            .parse::<u64>()
+            .map_err(|_| StrategyParseError::InvalidParameter {
+                strategy: name.to_owned(),
+                parameter: parameter.to_owned(),
+            })
+    };
+    let depth = |parameter: Option<&str>| {
+        let parameter = parameter.ok_or_else(|| StrategyParseError::MissingParameter(name.to_owned()))?;
+        let depth = number(parameter)?;
+        if depth > u8::MAX as u64 {
+            return Err(StrategyParseError::InvalidParameter {
+                strategy: name.to_owned(),
+                parameter: parameter.to_owned(),
+            });
+        }
+        Ok(depth as u8)
+    };
+    match (name, parameter) {
+        ("human", None) => Ok(Box::new(Human())),
+        ("human", Some(_)) => Err(StrategyParseError::UnexpectedParameter(name.to_owned())),
+        ("greedy", None) => Ok(Box::new(Greedy::new(0))),
+        ("greedy", lookahead) => Ok(Box::new(Greedy::new(depth(lookahead)?))),
+        ("minmax", parameter) => Ok(Box::new(MinMax::new(depth(parameter)?))),
+        ("alphabeta", parameter) => Ok(Box::new(AlphaBeta::new(depth(parameter)?))),
+        ("mtdf", parameter) => Ok(Box::new(MtdF::new(depth(parameter)?))),
+        ("mcts", Some(iterations)) => {
+            let count = u32::try_from(number(iterations)?).map_err(|_| {
+                StrategyParseError::InvalidParameter {
+                    strategy: name.to_owned(),
+                    parameter: iterations.to_owned(),
+                }
+            })?;
+            if count == 0 {
+                return Err(StrategyParseError::InvalidParameter {
+                    strategy: name.to_owned(),
+                    parameter: iterations.to_owned(),
+                });
+            }
+            Ok(Box::new(Mcts::iterations(count)))
+        }
+        ("mcts", None) => Err(StrategyParseError::MissingParameter(name.to_owned())),
+        ("random", Some(seed)) => Ok(Box::new(Random(number(seed)?))),
+        ("random", None) => Err(StrategyParseError::MissingParameter(name.to_owned())),
+        _ => Err(StrategyParseError::Unknown(name.to_owned())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn specifications_parse() {
+        let specifications = [
+            ("human", "Human"),
+            ("greedy", "Greedy (lookahead: 0)"),
+            ("greedy:2", "Greedy (lookahead: 2)"),
+            ("minmax:3", "Min - Max (max level: 3)"),
+            ("alphabeta:5", "Alpha - Beta (max level: 5)"),
+            ("mtdf:4", "MTD(f) (max level: 4)"),
+            ("mcts:100", "Monte Carlo Tree Search (100 iterations)"),
+            ("random:7", "Random"),
+        ];
+        for (specification, name) in specifications {
+            assert_eq!(parse_strategy(specification).unwrap().to_string(), name);
+        }
+    }
+
+    #[test]
+    fn malformed_specifications_are_rejected() {
+        let error = |specification| parse_strategy(specification).err().unwrap();
+        let invalid = |strategy: &str, parameter: &str| StrategyParseError::InvalidParameter {
+            strategy: strategy.to_owned(),
+            parameter: parameter.to_owned(),
+        };
+        assert_eq!(error("negamax:3"), StrategyParseError::Unknown("negamax".to_owned()));
+        assert_eq!(error(""), StrategyParseError::Unknown(String::new()));
+        assert_eq!(error("AlphaBeta:3"), StrategyParseError::Unknown("AlphaBeta".to_owned()));
+        assert_eq!(error("alphabeta"), StrategyParseError::MissingParameter("alphabeta".to_owned()));
+        assert_eq!(error("random"), StrategyParseError::MissingParameter("random".to_owned()));
+        assert_eq!(error("human:1"), StrategyParseError::UnexpectedParameter("human".to_owned()));
+        assert_eq!(error("minmax:deep"), invalid("minmax", "deep"));
+        assert_eq!(error("minmax:-1"), invalid("minmax", "-1"));
+        assert_eq!(error("alphabeta:256"), invalid("alphabeta", "256"));
+        assert_eq!(error("alphabeta:"), invalid("alphabeta", ""));
+        assert_eq!(error("mcts:5000000000"), invalid("mcts", "5000000000"));
+        assert_eq!(error("mcts:0"), invalid("mcts", "0"));
+        assert_eq!(error("greedy:1:2"), invalid("greedy", "1:2"));
+    }
+}
@@ -1,22 +1,90 @@
 //! Dumb greedy algorithm.
-use super::Strategy;
+use super::{BlobCount, Evaluator, Score, Strategy};
 use crate::configuration::{Configuration, Movement};
 use std::fmt;
 
 /// Dumb algorithm.
 /// Amongst all possible movements return the one which yields the configuration with the best
-/// immediate value.
-pub struct Greedy();
+/// value, looking ahead at the given number of replies (0 is the immediate value).
+/// Configurations are valued with given `Evaluator` (blob count by default).
+pub struct Greedy<E = BlobCount> {
+    lookahead: u8,
+    evaluator: E,
+}
+
+impl Greedy {
+    /// New greedy player looking ahead at given number of replies, counting blobs.
+    pub fn new(lookahead: u8) -> Self {
+        Greedy {
+            lookahead,
+            evaluator: BlobCount,
+        }
+    }
+}
 
-impl fmt::Display for Greedy {
+impl<E: Evaluator> Greedy<E> {
+    /// Value configurations with given `Evaluator` instead.
+    pub fn evaluator<F: Evaluator>(self, evaluator: F) -> Greedy<F> {
+        Greedy {
+            lookahead: self.lookahead,
+            evaluator,
+        }
+    }
+}
+
+impl<E> fmt::Display for Greedy<E> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Greedy")
+        write!(f, "Greedy (lookahead: {})", self.lookahead)
+    }
+}
+
+/// Value of given configuration for the player who just moved, assuming both players
+/// play their best moves for the next `depth` plies.
+fn lookahead<E: Evaluator>(node: &Configuration, depth: u8, evaluator: &E) -> Score {
+    if depth == 0 || node.is_terminal() {
+        return evaluator.evaluate(node);
     }
+    -node
+        .movements()
+        .map(|child| lookahead(&node.play(&child), depth - 1, evaluator))
+        .max()
+        // stuck : pass and let the other player go on
+        .unwrap_or_else(|| lookahead(&node.pass(), depth - 1, evaluator))
 }
 
-impl Strategy for Greedy {
+impl<E: Evaluator> Strategy for Greedy<E> {
     fn compute_next_move(&mut self, state: &Configuration) -> Option<Movement> {
-	state.movements()
-	    .max_by_key(|&child| state.play(&child).value())
+        state
+            .movements()
+            .max_by_key(|&child| lookahead(&state.play(&child), self.lookahead, &self.evaluator))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::positions::{BoardPosition, Position};
+
+    /// Can the player to play convert some of the opponent's blobs ?
+    fn can_capture(config: &Configuration) -> bool {
+        let opponent = !config.current_player as usize;
+        let blobs = config.blob_counts()[opponent];
+        config
+            .movements()
+            .any(|movement| config.play(&movement).blob_counts()[opponent] < blobs)
+    }
+
+    #[test]
+    fn lookahead_avoids_captures() {
+        let board = Board::default();
+        let config = Configuration::from_fen("r7/8/3b4/8/8/3r4/8/2b5 r", &board).unwrap();
+        // cloning next to the blue blob in (2,7) converts it but leaves both red blobs in
+        // reach of the one in (3,2)
+        let greedy = Greedy::new(0).compute_next_move(&config).unwrap();
+        assert_eq!(greedy, Movement::Duplicate(Position::from_2d(3, 6)));
+        assert!(can_capture(&config.play(&greedy)));
+        let careful = Greedy::new(2).compute_next_move(&config).unwrap();
+        assert!(!can_capture(&config.play(&careful)));
     }
 }
@@ -2,11 +2,13 @@
 use super::Strategy;
 use crate::configuration::{Configuration, Movement};
 use crate::positions::{BoardPosition, Position};
+use std::error::Error;
 use std::fmt;
 use std::io;
-use std::io::BufRead;
+use std::io::prelude::*;
 
-/// Let a human enter moves on stdin.
+/// Let a human enter moves on stdin (see `parse_move` for the format).
+/// Illegal moves are refused and asked again. If stdin is closed we pass.
 pub struct Human();
 impl fmt::Display for Human {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -14,55 +16,112 @@ impl fmt::Display for Human {
     }
 }
 
-fn ask_cell() -> Result<(u8, u8), io::Error> {
-    let mut line = String::new();
-    let stdin = io::stdin();
-    stdin.lock().read_line(&mut line)?;
-    let mut words = line.split_whitespace();
-    let y: u8 = words
-        .next()
-        .unwrap()
-        .parse()
-        .map_err(|_| io::ErrorKind::Other)?;
-    let x: u8 = words
-        .next()
-        .unwrap()
-        .parse()
-        .map_err(|_| io::ErrorKind::Other)?;
-    Ok((x, y))
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Everything which can go wrong when parsing a move.
+pub enum ParseMoveError {
+    /// We need exactly four coordinates.
+    WrongCount(usize),
+    /// A coordinate is not a number.
+    NotANumber(String),
+    /// A coordinate is outside the board.
+    OutOfBoard(u8),
+    /// Destination is neither at distance 1 nor at distance 2 from the start.
+    Distance(u8),
 }
 
-fn ask_move() -> Result<Movement, io::Error> {
-    println!("enter start point");
-    let (sx, sy) = ask_cell()?;
-    let start_position = Position::from_2d(sx, sy);
-    println!("enter end point");
-    let (ex, ey) = ask_cell()?;
-    let end_position = Position::from_2d(ex, ey);
-    match start_position.distance_to(end_position) {
-        1 => Ok(Movement::Duplicate(end_position)),
-        2 => Ok(Movement::Jump(start_position, end_position)),
-        _ => {
-            println!("invalid movement");
-            ask_move()
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseMoveError::WrongCount(count) => {
+                write!(f, "expected 4 coordinates but got {}", count)
+            }
+            ParseMoveError::NotANumber(word) => write!(f, "'{}' is not a coordinate", word),
+            ParseMoveError::OutOfBoard(coordinate) => {
+                write!(f, "coordinate {} is outside the board", coordinate)
+            }
+            ParseMoveError::Distance(distance) => write!(
+                f,
+                "destination is at distance {} (expected 1 or 2)",
+                distance
+            ),
         }
     }
 }
 
+impl Error for ParseMoveError {}
+
+/// Parse a move given as "from_x from_y to_x to_y".
+/// Destinations at distance 1 are duplications and at distance 2 jumps.
+/// Legality in a given configuration is not checked.
+pub fn parse_move(text: &str) -> Result<Movement, ParseMoveError> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.len() != 4 {
+        return Err(ParseMoveError::WrongCount(words.len()));
+    }
+    let mut coordinates = [0u8; 4];
+    for (coordinate, word) in coordinates.iter_mut().zip(&words) {
+        *coordinate = word
+            .parse()
+            .map_err(|_| ParseMoveError::NotANumber(word.to_string()))?;
+        if *coordinate >= 8 {
+            return Err(ParseMoveError::OutOfBoard(*coordinate));
+        }
+    }
+    let start = Position::from_2d(coordinates[0], coordinates[1]);
+    let end = Position::from_2d(coordinates[2], coordinates[3]);
+    match start.distance_to(end) {
+        1 => Ok(Movement::Duplicate(end)),
+        2 => Ok(Movement::Jump(start, end)),
+        distance => Err(ParseMoveError::Distance(distance)),
+    }
+}
+
 impl Strategy for Human {
     fn compute_next_move(&mut self, configuration: &Configuration) -> Option<Movement> {
-        if configuration.movements().next().is_some() {
-            loop {
-                if let Ok(movement) = ask_move() {
-                    if configuration.check_move(&movement) {
-                        return Some(movement);
-                    } else {
-                        println!("invalid movement (are you playing your color ?)");
-                    }
-                }
+        let movements: Vec<Movement> = configuration.movements().collect();
+        if movements.is_empty() {
+            return None;
+        }
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        loop {
+            print!("enter move (from_x from_y to_x to_y): ");
+            io::stdout().flush().ok()?;
+            let line = lines.next()?.ok()?;
+            match parse_move(&line) {
+                Ok(movement) if movements.contains(&movement) => return Some(movement),
+                Ok(_) => println!("illegal movement (are you playing your color ?)"),
+                Err(error) => println!("invalid movement: {}", error),
             }
-        } else {
-            None
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_parse() {
+        let start = Position::from_2d(1, 2);
+        assert_eq!(parse_move("1 2 2 3"), Ok(Movement::Duplicate(Position::from_2d(2, 3))));
+        assert_eq!(
+            parse_move("  1 2\t3 0 \n"),
+            Ok(Movement::Jump(start, Position::from_2d(3, 0)))
+        );
+    }
+
+    #[test]
+    fn malformed_moves_are_rejected() {
+        assert_eq!(parse_move(""), Err(ParseMoveError::WrongCount(0)));
+        assert_eq!(parse_move("1 2 3"), Err(ParseMoveError::WrongCount(3)));
+        assert_eq!(parse_move("1 2 3 4 5"), Err(ParseMoveError::WrongCount(5)));
+        assert_eq!(parse_move("1 2 a 3"), Err(ParseMoveError::NotANumber("a".to_string())));
+        assert_eq!(parse_move("1 -2 2 3"), Err(ParseMoveError::NotANumber("-2".to_string())));
+        assert_eq!(parse_move("1,2 2,3"), Err(ParseMoveError::WrongCount(2)));
+        assert_eq!(parse_move("1 2 8 3"), Err(ParseMoveError::OutOfBoard(8)));
+        assert_eq!(parse_move("1 2 300 3"), Err(ParseMoveError::NotANumber("300".to_string())));
+        assert_eq!(parse_move("1 2 1 2"), Err(ParseMoveError::Distance(0)));
+        assert_eq!(parse_move("0 0 3 0"), Err(ParseMoveError::Distance(3)));
+    }
+}
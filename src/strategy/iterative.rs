@@ -51,6 +51,7 @@ impl Strategy for IterativeDeepening {
                 panic!("failed to kill child")
             }
         }
+        child.wait().expect("failed to wait for child");
         movement.load()
     }
 }
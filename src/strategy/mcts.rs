@@ -0,0 +1,262 @@
+//! Monte Carlo Tree Search.
+use super::Strategy;
+use crate::configuration::{Configuration, Movement};
+use crate::zobrist::splitmix64;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Playouts stop after this number of plies : long random games are too noisy to tell good
+/// configurations from bad ones (and jumps can go on forever).
+const MAX_PLAYOUT_LENGTH: usize = 6;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// How much work `Mcts` does for each move.
+pub enum Budget {
+    /// Given number of simulations.
+    Iterations(u32),
+    /// As many simulations as possible during given time.
+    Time(Duration),
+}
+
+/// Monte Carlo Tree Search : grow a tree of configurations, going down along the most
+/// promising moves (UCT selection), play a short random game from each new node and back up
+/// by how many blobs it ended. The most visited move of the root is played.
+/// Random choices are drawn from a generator seeded with given value so games can be reproduced.
+pub struct Mcts {
+    budget: Budget,
+    exploration: f64,
+    seed: u64,
+}
+
+impl Mcts {
+    /// New search running given number of simulations per move.
+    pub fn iterations(iterations: u32) -> Self {
+        Mcts::new(Budget::Iterations(iterations))
+    }
+
+    /// New search running simulations during given time for each move.
+    pub fn time(duration: Duration) -> Self {
+        Mcts::new(Budget::Time(duration))
+    }
+
+    fn new(budget: Budget) -> Self {
+        Mcts {
+            budget,
+            exploration: std::f64::consts::SQRT_2,
+            seed: 0,
+        }
+    }
+
+    /// Weight of the exploration term in UCT (square root of 2 by default).
+    /// Higher values look at more moves, lower values search the best ones deeper.
+    pub fn exploration(self, exploration: f64) -> Self {
+        Mcts {
+            exploration,
+            ..self
+        }
+    }
+
+    /// Seed the random generator with given value (0 by default).
+    pub fn seed(self, seed: u64) -> Self {
+        Mcts { seed, ..self }
+    }
+
+    /// Random number below given bound.
+    fn random_below(&mut self, bound: usize) -> usize {
+        let (next_seed, value) = splitmix64(self.seed);
+        self.seed = next_seed;
+        ((value as u128 * bound as u128) >> 64) as usize
+    }
+
+    /// Play at random from given configuration and return how much red leads at the end, as a
+    /// fraction of all blobs (between -1 and 1).
+    fn playout(&mut self, configuration: &Configuration, movements: &mut Vec<Movement>) -> f64 {
+        let mut configuration = *configuration;
+        for _ in 0..MAX_PLAYOUT_LENGTH {
+            if configuration.is_terminal() {
+                break;
+            }
+            movements.clear();
+            movements.extend(configuration.movements());
+            configuration = if movements.is_empty() {
+                configuration.pass()
+            } else {
+                // the better of two random moves : purely random games waste too many blobs
+                // jumping around
+                let first = configuration.play(&movements[self.random_below(movements.len())]);
+                let second = configuration.play(&movements[self.random_below(movements.len())]);
+                std::cmp::max_by_key(first, second, |child| child.value())
+            };
+        }
+        let [red, blue] = configuration.blob_counts();
+        let total = (red + blue).max(1) as f64;
+        (red - blue) as f64 / total
+    }
+
+    /// Run the search and return the most visited move of the root.
+    fn search(&mut self, state: &Configuration, deadline: Option<Instant>) -> Option<Movement> {
+        let mut root_movements = state.movements();
+        let first = root_movements.next()?;
+        if root_movements.next().is_none() {
+            return Some(first);
+        }
+        let deadline = match self.budget {
+            Budget::Time(duration) => {
+                let end = Instant::now() + duration;
+                Some(deadline.map_or(end, |deadline| deadline.min(end)))
+            }
+            Budget::Iterations(_) => deadline,
+        };
+        let mut tree = vec![Node::new(*state, None, None)];
+        let mut buffer = Vec::new();
+        let mut path = Vec::new();
+        let mut iteration = 0;
+        loop {
+            if let Budget::Iterations(iterations) = self.budget {
+                if iteration >= iterations {
+                    break;
+                }
+            }
+            // always finish a first simulation so that some move gets visited
+            if iteration > 0 && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+            iteration += 1;
+
+            // selection : go down fully expanded nodes
+            path.clear();
+            let mut index = 0;
+            path.push(index);
+            while tree[index].untried.is_empty() && !tree[index].children.is_empty() {
+                index = tree[index].select(&tree, self.exploration);
+                path.push(index);
+            }
+            // expansion : add one child
+            if !tree[index].untried.is_empty() {
+                let untried = &mut tree[index].untried;
+                let movement = untried.swap_remove(self.random_below(untried.len()));
+                let configuration = tree[index].configuration;
+                let child = match movement {
+                    Some(movement) => configuration.play(&movement),
+                    None => configuration.pass(),
+                };
+                tree.push(Node::new(child, movement, Some(configuration.current_player)));
+                let child_index = tree.len() - 1;
+                tree[index].children.push(child_index);
+                index = child_index;
+                path.push(index);
+            }
+            // simulation
+            let configuration = tree[index].configuration;
+            let red_margin = self.playout(&configuration, &mut buffer);
+            // backpropagation : each node is scored for the player who moved into it,
+            // between 0 (lost everything) and 1 (won everything)
+            for &index in &path {
+                let node = &mut tree[index];
+                node.visits += 1;
+                let margin = if node.mover == Some(true) { -red_margin } else { red_margin };
+                node.score += (1.0 + margin) / 2.0;
+            }
+        }
+        // without any iteration (no budget) we just play the first legal move
+        tree[0]
+            .children
+            .iter()
+            .max_by_key(|&&child| tree[child].visits)
+            .map_or(Some(first), |&child| tree[child].movement)
+    }
+}
+
+/// A node of the search tree.
+struct Node<'a> {
+    configuration: Configuration<'a>,
+    /// Move leading here from the parent, None for the root and passes.
+    movement: Option<Movement>,
+    /// Player who played the move leading here, None for the root.
+    mover: Option<bool>,
+    /// Moves not expanded yet, None standing for a pass.
+    untried: Vec<Option<Movement>>,
+    children: Vec<usize>,
+    visits: u32,
+    /// Sum of the scores of all simulations for `mover`.
+    score: f64,
+}
+
+impl<'a> Node<'a> {
+    fn new(configuration: Configuration<'a>, movement: Option<Movement>, mover: Option<bool>) -> Self {
+        let untried = if configuration.is_terminal() {
+            Vec::new()
+        } else if configuration.must_pass() {
+            vec![None]
+        } else {
+            configuration.movements().map(Some).collect()
+        };
+        Node {
+            configuration,
+            movement,
+            mover,
+            untried,
+            children: Vec::new(),
+            visits: 0,
+            score: 0.0,
+        }
+    }
+
+    /// Child maximizing the UCT score : mean score plus a bonus for rarely visited children.
+    fn select(&self, tree: &[Node], exploration: f64) -> usize {
+        let log_visits = (self.visits as f64).ln();
+        let uct = |child: &Node| {
+            child.score / child.visits as f64
+                + exploration * (log_visits / child.visits as f64).sqrt()
+        };
+        *self
+            .children
+            .iter()
+            .max_by(|&&first, &&second| uct(&tree[first]).total_cmp(&uct(&tree[second])))
+            .unwrap()
+    }
+}
+
+impl Strategy for Mcts {
+    fn compute_next_move(&mut self, state: &Configuration) -> Option<Movement> {
+        self.search(state, None)
+    }
+
+    fn compute_next_move_until(
+        &mut self,
+        state: &Configuration,
+        deadline: Instant,
+    ) -> Option<Movement> {
+        self.search(state, Some(deadline))
+    }
+}
+
+impl fmt::Display for Mcts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.budget {
+            Budget::Iterations(iterations) => {
+                write!(f, "Monte Carlo Tree Search ({} iterations)", iterations)
+            }
+            Budget::Time(duration) => {
+                write!(f, "Monte Carlo Tree Search ({}ms)", duration.as_millis())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    #[test]
+    fn searches_without_iterations_play_the_first_move() {
+        let board = Board::default();
+        let start = Configuration::new(&board);
+        let movement = Mcts::iterations(0).compute_next_move(&start);
+        assert_eq!(movement, start.movements().next());
+        // some iterations find a legal move too
+        let movement = Mcts::iterations(50).compute_next_move(&start).unwrap();
+        assert!(start.is_legal(&movement));
+    }
+}
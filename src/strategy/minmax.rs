@@ -1,136 +1,309 @@
 //! Implementation of the min max algorithm.
-use super::Strategy;
+use super::alphabeta::{Parallelism, INFINITY};
+use super::{in_pool, BlobCount, Cancellation, Evaluator, Score, SearchStats, Strategy};
 use crate::configuration::{Configuration, Movement};
-use crate::shmem::AtomicMove;
+#[cfg(unix)]
+use crate::shmem::{AtomicMove, ShmemError};
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
+#[cfg(feature = "parallel")]
+use std::cmp::Reverse;
 use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::time::Instant;
 
 /// Min-Max algorithm with a given recursion depth.
-pub struct MinMax(pub u8);
-
-/// Min-Max algorithm
-fn minmax_iter(node: &Configuration, depth: u8, maximizing_player: bool) -> (Option<Movement>, i8) {
-    if maximizing_player == node.current_player {
-        return (None, -node.value());
-    } else {
-        return (None, node.value());
-    };
-    let mut best = (None, i8::MAX);
-    for child in node.movements() {
-        best = {
-            let mut new_val: i8 = best.1;
-            if node.current_player == maximizing_player {
-                new_val = std::cmp::max(
-                    best.1,
-                    minmax_iter(&node.play(&child), depth - 1, maximizing_player).1,
-                );
-            } else {
-                new_val = std::cmp::min(
-                    best.1,
-                    minmax_iter(&node.play(&child), depth - 1, maximizing_player).1,
-                );
-            };
-
-            if new_val != best.1 {
-                return (Some(child), new_val);
-            }
-            best
-        };
+/// Leaves are evaluated with given `Evaluator` (blob count by default).
+/// Every move of every node is searched, nothing is ever cut off : this is the reference the
+/// pruning of `AlphaBeta` is measured against.
+/// Parallel searches run in the global rayon pool unless a number of threads is given.
+pub struct MinMax<E = BlobCount> {
+    depth: u8,
+    evaluator: E,
+    parallelism: Parallelism,
+}
+
+impl MinMax {
+    /// New min-max searching at given depth, counting blobs on leaves.
+    pub fn new(depth: u8) -> Self {
+        MinMax {
+            depth,
+            evaluator: BlobCount,
+            parallelism: Parallelism::default(),
+        }
+    }
+}
+
+impl<E: Evaluator> MinMax<E> {
+    /// Evaluate leaves with given `Evaluator` instead.
+    pub fn evaluator<F: Evaluator>(self, evaluator: F) -> MinMax<F> {
+        MinMax {
+            depth: self.depth,
+            evaluator,
+            parallelism: self.parallelism,
+        }
+    }
+
+    /// Run the search in a dedicated pool of given number of threads
+    /// (1 gives a reproducible single threaded run).
+    pub fn threads(self, threads: usize) -> Self {
+        MinMax {
+            parallelism: Parallelism {
+                threads: Some(threads),
+                ..self.parallelism
+            },
+            ..self
+        }
+    }
+
+    /// Search nodes with at most given remaining depth sequentially instead of in parallel
+    /// (see `AlphaBeta::sequential_below`).
+    pub fn sequential_below(self, depth: u8) -> Self {
+        MinMax {
+            parallelism: Parallelism {
+                sequential_depth: depth,
+                ..self.parallelism
+            },
+            ..self
+        }
+    }
+}
+
+/// Everything a min-max search needs besides the node.
+struct Context<'c, E> {
+    root_depth: u8,
+    /// Nodes with at most this remaining depth are searched sequentially.
+    #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+    sequential_depth: u8,
+    evaluator: &'c E,
+    cancellation: &'c Cancellation,
+    nodes: AtomicU64,
+    max_depth_reached: AtomicU8,
+}
+
+impl<'c, E: Evaluator> Context<'c, E> {
+    /// Count the nodes of a subtree searched sequentially.
+    fn add(&self, stats: &SearchStats) {
+        self.nodes.fetch_add(stats.nodes, Ordering::Relaxed);
+        self.max_depth_reached.fetch_max(stats.max_depth_reached, Ordering::Relaxed);
+    }
+}
+
+/// Min-Max algorithm iterating directly on movements (without collecting them), in negamax
+/// form : values are seen from the player about to play in `node`.
+/// Nodes without moves are leaves, like the ones at depth 0.
+/// Amongst equally valued moves the first one in generation order is chosen.
+/// Once cancelled, nodes are not expanded anymore and evaluated as leaves.
+fn minmax_iter<E: Evaluator>(
+    node: &Configuration,
+    depth: u8,
+    context: &Context<E>,
+    stats: &mut SearchStats,
+) -> (Option<Movement>, Score) {
+    stats.nodes += 1;
+    stats.max_depth_reached = std::cmp::max(stats.max_depth_reached, context.root_depth - depth);
+    if depth == 0 || node.movements().next().is_none() || context.cancellation.expired() {
+        return (None, -context.evaluator.evaluate(node));
+    }
+    let mut best = (None, -INFINITY);
+    for movement in node.movements() {
+        let value = -minmax_iter(&node.play(&movement), depth - 1, context, stats).1;
+        if value > best.1 || best.0.is_none() {
+            best = (Some(movement), value);
+        }
     }
     best
 }
 
-/// Minimax algorithm using fonctional coding style
-fn minmax_fonc(node: &Configuration, depth: u8, maximizing_player: bool) -> (Option<Movement>, i8) {
-    if depth == 0 || node.movements().next().is_none() {
-        if maximizing_player == node.current_player {
-            return (None, -node.value());
-        } else {
-            return (None, node.value());
-        };
+#[cfg(feature = "parallel")]
+/// Parallelized version of `minmax_iter` : children are searched in parallel, down to the
+/// nodes searched sequentially.
+/// Like `minmax_iter`, amongst equally valued moves the first one in generation order is chosen.
+fn minmax_par<E: Evaluator>(
+    node: &Configuration,
+    depth: u8,
+    context: &Context<E>,
+) -> (Option<Movement>, Score) {
+    if depth <= context.sequential_depth {
+        let mut stats = SearchStats::default();
+        let result = minmax_iter(node, depth, context, &mut stats);
+        context.add(&stats);
+        return result;
     }
-    if node.current_player == maximizing_player {
-        let best = node
-            .movements()
-            .map(|child| {
-                (
-                    child,
-                    minmax_fonc(&node.play(&child), depth - 1, maximizing_player).1,
-                )
-            })
-            .max_by_key(|&(_, value)| value)
-            .unwrap();
-        return (Some(best.0), best.1);
-    } else {
-        let best = node
-            .movements()
-            .map(|child| {
-                (
-                    child,
-                    minmax_fonc(&node.play(&child), depth - 1, maximizing_player).1,
-                )
-            })
-            .min_by_key(|&(_, value)| value)
-            .unwrap();
-        return (Some(best.0), best.1);
+    context.add(&SearchStats {
+        nodes: 1,
+        max_depth_reached: context.root_depth - depth,
+        ..Default::default()
+    });
+    let movements: Vec<Movement> = node.movements().collect();
+    if movements.is_empty() || context.cancellation.expired() {
+        return (None, -context.evaluator.evaluate(node));
     }
+    movements
+        .into_par_iter()
+        .enumerate()
+        .map(|(index, movement)| {
+            (index, movement, -minmax_par(&node.play(&movement), depth - 1, context).1)
+        })
+        .max_by_key(|&(index, _, value)| (value, Reverse(index)))
+        .map(|(_, movement, value)| (Some(movement), value))
+        .unwrap()
 }
 
-/// Parallelized version of the minimax algorithm to get the best move
-fn minmax_par(node: &Configuration, depth: u8, maximizing_player: bool) -> (Option<Movement>, i8) {
-    if depth == 0 || node.movements().next().is_none() {
-        if maximizing_player == node.current_player {
-            return (None, -node.value());
-        } else {
-            return (None, node.value());
+impl<E: Evaluator> MinMax<E> {
+    fn search(
+        &self,
+        state: &Configuration,
+        cancellation: &Cancellation,
+        stats: Option<&mut SearchStats>,
+    ) -> (Option<Movement>, Score) {
+        let context = Context {
+            root_depth: self.depth,
+            sequential_depth: self.parallelism.sequential_depth,
+            evaluator: &self.evaluator,
+            cancellation,
+            nodes: AtomicU64::new(0),
+            max_depth_reached: AtomicU8::new(0),
         };
+        #[cfg(feature = "parallel")]
+        let search = || minmax_par(state, self.depth, &context);
+        #[cfg(not(feature = "parallel"))]
+        let search = || {
+            let mut stats = SearchStats::default();
+            let result = minmax_iter(state, self.depth, &context, &mut stats);
+            context.add(&stats);
+            result
+        };
+        let (movement, score) = in_pool(self.parallelism.threads, search);
+        if let Some(stats) = stats {
+            stats.nodes = context.nodes.into_inner();
+            stats.max_depth_reached = context.max_depth_reached.into_inner();
+        }
+        if movement.is_none() && !state.is_terminal() {
+            // deadline was hit before even looking at the root's children
+            if let Some(movement) = state.movements().next() {
+                return (Some(movement), self.evaluator.evaluate(&state.play(&movement)));
+            }
+        }
+        (movement, score)
+    }
+
+    /// Like `compute_next_move` but also return statistics about the search.
+    pub fn compute_next_move_with_stats(
+        &mut self,
+        state: &Configuration,
+    ) -> (Option<Movement>, SearchStats) {
+        let mut stats = SearchStats::default();
+        let start = Instant::now();
+        let (movement, _) = self.search(state, &Cancellation::never(), Some(&mut stats));
+        stats.elapsed = start.elapsed();
+        (movement, stats)
     }
-    let movements: Vec<Movement> = node.movements().collect();
-    if node.current_player == maximizing_player {
-        let (best_child, best_value) = movements
-            .into_par_iter()
-            .map(|child| {
-                (
-                    child,
-                    minmax_par(&node.play(&child), depth - 1, maximizing_player).1,
-                )
-            })
-            .max_by_key(|&(_, value)| value)
-            .unwrap();
-        return (Some(best_child), best_value);
-    }
-        let (best_child, best_value) = movements
-            .into_par_iter()
-            .map(|child| {
-                (
-                    child,
-                    minmax_par(&node.play(&child), depth - 1, maximizing_player).1,
-                )
-            })
-            .min_by_key(|&(_, value)| value)
-            .unwrap();
-        return (Some(best_child), best_value);
 }
 
-impl Strategy for MinMax {
+impl<E: Evaluator> Strategy for MinMax<E> {
     fn compute_next_move(&mut self, state: &Configuration) -> Option<Movement> {
-        minmax_par(state, self.0 - 1, state.current_player).0
+        self.search(state, &Cancellation::never(), None).0
+    }
+
+    fn compute_next_move_until(
+        &mut self,
+        state: &Configuration,
+        deadline: Instant,
+    ) -> Option<Movement> {
+        self.search(state, &Cancellation::at(deadline), None).0
+    }
+
+    fn compute_next_move_scored(&mut self, state: &Configuration) -> (Option<Movement>, Score) {
+        self.search(state, &Cancellation::never(), None)
     }
 }
 
-impl fmt::Display for MinMax {
+impl<E> fmt::Display for MinMax<E> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Min - Max (max level: {})", self.0)
+        write!(f, "Min - Max (max level: {})", self.depth)
     }
 }
 
+#[cfg(unix)]
 /// Anytime min max algorithm.
 /// Any time algorithms will compute until a deadline is hit and the process is killed.
 /// They are therefore run in another process and communicate through shared memory.
 /// This function is intended to be called from blobwar_iterative_deepening.
-pub fn min_max_anytime(state: &Configuration) {
-    let mut movement = AtomicMove::connect().expect("failed connecting to shmem");
+/// It fails if the shared memory was not created by the master process.
+pub fn min_max_anytime(state: &Configuration) -> Result<(), ShmemError> {
+    let mut movement = AtomicMove::connect()?;
     for depth in 1..100 {
-        movement.store(MinMax(depth).compute_next_move(state));
+        movement.store(MinMax::new(depth).compute_next_move(state))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::strategy::alphabeta::tests::minimax;
+    use crate::strategy::Search;
+    use std::cmp::Reverse;
+    use std::time::Duration;
+
+    #[test]
+    fn deadlines_stop_searches() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        let start = Instant::now();
+        let deadline = start + Duration::from_millis(50);
+        let movement = MinMax::new(30).compute_next_move_until(&config, deadline);
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(movement.is_some_and(|movement| config.is_legal(&movement)));
+    }
+
+    /// Min-Max algorithm using functional coding style, the reference of `minmax_iter`.
+    fn minmax_fonc(node: &Configuration, depth: u8) -> (Option<Movement>, Score) {
+        if depth == 0 || node.movements().next().is_none() {
+            return (None, -node.value() as Score);
+        }
+        node.movements()
+            .enumerate()
+            .map(|(index, movement)| {
+                (index, movement, -minmax_fonc(&node.play(&movement), depth - 1).1)
+            })
+            .min_by_key(|&(index, _, value)| (Reverse(value), index))
+            .map(|(_, movement, value)| (Some(movement), value))
+            .unwrap()
+    }
+
+    #[test]
+    fn minmax_iter_matches_minmax_fonc() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        let context = Context {
+            root_depth: 4,
+            sequential_depth: 0,
+            evaluator: &BlobCount,
+            cancellation: &Cancellation::never(),
+            nodes: AtomicU64::new(0),
+            max_depth_reached: AtomicU8::new(0),
+        };
+        let mut stats = SearchStats::default();
+        let expected = minmax_fonc(&config, 4);
+        assert_eq!(minmax_iter(&config, 4, &context, &mut stats), expected);
+        // nothing is cut off : every node of the tree is visited
+        let full_tree: u64 = (0..=4).map(|depth| crate::perft::perft(&config, depth)).sum();
+        assert_eq!(stats.nodes, full_tree);
+        assert_eq!(MinMax::new(4).compute_next_move_scored(&config), expected);
+        let (movement, stats) = MinMax::new(4).threads(2).compute_next_move_with_stats(&config);
+        assert_eq!(movement, expected.0);
+        assert_eq!(stats.nodes, full_tree);
+    }
+
+    #[test]
+    fn sequential_search_matches_full_minmax() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        let expected = minimax(&config, 4);
+        assert_eq!(Search::new().run(&config, 4).1, expected);
+        assert_eq!(MinMax::new(4).compute_next_move_scored(&config).1, expected);
     }
 }
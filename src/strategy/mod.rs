@@ -1,24 +1,236 @@
 //! We provide here structs for all possible kinds of players and AI.
 use crate::configuration::{Configuration, Movement};
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
 /// To be a strategy you need to be able to compute the next move.
 pub trait Strategy: fmt::Display {
     /// Take current `Configuration` and return what to do next.
     /// None if no move is possible.
     fn compute_next_move(&mut self, configuration: &Configuration) -> Option<Movement>;
+
+    /// Like `compute_next_move` but try to answer before given deadline.
+    /// Searches able to stop early return the best move found so far once the deadline is hit.
+    /// By default the deadline is ignored.
+    fn compute_next_move_until(
+        &mut self,
+        configuration: &Configuration,
+        deadline: Instant,
+    ) -> Option<Movement> {
+        let _ = deadline;
+        self.compute_next_move(configuration)
+    }
+
+    /// Like `compute_next_move` but also return how good the position is.
+    /// Scores are blob differences seen from the player about to play in given `Configuration`:
+    /// positive is good for them, negative good for their opponent.
+    /// By default we score the configuration obtained right after the chosen move
+    /// (whose `value` is seen from the player having played it, just like we want).
+    fn compute_next_move_scored(&mut self, configuration: &Configuration) -> (Option<Movement>, Score) {
+        let movement = self.compute_next_move(configuration);
+        let score = match movement {
+            Some(ref movement) => configuration.play(movement).value() as Score,
+            None => -(configuration.value() as Score),
+        };
+        (movement, score)
+    }
+}
+
+/// Boxed strategies (like the ones built by `parse_strategy`) are strategies too.
+impl<S: Strategy + ?Sized> Strategy for Box<S> {
+    fn compute_next_move(&mut self, configuration: &Configuration) -> Option<Movement> {
+        (**self).compute_next_move(configuration)
+    }
+
+    fn compute_next_move_until(
+        &mut self,
+        configuration: &Configuration,
+        deadline: Instant,
+    ) -> Option<Movement> {
+        (**self).compute_next_move_until(configuration, deadline)
+    }
+
+    fn compute_next_move_scored(&mut self, configuration: &Configuration) -> (Option<Movement>, Score) {
+        (**self).compute_next_move_scored(configuration)
+    }
 }
 
+/// Shared flag telling parallel searches when to stop expanding nodes.
+pub(crate) struct Cancellation {
+    deadline: Option<Instant>,
+    expired: AtomicBool,
+}
+
+impl Cancellation {
+    /// Searches will never be cancelled.
+    pub(crate) fn never() -> Self {
+        Cancellation {
+            deadline: None,
+            expired: AtomicBool::new(false),
+        }
+    }
+
+    /// Searches will be cancelled once given deadline is hit.
+    pub(crate) fn at(deadline: Instant) -> Self {
+        Cancellation {
+            deadline: Some(deadline),
+            expired: AtomicBool::new(false),
+        }
+    }
+
+    /// Should we stop now ?
+    pub(crate) fn expired(&self) -> bool {
+        if self.expired.load(Ordering::Relaxed) {
+            return true;
+        }
+        let expired = self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if expired {
+            self.expired.store(true, Ordering::Relaxed);
+        }
+        expired
+    }
+}
+
+/// Run given search in a dedicated rayon pool with given number of threads,
+/// or in the global pool if no count is given.
+#[cfg(feature = "parallel")]
+pub(crate) fn in_pool<T: Send>(threads: Option<usize>, search: impl FnOnce() -> T + Send) -> T {
+    match threads {
+        Some(threads) => rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("failed building thread pool")
+            .install(search),
+        None => search(),
+    }
+}
+
+/// Without the parallel feature searches are sequential and the count is ignored.
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn in_pool<T: Send>(_threads: Option<usize>, search: impl FnOnce() -> T + Send) -> T {
+    search()
+}
+
+/// Names of all strategies shipped in this build : network players need the serde feature
+/// and iterative deepening in a separate process is only available on unix.
+pub const STRATEGY_NAMES: &[&str] = &[
+    "human",
+    #[cfg(feature = "serde")]
+    "network",
+    "greedy",
+    "random",
+    "minmax",
+    "alphabeta",
+    "mcts",
+    "mtdf",
+    #[cfg(unix)]
+    "iterative",
+    "deepening",
+];
+
+pub mod factory;
+pub use self::factory::{parse_strategy, StrategyParseError};
 pub mod human;
-pub use self::human::Human;
+pub use self::human::{parse_move, Human, ParseMoveError};
+#[cfg(feature = "serde")]
 pub mod network;
+#[cfg(feature = "serde")]
 pub use self::network::NetworkPlayer;
+#[cfg(feature = "serde")]
+pub mod peer;
+#[cfg(feature = "serde")]
+pub use self::peer::{Broadcast, NetworkError, NetworkStrategy};
+pub mod evaluation;
+pub use self::evaluation::{
+    BlobCount, Evaluator, Mobility, ParseWeightsError, Score, WeightedEval, SCORE_MAX, SCORE_MIN,
+};
+#[cfg(feature = "nn")]
+pub mod nn;
+#[cfg(feature = "nn")]
+pub use self::nn::{input_planes, NnError, NnEval, NN_INPUTS, NN_SCALE};
 pub mod greedy;
 pub use self::greedy::Greedy;
+pub mod random;
+pub use self::random::Random;
 pub mod minmax;
-pub use self::minmax::{min_max_anytime, MinMax};
+#[cfg(unix)]
+pub use self::minmax::min_max_anytime;
+pub use self::minmax::MinMax;
 pub mod alphabeta;
-pub use self::alphabeta::{alpha_beta_anytime, AlphaBeta};
+#[cfg(unix)]
+pub use self::alphabeta::alpha_beta_anytime;
+pub use self::alphabeta::{
+    principal_variation, strength_curve, AlphaBeta, LateMoveReductions, Search, SearchStats,
+    SEQUENTIAL_DEPTH,
+};
+pub mod mcts;
+pub use self::mcts::{Budget, Mcts};
+pub mod mtdf;
+pub use self::mtdf::MtdF;
+pub mod book;
+pub use self::book::{format_entry, BookBuilder, BookError, Booked, MoveStats, OpeningBook};
+pub mod endgame;
+pub use self::endgame::{
+    endgame_outcome, solve_exact, Outcome, ENDGAME_EMPTY_CELLS, ENDGAME_NODES,
+};
+pub mod tablebase;
+pub use self::tablebase::{Tablebase, TablebaseError, MAX_TABLEBASE_CELLS};
+pub mod ordering;
+pub use self::ordering::{CenterOrderer, MoveOrderer};
+mod symmetry;
+pub mod ttable;
+pub use self::ttable::TranspositionTable;
+#[cfg(unix)]
 pub mod iterative;
+#[cfg(unix)]
 pub use self::iterative::IterativeDeepening;
+#[cfg(unix)]
 pub use self::iterative::IterativeStrategy;
+#[cfg(unix)]
+pub use crate::shmem::ShmemError;
+pub mod deepening;
+pub use self::deepening::{AspirationStats, InProcessDeepening};
+pub mod difficulty;
+pub use self::difficulty::Difficulty;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    #[test]
+    fn scores_are_seen_from_the_player_to_play() {
+        // the player to play clones in (1,1), converting three blobs : five blobs ahead
+        // whichever color it is
+        for fen in ["r1b5/2b5/1b6/8/8/8/8/6rb r", "b1r5/2r5/1r6/8/8/8/8/6br b"] {
+            let board = Board::from_fen(fen).unwrap();
+            let config = Configuration::new(&board);
+            let mut strategies: Vec<Box<dyn Strategy>> = vec![
+                Box::new(AlphaBeta::new(1)),
+                Box::new(MinMax::new(1)),
+                Box::new(Greedy::new(0)),
+            ];
+            for strategy in &mut strategies {
+                assert_eq!(strategy.compute_next_move_scored(&config).1, 5, "{}", strategy);
+            }
+            // the opponent answers by converting some of them back
+            assert_eq!(AlphaBeta::new(2).compute_next_move_scored(&config).1, 2);
+        }
+    }
+
+    #[test]
+    fn symmetric_starts_score_alike_for_both_players() {
+        let board = Board::default();
+        let red = Configuration::new(&board);
+        let blue = Configuration::new_with_player(&board, true);
+        for depth in 1..=4 {
+            let (_, value) = AlphaBeta::new(depth).compute_next_move_scored(&red);
+            // nobody is far ahead after a few moves in a symmetric start
+            assert!(value.abs() <= 1, "depth {}: {}", depth, value);
+            assert_eq!(AlphaBeta::new(depth).compute_next_move_scored(&blue).1, value);
+            assert_eq!(MinMax::new(depth).compute_next_move_scored(&red).1, value);
+            assert_eq!(MinMax::new(depth).compute_next_move_scored(&blue).1, value);
+        }
+    }
+}
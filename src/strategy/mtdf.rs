@@ -0,0 +1,88 @@
+//! MTD(f) : converge on the value with null window searches.
+use super::alphabeta::{Search, INFINITY};
+use super::ttable::TranspositionTable;
+use super::{Cancellation, Score, Strategy};
+use crate::configuration::{Configuration, Movement};
+use std::fmt;
+use std::time::Instant;
+
+/// MTD(f) with given maximum number of recursions.
+/// The value is bracketed by a sequence of null window alpha - beta searches, each one telling
+/// whether it lies above or below a guess. They share a transposition table so each one mostly
+/// re-uses the work of the previous ones. The first guess is the value found at the previous
+/// move.
+/// The table is kept from one move to the next (see `AlphaBeta::transposition_table`).
+pub struct MtdF {
+    depth: u8,
+    table: TranspositionTable,
+    guess: Score,
+}
+
+impl MtdF {
+    /// New MTD(f) searching at given depth, counting blobs on leaves.
+    pub fn new(depth: u8) -> Self {
+        MtdF {
+            depth,
+            table: TranspositionTable::new(),
+            guess: 0,
+        }
+    }
+
+    /// Return the best move and its value, or what was found when cancelled.
+    fn search(&mut self, state: &Configuration, cancellation: &Cancellation) -> (Option<Movement>, Score) {
+        let mut search = Search::with_table(&mut self.table)
+            .killer_moves()
+            .cancellation(cancellation);
+        let (mut lower, mut upper) = (-INFINITY, INFINITY);
+        let mut guess = self.guess;
+        while lower < upper && !cancellation.expired() {
+            let beta = std::cmp::max(guess, lower + 1);
+            search.set_window(beta - 1, beta);
+            guess = search.run(state, self.depth).1;
+            if guess < beta {
+                upper = guess;
+            } else {
+                lower = guess;
+            }
+        }
+        // a last search with a window around the value finds the same move as alpha - beta :
+        // the first generated one amongst the best ones
+        search.set_window(
+            std::cmp::max(guess.saturating_sub(1), -INFINITY),
+            guess.saturating_add(1),
+        );
+        let (movement, value) = search.run(state, self.depth);
+        self.guess = value;
+        if movement.is_none() && !state.is_terminal() {
+            // deadline was hit before even looking at the root's children
+            if let Some(movement) = state.movements().next() {
+                return (Some(movement), value);
+            }
+        }
+        (movement, value)
+    }
+}
+
+impl Strategy for MtdF {
+    fn compute_next_move(&mut self, state: &Configuration) -> Option<Movement> {
+        self.search(state, &Cancellation::never()).0
+    }
+
+    fn compute_next_move_until(
+        &mut self,
+        state: &Configuration,
+        deadline: Instant,
+    ) -> Option<Movement> {
+        self.search(state, &Cancellation::at(deadline)).0
+    }
+
+    fn compute_next_move_scored(&mut self, state: &Configuration) -> (Option<Movement>, Score) {
+        self.search(state, &Cancellation::never())
+    }
+}
+
+impl fmt::Display for MtdF {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MTD(f) (max level: {})", self.depth)
+    }
+}
@@ -0,0 +1,173 @@
+//! Neural network evaluation : a small perceptron reading the board as input planes.
+use super::{Evaluator, Score};
+use crate::configuration::Configuration;
+use crate::positions::Positions;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Number of inputs of the network : three planes of 64 cells (see `input_planes`).
+pub const NN_INPUTS: usize = 3 * 64;
+
+/// Network outputs are squashed between -1 and 1 and multiplied by this to get scores.
+pub const NN_SCALE: f32 = 1000.0;
+
+/// First word of weight files.
+const HEADER: &str = "blobwar-nn";
+
+#[derive(Debug)]
+/// Everything which can go wrong when loading an `NnEval`.
+pub enum NnError {
+    /// Reading the file failed.
+    Io(io::Error),
+    /// File does not start with "blobwar-nn" and the size of the hidden layer.
+    Header,
+    /// A weight is not a number.
+    Weight(String),
+    /// File does not contain the number of weights its hidden layer needs.
+    WeightCount {
+        /// How many weights the network needs.
+        expected: usize,
+        /// How many we found.
+        found: usize,
+    },
+}
+
+impl fmt::Display for NnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NnError::Io(error) => write!(f, "failed reading network: {}", error),
+            NnError::Header => write!(f, "expected '{} <hidden size>' header", HEADER),
+            NnError::Weight(found) => write!(f, "invalid weight '{}'", found),
+            NnError::WeightCount { expected, found } => {
+                write!(f, "expected {} weights but got {}", expected, found)
+            }
+        }
+    }
+}
+
+impl Error for NnError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NnError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NnError {
+    fn from(error: io::Error) -> Self {
+        NnError::Io(error)
+    }
+}
+
+/// Encode given configuration as the inputs of the network : 1 on cells holding a blob of
+/// the player who just moved (first plane), of the player about to play (second plane) and on
+/// holes (third plane, cells outside the board included), 0 elsewhere.
+/// Cell (x, y) of a plane is at index 8 * y + x.
+pub fn input_planes(config: &Configuration) -> [f32; NN_INPUTS] {
+    let mut inputs = [0.0; NN_INPUTS];
+    for (plane, positions) in planes(config).iter().enumerate() {
+        for position in positions.positions() {
+            inputs[64 * plane + position as usize] = 1.0;
+        }
+    }
+    inputs
+}
+
+/// Cells set in each input plane.
+fn planes(config: &Configuration) -> [Positions; 3] {
+    let blobs = config.blobs();
+    let mover = !config.current_player as usize;
+    [blobs[mover], blobs[1 - mover], config.board().holes]
+}
+
+/// Evaluation by a perceptron with one hidden layer of rectified linear units, whose weights
+/// are loaded from a file (see `NnEval::parse`).
+/// The output is squashed by a hyperbolic tangent : -1 is a sure loss for the player who just
+/// moved and 1 a sure win, scaled by `NN_SCALE`.
+pub struct NnEval {
+    /// Weights of the hidden layer, input by input (inputs are sparse).
+    hidden_weights: Vec<f32>,
+    hidden_biases: Vec<f32>,
+    output_weights: Vec<f32>,
+    output_bias: f32,
+}
+
+impl NnEval {
+    /// Load weights from given file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, NnError> {
+        NnEval::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse weights out of given text : "blobwar-nn", the number H of hidden units, then
+    /// whitespace separated numbers.
+    /// These are the H * `NN_INPUTS` weights of the hidden layer (unit by unit, each one
+    /// reading inputs in the order of `input_planes`), the H biases of the hidden layer, the H
+    /// weights of the output and its bias.
+    /// Lines starting with '#' are comments.
+    pub fn parse(text: &str) -> Result<Self, NnError> {
+        let mut words = text
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .flat_map(str::split_whitespace);
+        if words.next() != Some(HEADER) {
+            return Err(NnError::Header);
+        }
+        let hidden: usize = words
+            .next()
+            .and_then(|size| size.parse().ok())
+            .ok_or(NnError::Header)?;
+        let weights = words
+            .map(|word| word.parse::<f32>().map_err(|_| NnError::Weight(word.to_owned())))
+            .collect::<Result<Vec<f32>, NnError>>()?;
+        let expected = hidden * (NN_INPUTS + 2) + 1;
+        if weights.len() != expected {
+            return Err(NnError::WeightCount {
+                expected,
+                found: weights.len(),
+            });
+        }
+        let (unit_weights, rest) = weights.split_at(hidden * NN_INPUTS);
+        let (hidden_biases, rest) = rest.split_at(hidden);
+        let (output_weights, output_bias) = rest.split_at(hidden);
+        let mut hidden_weights = vec![0.0; hidden * NN_INPUTS];
+        for (unit, inputs) in unit_weights.chunks_exact(NN_INPUTS).enumerate() {
+            for (input, &weight) in inputs.iter().enumerate() {
+                hidden_weights[input * hidden + unit] = weight;
+            }
+        }
+        Ok(NnEval {
+            hidden_weights,
+            hidden_biases: hidden_biases.to_vec(),
+            output_weights: output_weights.to_vec(),
+            output_bias: output_bias[0],
+        })
+    }
+}
+
+impl Evaluator for NnEval {
+    fn evaluate(&self, config: &Configuration) -> Score {
+        let hidden = self.hidden_biases.len();
+        let mut units = self.hidden_biases.clone();
+        // only set inputs contribute
+        for (plane, positions) in planes(config).iter().enumerate() {
+            for position in positions.positions() {
+                let input = 64 * plane + position as usize;
+                let weights = &self.hidden_weights[input * hidden..(input + 1) * hidden];
+                for (unit, weight) in units.iter_mut().zip(weights) {
+                    *unit += weight;
+                }
+            }
+        }
+        let output = units
+            .iter()
+            .zip(&self.output_weights)
+            .fold(self.output_bias, |output, (unit, weight)| {
+                output + unit.max(0.0) * weight
+            });
+        (output.tanh() * NN_SCALE).round() as Score
+    }
+}
@@ -0,0 +1,170 @@
+//! Network play between two peers : each side runs its own game and they exchange movements.
+use super::Strategy;
+use crate::configuration::{Configuration, Movement};
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+#[derive(Debug)]
+/// Everything which can go wrong when receiving a movement.
+pub enum NetworkError {
+    /// Reading or writing on the connection failed.
+    Io(io::Error),
+    /// Peer closed the connection.
+    Closed,
+    /// Peer sent something which is not a movement.
+    Invalid(String),
+    /// Peer sent a movement which is not legal here.
+    Illegal(Movement),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::Io(error) => write!(f, "network failure: {}", error),
+            NetworkError::Closed => write!(f, "peer closed the connection"),
+            NetworkError::Invalid(message) => write!(f, "invalid message from peer: {}", message),
+            NetworkError::Illegal(movement) => write!(f, "illegal movement from peer: {}", movement),
+        }
+    }
+}
+
+impl Error for NetworkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NetworkError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NetworkError {
+    fn from(error: io::Error) -> Self {
+        NetworkError::Io(error)
+    }
+}
+
+/// The remote player, on the other side of a TCP connection.
+/// Movements are exchanged as JSON lines (`null` for passing).
+/// Local moves need to be sent out : wrap the local strategy with `NetworkStrategy::broadcast`.
+///
+/// Once something went wrong the remote player only passes and `error` tells why.
+pub struct NetworkStrategy {
+    reader: BufReader<TcpStream>,
+    name: String,
+    error: Option<NetworkError>,
+}
+
+impl NetworkStrategy {
+    /// Wait for a peer to connect on given address.
+    pub fn listen<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let (stream, _) = TcpListener::bind(address)?.accept()?;
+        NetworkStrategy::new(stream)
+    }
+
+    /// Connect to a listening peer.
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        NetworkStrategy::new(TcpStream::connect(address)?)
+    }
+
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        Ok(NetworkStrategy {
+            name: stream.peer_addr()?.to_string(),
+            reader: BufReader::new(stream),
+            error: None,
+        })
+    }
+
+    /// Wrap given local strategy so that all its moves are sent to the peer.
+    pub fn broadcast<S: Strategy>(&self, strategy: S) -> io::Result<Broadcast<S>> {
+        Ok(Broadcast {
+            strategy,
+            writer: self.reader.get_ref().try_clone()?,
+            error: None,
+        })
+    }
+
+    /// Wait for the peer's movement in given `Configuration`.
+    pub fn receive(&mut self, configuration: &Configuration) -> Result<Option<Movement>, NetworkError> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(NetworkError::Closed);
+        }
+        let movement: Option<Movement> = serde_json::from_str(&line)
+            .map_err(|error| NetworkError::Invalid(error.to_string()))?;
+        match movement {
+            Some(movement) if !configuration.check_move(&movement) => {
+                Err(NetworkError::Illegal(movement))
+            }
+            _ => Ok(movement),
+        }
+    }
+
+    /// What went wrong with the connection, if anything.
+    pub fn error(&self) -> Option<&NetworkError> {
+        self.error.as_ref()
+    }
+}
+
+impl fmt::Display for NetworkStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Peer : {}", self.name)
+    }
+}
+
+impl Strategy for NetworkStrategy {
+    fn compute_next_move(&mut self, configuration: &Configuration) -> Option<Movement> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.receive(configuration) {
+            Ok(movement) => movement,
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+}
+
+/// Local strategy whose moves are sent to the peer.
+pub struct Broadcast<S> {
+    strategy: S,
+    writer: TcpStream,
+    error: Option<io::Error>,
+}
+
+impl<S> Broadcast<S> {
+    /// Why sending moves failed, if it did. Moves are still played locally.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    fn send(&mut self, movement: Option<Movement>) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, &movement)?;
+        self.writer.write_all(b"\n")
+    }
+}
+
+impl<S: fmt::Display> fmt::Display for Broadcast<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.strategy)
+    }
+}
+
+impl<S: Strategy> Strategy for Broadcast<S> {
+    fn compute_next_move(&mut self, configuration: &Configuration) -> Option<Movement> {
+        let movement = self.strategy.compute_next_move(configuration);
+        if self.error.is_none() {
+            if let Err(error) = self.send(movement) {
+                self.error = Some(error);
+            }
+        }
+        movement
+    }
+}
@@ -0,0 +1,158 @@
+//! Symmetries of configurations, to avoid searching moves equivalent to already searched ones.
+use crate::board::outside;
+use crate::configuration::{Configuration, Movement};
+use crate::positions::{BoardPosition, Position, Positions};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Non trivial symmetries of a rectangle (the last four only exist for squares).
+enum Symmetry {
+    /// Left becomes right.
+    FlipHorizontally,
+    /// Top becomes bottom.
+    FlipVertically,
+    /// Half turn.
+    HalfTurn,
+    /// Reflection across the diagonal from top left to bottom right.
+    Transpose,
+    /// Reflection across the diagonal from top right to bottom left.
+    AntiTranspose,
+    /// Quarter turn clockwise.
+    QuarterTurn,
+    /// Quarter turn counter clockwise.
+    ReverseQuarterTurn,
+}
+
+const SYMMETRIES: [Symmetry; 7] = [
+    Symmetry::FlipHorizontally,
+    Symmetry::FlipVertically,
+    Symmetry::HalfTurn,
+    Symmetry::Transpose,
+    Symmetry::AntiTranspose,
+    Symmetry::QuarterTurn,
+    Symmetry::ReverseQuarterTurn,
+];
+
+impl Symmetry {
+    /// Does it map a board of given dimensions onto itself ?
+    fn fits(self, (width, height): (usize, usize)) -> bool {
+        match self {
+            Symmetry::FlipHorizontally | Symmetry::FlipVertically | Symmetry::HalfTurn => true,
+            _ => width == height,
+        }
+    }
+
+    /// Image of given cell, on a board of given dimensions.
+    fn position(self, position: Position, (width, height): (usize, usize)) -> Position {
+        let (x, y) = position.to_2d();
+        let (right, bottom) = (width as u8 - 1, height as u8 - 1);
+        let (x, y) = match self {
+            Symmetry::FlipHorizontally => (right - x, y),
+            Symmetry::FlipVertically => (x, bottom - y),
+            Symmetry::HalfTurn => (right - x, bottom - y),
+            Symmetry::Transpose => (y, x),
+            Symmetry::AntiTranspose => (right - y, bottom - x),
+            Symmetry::QuarterTurn => (right - y, x),
+            Symmetry::ReverseQuarterTurn => (y, bottom - x),
+        };
+        Position::from_2d(x, y)
+    }
+
+    /// Images of given cells, which must all be on a board of given dimensions.
+    fn positions(self, positions: Positions, dimensions: (usize, usize)) -> Positions {
+        let mut images = Positions::default();
+        for position in positions.positions() {
+            images.add(Positions::single(self.position(position, dimensions)));
+        }
+        images
+    }
+
+    /// Image of given movement.
+    fn movement(self, movement: &Movement, dimensions: (usize, usize)) -> Movement {
+        match *movement {
+            Movement::Duplicate(destination) => {
+                Movement::Duplicate(self.position(destination, dimensions))
+            }
+            Movement::Jump(source, destination) => Movement::Jump(
+                self.position(source, dimensions),
+                self.position(destination, dimensions),
+            ),
+        }
+    }
+}
+
+/// Symmetries leaving given configuration unchanged : holes and blobs of each player are
+/// mapped onto themselves.
+fn symmetries(configuration: &Configuration) -> Vec<Symmetry> {
+    let board = configuration.board();
+    let dimensions = board.dimensions();
+    // cells outside of the board are not moved around
+    let holes = board
+        .holes
+        .intersection_with(outside(dimensions.0, dimensions.1).invert());
+    let blobs = configuration.blobs();
+    SYMMETRIES
+        .iter()
+        .copied()
+        .filter(|symmetry| symmetry.fits(dimensions))
+        .filter(|symmetry| {
+            symmetry.positions(holes, dimensions) == holes
+                && blobs
+                    .iter()
+                    .all(|&blobs| symmetry.positions(blobs, dimensions) == blobs)
+        })
+        .collect()
+}
+
+/// Remove from given movements (in generation order) the ones equivalent by a symmetry of the
+/// configuration to an earlier one : only the first movement of each class is kept.
+/// Equivalent movements lead to configurations which are images of each other and therefore
+/// have the same value (for evaluators not caring about orientation).
+pub(crate) fn distinct_movements(configuration: &Configuration, movements: &mut Vec<(usize, Movement)>) {
+    let symmetries = symmetries(configuration);
+    if symmetries.is_empty() {
+        return;
+    }
+    let dimensions = configuration.board().dimensions();
+    let mut kept = Vec::new();
+    movements.retain(|(_, movement)| {
+        let equivalent = symmetries
+            .iter()
+            .any(|symmetry| kept.contains(&symmetry.movement(movement, dimensions)));
+        if !equivalent {
+            kept.push(*movement);
+        }
+        !equivalent
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::strategy::alphabeta::tests::minimax;
+    use crate::strategy::{AlphaBeta, Strategy};
+
+    #[test]
+    fn symmetric_roots_search_fewer_moves_for_the_same_score() {
+        let board = Board::default();
+        let start = Configuration::new(&board);
+        let mut movements: Vec<(usize, Movement)> = start.movements().enumerate().collect();
+        let all = movements.len();
+        distinct_movements(&start, &mut movements);
+        assert!(movements.len() < all, "{} out of {}", movements.len(), all);
+        for depth in 1..=3 {
+            let mut full = AlphaBeta::new(depth).threads(1);
+            let (full, full_stats) = full.compute_next_move_with_stats(&start);
+            let mut symmetric = AlphaBeta::new(depth).threads(1).symmetric();
+            let (movement, stats) = symmetric.compute_next_move_with_stats(&start);
+            assert!(stats.nodes < full_stats.nodes, "depth {}", depth);
+            // the chosen move is as good as the one of the full search
+            let score = |movement: Option<Movement>| {
+                -minimax(&start.play(&movement.unwrap()), depth - 1)
+            };
+            assert_eq!(score(movement), score(full), "depth {}", depth);
+            let scored = AlphaBeta::new(depth).symmetric().compute_next_move_scored(&start);
+            assert_eq!(scored.1, AlphaBeta::new(depth).compute_next_move_scored(&start).1);
+        }
+    }
+}
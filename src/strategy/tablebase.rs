@@ -0,0 +1,291 @@
+//! Tablebases : outcome of every configuration of a small board, by retrograde analysis.
+use super::endgame::Outcome;
+use crate::board::Board;
+use crate::configuration::{Configuration, Movement, ScoringRule};
+use crate::positions::{Position, Positions};
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Tablebases can only be built for boards with at most this number of cells
+/// (holes excluded) : each configuration is stored and there are 2 * 3^cells of them.
+pub const MAX_TABLEBASE_CELLS: usize = 14;
+
+/// First bytes of tablebase files.
+const MAGIC: &[u8; 4] = b"BWTB";
+
+/// Entries store an outcome in their two lowest bits...
+const UNKNOWN: u16 = 0;
+const WIN: u16 = 1;
+const DRAW: u16 = 2;
+const LOSS: u16 = 3;
+/// ... and in the others the pass of the analysis which found it.
+const PASS_SHIFT: u16 = 2;
+
+#[derive(Debug)]
+/// Everything which can go wrong when building, saving or loading a `Tablebase`.
+pub enum TablebaseError {
+    /// Board has too many cells (see `MAX_TABLEBASE_CELLS`).
+    TooManyCells(usize),
+    /// Reading or writing the file failed.
+    Io(io::Error),
+    /// File is not a tablebase, or a truncated one.
+    Format,
+}
+
+impl fmt::Display for TablebaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TablebaseError::TooManyCells(cells) => write!(
+                f,
+                "board has {} cells, tablebases handle at most {}",
+                cells, MAX_TABLEBASE_CELLS
+            ),
+            TablebaseError::Io(error) => write!(f, "failed accessing tablebase: {}", error),
+            TablebaseError::Format => write!(f, "not a valid tablebase file"),
+        }
+    }
+}
+
+impl Error for TablebaseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TablebaseError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TablebaseError {
+    fn from(error: io::Error) -> Self {
+        TablebaseError::Io(error)
+    }
+}
+
+/// Outcome, with perfect play, of every configuration of a board (whatever its blobs and
+/// player to play).
+/// Games which can go on forever (jumps do not fill the board) are draws, like threefold
+/// repetitions.
+/// Each winning configuration also knows how many passes of the analysis were needed to prove
+/// it, so that `Tablebase::best_move` makes progress towards the win.
+pub struct Tablebase {
+    board: Board,
+    rule: ScoringRule,
+    /// Cells of the board, in the order they are encoded in indices.
+    cells: Vec<Position>,
+    entries: Vec<u16>,
+}
+
+impl Tablebase {
+    /// Analyse all configurations of given board, deciding winners of finished games with
+    /// given rule.
+    /// We start from finished games and go back : a configuration is won if a move leads to
+    /// a lost one for the other player, lost if all moves lead to won ones. What is left once
+    /// nothing changes anymore is drawn.
+    pub fn generate(board: &Board, rule: ScoringRule) -> Result<Self, TablebaseError> {
+        let cells: Vec<Position> = board.holes.invert().positions().collect();
+        if cells.len() > MAX_TABLEBASE_CELLS {
+            return Err(TablebaseError::TooManyCells(cells.len()));
+        }
+        let mut tablebase = Tablebase {
+            board: board.clone(),
+            rule,
+            entries: vec![UNKNOWN; 2 * 3usize.pow(cells.len() as u32)],
+            cells,
+        };
+        for index in 0..tablebase.entries.len() {
+            let configuration = tablebase.configuration(index);
+            if configuration.is_terminal() {
+                let outcome = match configuration.winner(rule) {
+                    None => DRAW,
+                    Some(winner) if winner == configuration.current_player => WIN,
+                    Some(_) => LOSS,
+                };
+                tablebase.entries[index] = outcome;
+            }
+        }
+        // each pass only looks at outcomes found by the previous ones
+        let mut updates = Vec::new();
+        for pass in 1.. {
+            for index in 0..tablebase.entries.len() {
+                if tablebase.entries[index] == UNKNOWN {
+                    if let Some(outcome) = tablebase.deduce(index) {
+                        updates.push((index, outcome | pass << PASS_SHIFT));
+                    }
+                }
+            }
+            if updates.is_empty() {
+                break;
+            }
+            for (index, entry) in updates.drain(..) {
+                tablebase.entries[index] = entry;
+            }
+        }
+        for entry in &mut tablebase.entries {
+            if *entry == UNKNOWN {
+                *entry = DRAW;
+            }
+        }
+        Ok(tablebase)
+    }
+
+    /// Outcome of given configuration if the ones of its children are known enough.
+    fn deduce(&self, index: usize) -> Option<u16> {
+        let configuration = self.configuration(index);
+        let mut all_lost = true;
+        for child in children(&configuration) {
+            match self.entries[self.index(&child)] & 3 {
+                LOSS => return Some(WIN),
+                WIN => {}
+                _ => all_lost = false,
+            }
+        }
+        all_lost.then_some(LOSS)
+    }
+
+    /// Index of given configuration of our board.
+    fn index(&self, configuration: &Configuration) -> usize {
+        let blobs = configuration.blobs();
+        let index = self.cells.iter().rev().fold(0, |index, &cell| {
+            3 * index
+                + if blobs[0].contains(cell) {
+                    1
+                } else if blobs[1].contains(cell) {
+                    2
+                } else {
+                    0
+                }
+        });
+        2 * index + configuration.current_player as usize
+    }
+
+    /// Configuration of given index.
+    fn configuration(&self, index: usize) -> Configuration<'_> {
+        let mut blobs = [Positions::default(); 2];
+        let mut code = index / 2;
+        for &cell in &self.cells {
+            match code % 3 {
+                0 => {}
+                digit => blobs[digit - 1].add(Positions::single(cell)),
+            }
+            code /= 3;
+        }
+        Configuration::with_blobs(&self.board, blobs, index % 2 == 1)
+    }
+
+    /// Entry of given configuration, None if it is not on our board (starting blobs do not
+    /// matter).
+    fn entry(&self, configuration: &Configuration) -> Option<u16> {
+        let board = configuration.board();
+        (board.dimensions() == self.board.dimensions() && board.holes == self.board.holes)
+            .then(|| self.entries[self.index(configuration)])
+    }
+
+    /// Outcome of given configuration for the player about to play, None if it is not on the
+    /// board of the tablebase.
+    pub fn probe(&self, configuration: &Configuration) -> Option<Outcome> {
+        self.entry(configuration).map(|entry| match entry & 3 {
+            WIN => Outcome::Win,
+            LOSS => Outcome::Loss,
+            _ => Outcome::Draw,
+        })
+    }
+
+    /// Best move in given configuration : the fastest win, else a draw, else the slowest loss.
+    /// None if the player about to play has to pass, the game is over or the configuration is
+    /// not on the board of the tablebase.
+    pub fn best_move(&self, configuration: &Configuration) -> Option<Movement> {
+        self.entry(configuration)?;
+        configuration.movements().max_by_key(|movement| {
+            let child = self.entries[self.index(&configuration.play(movement))];
+            let pass = (child >> PASS_SHIFT) as i32;
+            // what the child is worth for us, then how fast
+            match child & 3 {
+                LOSS => (2, -pass),
+                WIN => (0, pass),
+                _ => (1, 0),
+            }
+        })
+    }
+
+    /// Write the tablebase to given file : `MAGIC`, the length of the board in the format of
+    /// `Board::parse` (4 bytes, little endian) and the board, the rule (one byte) and all
+    /// entries (2 bytes each, little endian).
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), TablebaseError> {
+        let board = self.board.to_text();
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&(board.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(board.as_bytes());
+        bytes.push(match self.rule {
+            ScoringRule::BlobDifference => 0,
+            ScoringRule::MostBlobsWins => 1,
+            ScoringRule::TerritoryControl => 2,
+        });
+        for entry in &self.entries {
+            bytes.extend_from_slice(&entry.to_le_bytes());
+        }
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    /// Read a tablebase written by `Tablebase::save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TablebaseError> {
+        let bytes = fs::read(path)?;
+        let start = MAGIC.len() + 4;
+        if bytes.len() < start || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(TablebaseError::Format);
+        }
+        let length = u32::from_le_bytes(bytes[MAGIC.len()..start].try_into().unwrap()) as usize;
+        let header = start + length + 1;
+        if bytes.len() < header {
+            return Err(TablebaseError::Format);
+        }
+        let board = std::str::from_utf8(&bytes[start..start + length])
+            .ok()
+            .and_then(|text| Board::parse(text).ok())
+            .ok_or(TablebaseError::Format)?;
+        let rule = match bytes[header - 1] {
+            0 => ScoringRule::BlobDifference,
+            1 => ScoringRule::MostBlobsWins,
+            2 => ScoringRule::TerritoryControl,
+            _ => return Err(TablebaseError::Format),
+        };
+        let cells: Vec<Position> = board.holes.invert().positions().collect();
+        if cells.len() > MAX_TABLEBASE_CELLS
+            || bytes.len() != header + 4 * 3usize.pow(cells.len() as u32)
+        {
+            return Err(TablebaseError::Format);
+        }
+        let entries = bytes[header..]
+            .chunks_exact(2)
+            .map(|entry| u16::from_le_bytes([entry[0], entry[1]]))
+            .collect();
+        Ok(Tablebase {
+            board,
+            rule,
+            cells,
+            entries,
+        })
+    }
+
+    /// Rule deciding winners of finished games.
+    pub fn rule(&self) -> ScoringRule {
+        self.rule
+    }
+}
+
+/// Configurations reachable in one move, or by passing if stuck.
+fn children<'a>(configuration: &Configuration<'a>) -> Vec<Configuration<'a>> {
+    let children: Vec<Configuration> = configuration
+        .movements()
+        .map(|movement| configuration.play(&movement))
+        .collect();
+    if children.is_empty() {
+        vec![configuration.pass()]
+    } else {
+        children
+    }
+}
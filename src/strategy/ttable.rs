@@ -0,0 +1,93 @@
+//! Transposition table : remember results of already searched configurations.
+use super::Score;
+use crate::configuration::{Configuration, Movement};
+use std::collections::HashMap;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// How a stored value relates to the real value of the configuration.
+pub enum Flag {
+    /// Stored value is the real value.
+    Exact,
+    /// Real value is at least the stored value (search was cut).
+    LowerBound,
+    /// Real value is at most the stored value (no move reached alpha).
+    UpperBound,
+}
+
+#[derive(Debug, Copy, Clone)]
+/// What we remember about a searched configuration.
+pub struct Entry {
+    /// Remaining depth the configuration was searched to.
+    pub depth: u8,
+    /// Value found (for the player about to play in the configuration).
+    pub value: Score,
+    /// Is `value` exact or just a bound ?
+    pub flag: Flag,
+    /// Best move found, if any.
+    pub movement: Option<Movement>,
+}
+
+/// Table of already searched `Configuration`s.
+/// Values are stored for the player about to play so a table can be shared between searches
+/// for both players.
+#[derive(Default)]
+pub struct TranspositionTable {
+    entries: HashMap<u64, Entry>,
+}
+
+fn key(configuration: &Configuration) -> u64 {
+    configuration.zobrist_hash()
+}
+
+impl TranspositionTable {
+    /// Create a new empty table.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Return what we know about given `Configuration`.
+    pub fn get(&self, configuration: &Configuration) -> Option<Entry> {
+        self.entries.get(&key(configuration)).copied()
+    }
+
+    /// Remember given `Entry` for given `Configuration`, replacing any previous one.
+    pub fn insert(&mut self, configuration: &Configuration, entry: Entry) {
+        self.entries.insert(key(configuration), entry);
+    }
+
+    /// How many configurations are stored ?
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Is the table empty ?
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forget everything.
+    pub fn clear(&mut self) {
+        self.entries.clear()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::strategy::Search;
+
+    #[test]
+    fn table_saves_nodes() {
+        let board = Board::default();
+        let config = Configuration::new(&board);
+        let mut plain = Search::new();
+        let (_, plain_value) = plain.run(&config, 6);
+        let mut table = TranspositionTable::new();
+        let mut cached = Search::with_table(&mut table);
+        let (_, cached_value) = cached.run(&config, 6);
+        assert_eq!(cached_value, plain_value);
+        assert!(cached.nodes < plain.nodes);
+        assert!(!table.is_empty());
+    }
+}
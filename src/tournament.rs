@@ -0,0 +1,257 @@
+//! Round-robin tournaments between strategies, played without any output.
+use super::board::Board;
+use super::configuration::{Configuration, ScoringRule};
+use super::stats::{play_measured, GameStats};
+use super::strategy::{Random, Strategy};
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+/// Results of one strategy over a whole tournament.
+pub struct Score {
+    /// Games won.
+    pub wins: usize,
+    /// Games lost.
+    pub losses: usize,
+    /// Games drawn (equal blob counts or threefold repetition).
+    pub draws: usize,
+}
+
+impl Score {
+    /// How many games were played.
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Score table of a tournament.
+pub struct TournamentResult {
+    /// Name of each strategy (as displayed).
+    pub names: Vec<String>,
+    /// Score of each strategy, in the same order.
+    pub scores: Vec<Score>,
+    /// `crosstable[i][j]` is the score of strategy i against strategy j alone
+    /// (empty on the diagonal).
+    pub crosstable: Vec<Vec<Score>>,
+    /// Total number of games played.
+    pub games: usize,
+    /// Statistics of each game, in the order they were played.
+    pub records: Vec<GameStats>,
+    /// Red and blue strategy (indices in `names`) of each game of `records`.
+    pub pairings: Vec<(usize, usize)>,
+}
+
+/// Play all pairs of given strategies against each other on the default board.
+/// For each round, each pair plays twice so that both strategies start once.
+/// Winners are decided with given `ScoringRule`.
+pub fn tournament(
+    strategies: Vec<Box<dyn Strategy>>,
+    rounds: usize,
+    rule: ScoringRule,
+) -> TournamentResult {
+    tournament_on(&Board::default(), strategies, rounds, rule)
+}
+
+/// Like `tournament` but on given board.
+pub fn tournament_on(
+    board: &Board,
+    mut strategies: Vec<Box<dyn Strategy>>,
+    rounds: usize,
+    rule: ScoringRule,
+) -> TournamentResult {
+    let mut crosstable = vec![vec![Score::default(); strategies.len()]; strategies.len()];
+    let mut records = Vec::new();
+    let mut pairings = Vec::new();
+    let mut games = 0;
+    for second in 1..strategies.len() {
+        let (before, after) = strategies.split_at_mut(second);
+        let second_strategy = &mut after[0];
+        for (first, first_strategy) in before.iter_mut().enumerate() {
+            for _ in 0..rounds {
+                for &(red, blue) in &[(first, second), (second, first)] {
+                    let game = Configuration::new(board);
+                    let stats = if red == first {
+                        play_measured(&game, first_strategy.as_mut(), second_strategy.as_mut(), rule)
+                    } else {
+                        play_measured(&game, second_strategy.as_mut(), first_strategy.as_mut(), rule)
+                    };
+                    match stats.winner {
+                        None => {
+                            crosstable[red][blue].draws += 1;
+                            crosstable[blue][red].draws += 1;
+                        }
+                        Some(false) => {
+                            crosstable[red][blue].wins += 1;
+                            crosstable[blue][red].losses += 1;
+                        }
+                        Some(true) => {
+                            crosstable[blue][red].wins += 1;
+                            crosstable[red][blue].losses += 1;
+                        }
+                    }
+                    records.push(stats);
+                    pairings.push((red, blue));
+                    games += 1;
+                }
+            }
+        }
+    }
+    let scores = crosstable
+        .iter()
+        .map(|row| {
+            row.iter().fold(Score::default(), |total, score| Score {
+                wins: total.wins + score.wins,
+                losses: total.losses + score.losses,
+                draws: total.draws + score.draws,
+            })
+        })
+        .collect();
+    TournamentResult {
+        names: strategies.iter().map(|strategy| strategy.to_string()).collect(),
+        scores,
+        crosstable,
+        games,
+        records,
+        pairings,
+    }
+}
+
+/// Play given number of games between strategies built by `first` and `second` (fresh ones
+/// for each game) on given board and return the results of the first one.
+/// Games go by pairs starting with the same two random moves, seeded with the index of the
+/// pair, the first strategy playing red in the first game of each pair and blue in the second.
+/// With the parallel feature games are played at the same time in the rayon pool, one game
+/// per task.
+pub fn run_matches<A, B, S, T>(
+    board: &Board,
+    first: A,
+    second: B,
+    games: usize,
+    rule: ScoringRule,
+) -> Score
+where
+    A: Fn() -> S + Sync,
+    B: Fn() -> T + Sync,
+    S: Strategy,
+    T: Strategy,
+{
+    play_matches(board, first, second, games, |pair| pair as u64, rule)
+}
+
+/// `run_matches` with openings of each pair seeded by `opening`.
+pub(crate) fn play_matches<A, B, S, T, O>(
+    board: &Board,
+    first: A,
+    second: B,
+    games: usize,
+    opening: O,
+    rule: ScoringRule,
+) -> Score
+where
+    A: Fn() -> S + Sync,
+    B: Fn() -> T + Sync,
+    S: Strategy,
+    T: Strategy,
+    O: Fn(usize) -> u64 + Sync,
+{
+    // did the first strategy win each game ?
+    let play = |game: usize| {
+        let mut random = Random(opening(game / 2));
+        let mut configuration = Configuration::new(board);
+        for _ in 0..2 {
+            configuration = match random.compute_next_move(&configuration) {
+                Some(movement) => configuration.play(&movement),
+                None => configuration.pass(),
+            };
+        }
+        let first_red = game & 1 == 0;
+        let (mut first, mut second) = (first(), second());
+        let outcome = if first_red {
+            configuration.play_game(&mut first, &mut second, rule, None, None)
+        } else {
+            configuration.play_game(&mut second, &mut first, rule, None, None)
+        };
+        outcome.winner.map(|winner| winner != first_red)
+    };
+    #[cfg(feature = "parallel")]
+    let winners: Vec<Option<bool>> = {
+        use rayon::prelude::*;
+        (0..games).into_par_iter().map(play).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let winners: Vec<Option<bool>> = (0..games).map(play).collect();
+    let mut score = Score::default();
+    for winner in winners {
+        match winner {
+            Some(true) => score.wins += 1,
+            Some(false) => score.losses += 1,
+            None => score.draws += 1,
+        }
+    }
+    score
+}
+
+/// Scores are displayed as "wins-losses-draws".
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let score = format!("{}-{}-{}", self.wins, self.losses, self.draws);
+        f.pad(&score)
+    }
+}
+
+/// Display the crosstable : one line per strategy with its scores against each other
+/// strategy (columns are numbered like lines) and its total score.
+impl fmt::Display for TournamentResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.names.iter().map(|name| name.len()).max().unwrap_or(0);
+        write!(f, "{:>3} {:width$}", "", "", width = width)?;
+        for column in 1..=self.names.len() {
+            write!(f, " {:>9}", column)?;
+        }
+        writeln!(f, " {:>9}", "total")?;
+        for (line, (name, row)) in self.names.iter().zip(&self.crosstable).enumerate() {
+            write!(f, "{:>3} {:width$}", line + 1, name, width = width)?;
+            for (column, score) in row.iter().enumerate() {
+                if column == line {
+                    write!(f, " {:>9}", "-")?;
+                } else {
+                    write!(f, " {:>9}", score)?;
+                }
+            }
+            writeln!(f, " {:>9}", self.scores[line])?;
+        }
+        write!(f, "{} games", self.games)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::Greedy;
+
+    #[test]
+    fn totals_add_up() {
+        for (strategies, rounds, games) in [
+            (vec![Box::new(Greedy::new(0)) as Box<dyn Strategy>, Box::new(Random(1))], 2, 4),
+            (vec![Box::new(Greedy::new(0)), Box::new(Random(1)), Box::new(Random(2))], 1, 6),
+        ] {
+            let count = strategies.len();
+            let result = tournament(strategies, rounds, ScoringRule::BlobDifference);
+            assert_eq!(result.games, games);
+            assert_eq!(result.records.len(), games);
+            assert_eq!(result.names.len(), count);
+            let played: usize = result.scores.iter().map(Score::games).sum();
+            assert_eq!(played, 2 * games);
+            let wins: usize = result.scores.iter().map(|score| score.wins).sum();
+            let losses: usize = result.scores.iter().map(|score| score.losses).sum();
+            assert_eq!(wins, losses);
+            for (first, row) in result.crosstable.iter().enumerate() {
+                assert_eq!(row[first], Score::default());
+                for (second, score) in row.iter().enumerate().filter(|&(second, _)| second != first) {
+                    assert_eq!(score.games(), 2 * rounds);
+                    assert_eq!(score.wins, result.crosstable[second][first].losses);
+                }
+            }
+        }
+    }
+}
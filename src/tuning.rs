@@ -0,0 +1,162 @@
+//! Tuning of `WeightedEval` weights by simultaneous perturbation stochastic approximation
+//! (SPSA) : two close parameterizations play each other and weights move towards the winner.
+use super::board::Board;
+use super::configuration::ScoringRule;
+use super::strategy::{AlphaBeta, Score as Weight, WeightedEval};
+use super::tournament::{play_matches, Score};
+use super::zobrist::splitmix64;
+
+/// Weights of `WeightedEval` are tuned, except the material one which sets the scale.
+const TUNED: std::ops::Range<usize> = 1..WeightedEval::WEIGHTS;
+
+/// Result of one iteration of the tuner.
+pub struct Iteration {
+    /// Index of the iteration (0 for the first one).
+    pub index: usize,
+    /// Weights shifted in the direction of the perturbation.
+    pub plus: WeightedEval,
+    /// Weights shifted in the opposite direction.
+    pub minus: WeightedEval,
+    /// Results of `plus` against `minus`.
+    pub score: Score,
+    /// Weights after the iteration.
+    pub weights: WeightedEval,
+}
+
+/// SPSA tuner of `WeightedEval` weights.
+/// Each iteration shifts all weights at once by plus or minus a perturbation (sign chosen at
+/// random for each weight), lets both shifted parameterizations play a match of alpha - beta
+/// searches and moves the weights towards the better one, proportionally to its margin.
+/// Steps and perturbations slowly decrease so that weights settle.
+/// Games of a match start with two random moves, each opening being played with both colors.
+/// With the parallel feature the games of a match are played at the same time.
+pub struct Spsa {
+    /// Current weights, kept fractional between iterations.
+    weights: [f64; WeightedEval::WEIGHTS],
+    depth: u8,
+    games: usize,
+    perturbation: f64,
+    step: f64,
+    rule: ScoringRule,
+    seed: u64,
+    iteration: usize,
+}
+
+impl Spsa {
+    /// New tuner starting from given weights.
+    pub fn new(start: WeightedEval) -> Self {
+        let mut weights = [0.0; WeightedEval::WEIGHTS];
+        for (weight, &start) in weights.iter_mut().zip(&start.weights()) {
+            *weight = start as f64;
+        }
+        Spsa {
+            weights,
+            depth: 2,
+            games: 8,
+            perturbation: 2.0,
+            step: 4.0,
+            rule: ScoringRule::default(),
+            seed: 0,
+            iteration: 0,
+        }
+    }
+
+    /// Depth of the searches playing the matches (2 by default).
+    pub fn depth(self, depth: u8) -> Self {
+        Spsa { depth, ..self }
+    }
+
+    /// Number of games of each match, rounded up to an even number (8 by default).
+    pub fn games(self, games: usize) -> Self {
+        Spsa {
+            games: games + games % 2,
+            ..self
+        }
+    }
+
+    /// Initial shift of the weights of both parameterizations (2 by default).
+    pub fn perturbation(self, perturbation: f64) -> Self {
+        Spsa {
+            perturbation,
+            ..self
+        }
+    }
+
+    /// Initial move of the weights when winning every game of a match (4 by default).
+    pub fn step(self, step: f64) -> Self {
+        Spsa { step, ..self }
+    }
+
+    /// Decide winners of games with given rule.
+    pub fn rule(self, rule: ScoringRule) -> Self {
+        Spsa { rule, ..self }
+    }
+
+    /// Seed the random generator (perturbations and openings) with given value (0 by default).
+    pub fn seed(self, seed: u64) -> Self {
+        Spsa { seed, ..self }
+    }
+
+    /// Current weights, rounded.
+    pub fn weights(&self) -> WeightedEval {
+        WeightedEval::from_weights(round(self.weights))
+    }
+
+    /// Random number.
+    fn random(&mut self) -> u64 {
+        let (next_seed, value) = splitmix64(self.seed);
+        self.seed = next_seed;
+        value
+    }
+
+    /// Play one match on given board and update the weights.
+    pub fn iterate(&mut self, board: &Board) -> Iteration {
+        // usual SPSA gain sequences
+        let index = self.iteration;
+        let perturbation = self.perturbation / ((index + 1) as f64).powf(0.101);
+        let step = self.step / ((index + 1) as f64).powf(0.602);
+        let mut signs = [0.0; WeightedEval::WEIGHTS];
+        for sign in &mut signs[TUNED] {
+            *sign = if self.random() & 1 == 0 { 1.0 } else { -1.0 };
+        }
+        let shifted = |direction: f64| {
+            let mut weights = self.weights;
+            for (weight, sign) in weights.iter_mut().zip(&signs) {
+                *weight += direction * perturbation * sign;
+            }
+            WeightedEval::from_weights(round(weights))
+        };
+        let (plus, minus) = (shifted(1.0), shifted(-1.0));
+        let openings: Vec<u64> = (0..self.games / 2).map(|_| self.random()).collect();
+        let depth = self.depth;
+        let score = play_matches(
+            board,
+            || AlphaBeta::new(depth).evaluator(plus),
+            || AlphaBeta::new(depth).evaluator(minus),
+            self.games,
+            |pair| openings[pair],
+            self.rule,
+        );
+        let margin = (score.wins as f64 - score.losses as f64) / score.games().max(1) as f64;
+        for (weight, sign) in self.weights.iter_mut().zip(&signs) {
+            *weight += step * margin * sign;
+        }
+        self.iteration += 1;
+        Iteration {
+            index,
+            plus,
+            minus,
+            score,
+            weights: self.weights(),
+        }
+    }
+}
+
+/// Round fractional weights.
+fn round(weights: [f64; WeightedEval::WEIGHTS]) -> [Weight; WeightedEval::WEIGHTS] {
+    let mut rounded = [0; WeightedEval::WEIGHTS];
+    for (rounded, weight) in rounded.iter_mut().zip(&weights) {
+        *rounded = weight.round() as Weight;
+    }
+    rounded
+}
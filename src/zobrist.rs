@@ -0,0 +1,44 @@
+//! Zobrist hashing : each (player, cell) pair gets a random key and a set of blobs is hashed
+//! by xoring the keys of all its cells.
+//! Adding or removing blobs is then just xoring their keys again.
+use super::positions::Positions;
+
+/// Step of the splitmix64 generator : return next state and generated value.
+/// Also used by strategies needing reproducible randomness.
+pub(crate) const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+const fn generate_keys() -> [[u64; 64]; 2] {
+    let mut keys = [[0; 64]; 2];
+    let mut state = 0x0062_6c6f_6277_6172; // "blobwar"
+    let mut player = 0;
+    while player < 2 {
+        let mut cell = 0;
+        while cell < 64 {
+            let (next_state, key) = splitmix64(state);
+            state = next_state;
+            keys[player][cell] = key;
+            cell += 1;
+        }
+        player += 1;
+    }
+    keys
+}
+
+/// Key of each cell for each player, fixed at compile time.
+static KEYS: [[u64; 64]; 2] = generate_keys();
+
+/// Key xored in when the second player is about to play.
+pub(crate) const SIDE_KEY: u64 = splitmix64(0x7369_6465).1;
+
+/// Hash of the blobs of given player located on given `Positions`.
+pub(crate) fn hash_blobs(player: usize, positions: Positions) -> u64 {
+    positions
+        .positions()
+        .fold(0, |hash, position| hash ^ KEYS[player][position as usize])
+}
@@ -0,0 +1,29 @@
+//! Smoke test of the benchmark binary.
+use assert_cmd::Command;
+
+#[test]
+fn shallow_benchmarks_count_nodes() {
+    let output = Command::cargo_bin("bench")
+        .unwrap()
+        .args(["--depth", "2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    // a header and one line per strategy
+    assert_eq!(lines.len(), 4, "{}", stdout);
+    let nodes: Vec<u64> = lines[1..]
+        .iter()
+        .map(|line| {
+            // strategy names have spaces : nodes come third from the end
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            columns[columns.len() - 3].parse().unwrap()
+        })
+        .collect();
+    assert!(nodes.iter().all(|&nodes| nodes > 0), "{}", stdout);
+    // min-max searches the whole tree, alpha - beta prunes it
+    assert!(lines[1].starts_with("Min - Max"), "{}", stdout);
+    assert_eq!(nodes[0], 1 + 16 + 16 * 16);
+    assert!(nodes[1] < nodes[0], "{}", stdout);
+}
@@ -0,0 +1,30 @@
+//! Batch runs of the command line game.
+use assert_cmd::Command;
+
+#[test]
+fn quiet_games_print_the_winner() {
+    let output = Command::cargo_bin("blobwar")
+        .unwrap()
+        .args(["--p0", "greedy", "--p1", "greedy:1", "--quiet"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    // the looking ahead player wipes the other one out
+    assert_eq!(lines.len(), 2, "{}", stdout);
+    assert_eq!(
+        lines[0],
+        "BLUE (Greedy (lookahead: 1)) wins over RED (Greedy (lookahead: 0))!"
+    );
+    assert_eq!(lines[1], "red: 0 blobs, blue: 20 blobs");
+}
+
+#[test]
+fn bad_arguments_fail() {
+    Command::cargo_bin("blobwar")
+        .unwrap()
+        .args(["--p0", "nobody", "--quiet"])
+        .assert()
+        .failure();
+}
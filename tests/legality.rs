@@ -0,0 +1,67 @@
+//! `Configuration::is_legal` agrees with move generation on random positions.
+use blobwar::board::Board;
+use blobwar::configuration::{Configuration, Movement};
+// proptest has its own `Strategy`
+use blobwar::strategy::{Random, Strategy as _};
+use proptest::prelude::*;
+
+fn boards() -> Vec<Board> {
+    ["standard", "cross", "island", "handicap", "x"]
+        .iter()
+        .map(|name| Board::load(format!("{}/boards/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap())
+        .chain(vec![Board::empty(5, 4)])
+        .collect()
+}
+
+/// Configuration reached after given number of random plies on given board.
+fn random_position(board: &Board, seed: u64, plies: usize) -> Configuration<'_> {
+    let mut random = Random(seed);
+    let mut config = Configuration::new(board);
+    for _ in 0..plies {
+        if config.is_terminal() {
+            break;
+        }
+        config = match random.compute_next_move(&config) {
+            Some(movement) => config.play(&movement),
+            None => config.pass(),
+        };
+    }
+    config
+}
+
+/// Any movement, with cells possibly off the board.
+fn any_movement() -> impl Strategy<Value = Movement> {
+    prop_oneof![
+        any::<u8>().prop_map(Movement::Duplicate),
+        (any::<u8>(), any::<u8>())
+            .prop_map(|(source, destination)| Movement::Jump(source, destination)),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn legal_moves_are_generated_ones(
+        board in 0..6usize,
+        seed in any::<u64>(),
+        plies in 0..80usize,
+        outsiders in proptest::collection::vec(any_movement(), 16),
+    ) {
+        let boards = boards();
+        let config = random_position(&boards[board], seed, plies);
+        let generated: Vec<Movement> = config.movements().collect();
+        let cells = 0..64u8;
+        let clones = cells.clone().map(Movement::Duplicate);
+        let jumps = cells.clone().flat_map(|source| {
+            cells.clone().map(move |destination| Movement::Jump(source, destination))
+        });
+        for movement in clones.chain(jumps).chain(outsiders) {
+            prop_assert_eq!(
+                config.is_legal(&movement),
+                generated.contains(&movement),
+                "{} in {}",
+                movement,
+                config
+            );
+        }
+    }
+}
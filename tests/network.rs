@@ -0,0 +1,112 @@
+//! Two peers playing over a loopback TCP connection.
+#![cfg(feature = "serde")]
+use blobwar::board::Board;
+use blobwar::configuration::{Configuration, Movement, ScoringRule};
+use blobwar::strategy::{NetworkError, NetworkStrategy, Strategy};
+use std::fmt;
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+/// Play given moves in turn, then pass.
+struct Scripted(Vec<Movement>);
+
+impl fmt::Display for Scripted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Scripted")
+    }
+}
+
+impl Strategy for Scripted {
+    fn compute_next_move(&mut self, configuration: &Configuration) -> Option<Movement> {
+        let movement = (!self.0.is_empty()).then(|| self.0.remove(0));
+        assert!(movement.is_none_or(|movement| configuration.check_move(&movement)));
+        movement
+    }
+}
+
+fn moves(notation: &[&str]) -> Vec<Movement> {
+    notation.iter().map(|text| text.parse().unwrap()).collect()
+}
+
+/// Address of a currently free loopback port.
+fn free_address() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+/// Connect to given address, waiting for the peer to listen.
+fn connect(address: &str) -> NetworkStrategy {
+    for _ in 0..100 {
+        if let Ok(peer) = NetworkStrategy::connect(address) {
+            return peer;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    panic!("nobody listening on {}", address)
+}
+
+/// Play a game with the local player using given script and the remote one on the other
+/// side of the connection. Return all moves played and the final configuration.
+fn play(peer: NetworkStrategy, local_is_red: bool, script: Vec<Movement>) -> (Vec<Movement>, String) {
+    let board = Board::default();
+    let configuration = Configuration::new(&board);
+    let mut local = peer.broadcast(Scripted(script)).unwrap();
+    let mut remote = peer;
+    let mut played = Vec::new();
+    let mut last = configuration.to_fen();
+    let mut observer = |configuration: &Configuration, movement: &Movement| {
+        played.push(*movement);
+        last = configuration.to_fen();
+    };
+    let rule = ScoringRule::BlobDifference;
+    let outcome = if local_is_red {
+        configuration.play_game(&mut local, &mut remote, rule, None, Some(&mut observer))
+    } else {
+        configuration.play_game(&mut remote, &mut local, rule, None, Some(&mut observer))
+    };
+    // once both scripts are over both players pass until the configuration repeats
+    assert!(outcome.repetition);
+    assert!(remote.error().is_none());
+    assert!(local.error().is_none());
+    (played, last)
+}
+
+#[test]
+fn scripted_moves_go_through() {
+    let address = free_address();
+    let red_script = moves(&["1 1", "7 7 5 5"]);
+    let blue_script = moves(&["6 1", "0 7 2 5"]);
+    let listening = {
+        let address = address.clone();
+        let script = red_script.clone();
+        thread::spawn(move || play(NetworkStrategy::listen(address).unwrap(), true, script))
+    };
+    let (blue_played, blue_end) = play(connect(&address), false, blue_script.clone());
+    let (red_played, red_end) = listening.join().unwrap();
+    let expected: Vec<Movement> = red_script
+        .iter()
+        .zip(&blue_script)
+        .flat_map(|(&red, &blue)| vec![red, blue])
+        .collect();
+    assert_eq!(red_played, expected);
+    assert_eq!(blue_played, expected);
+    assert_eq!(red_end, blue_end);
+}
+
+#[test]
+fn dropped_connections_are_errors() {
+    let address = free_address();
+    let listening = {
+        let address = address.clone();
+        thread::spawn(move || NetworkStrategy::listen(address).unwrap())
+    };
+    let mut peer = connect(&address);
+    drop(listening.join().unwrap());
+    let board = Board::default();
+    let configuration = Configuration::new(&board);
+    assert!(matches!(peer.receive(&configuration), Err(NetworkError::Closed)));
+    // as a strategy it passes once the connection is lost
+    assert_eq!(peer.compute_next_move(&configuration), None);
+    assert!(peer.error().is_some());
+}